@@ -0,0 +1,50 @@
+//! The `axioms` subcommand, which lists the axioms that each justified
+//! statement relies on.
+
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::{
+    diagnostic::Diagnostic,
+    output::Output,
+    pipeline::{self, Stage},
+    CommandArgs,
+};
+
+/// Audits the root given in the arguments, returning the exit code the process
+/// should use. The code must justify successfully before it can be audited.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let root = match CommandArgs::parse(args, &[], 1) {
+        Ok(args) => args.path(0),
+        Err(code) => return code,
+    };
+    let mut analysis = pipeline::analyze(Path::new(&root));
+    for diagnostic in &analysis.diagnostics {
+        output.report(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    let root = match (analysis.stage, &analysis.root) {
+        (Stage::Justified, Some(root)) => root.ptr_clone(),
+        _ => return 1,
+    };
+    for audit in analysis.env.audit_axioms(&root) {
+        let text = if audit.axioms.len() == 0 {
+            format!("Relies on no axioms:")
+        } else {
+            format!("Relies on these axioms: {}", audit.axioms.iter().join(", "))
+        };
+        let mut diagnostic = Diagnostic::new().with_text_info(text).with_item_info(
+            &audit.item,
+            &root,
+            &mut analysis.env,
+        );
+        if audit.auto_theorems.len() > 0 {
+            diagnostic = diagnostic.with_text_info(format!("And these auto theorems:"));
+            for theorem in &audit.auto_theorems {
+                diagnostic = diagnostic.with_item_info(theorem, &root, &mut analysis.env);
+            }
+        }
+        output.report(&diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    0
+}
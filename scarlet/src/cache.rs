@@ -0,0 +1,472 @@
+//! Remembers what was worked out about the source code between runs, so that
+//! code which hasn't changed doesn't have to be worked through again. Each
+//! root gets its own file in a folder next to the roots, holding an entry for
+//! each of its files with three parts:
+//!
+//! - What each identifier and import in the file was resolved to. These are
+//!   only keyed by a hash of the file and of every root its root imports from,
+//!   because each of them is checked by resolving the item again once
+//!   everything else is linked up. Links that come out differently are undone
+//!   and left for resolve_all.
+//! - The invariant set of each item in the file. Sets are worked out from the
+//!   items their item refers to, so they are keyed like the certificates below,
+//!   and only used if everything they refer to can still be found.
+//! - A certificate for the invariant sets of the file, keyed by a hash of the
+//!   file, of every file its items refer to directly or through other items,
+//!   and of every root its root imports from. Each step in it is checked again
+//!   before it is used.
+//!
+//! Items only exist in memory, so they are stored as where they were written,
+//! see items.rs. Changes that a key doesn't capture, like a new theorem in a
+//! file that nothing refers to, can only make a link or a step fail its check
+//! and be worked out again, never let a wrong one through.
+
+mod items;
+mod tests;
+
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use self::items::{CachedInvariants, Decoder, Encoder, Locator, Locators};
+use crate::{
+    environment::Environment,
+    file_tree::FileNode,
+    item::{
+        definitions::other::DOther,
+        invariants::{InvariantSetPtr, Step},
+        resolvable::{DResolvable, ResolveResult},
+        ItemDefinition, ItemPtr,
+    },
+    modules::Sources,
+    util::fnv1a,
+};
+
+pub const CACHE_FOLDER: &str = ".scarlet-cache";
+
+/// What is stored for a single root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedRoot {
+    pub files: Vec<CachedFile>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedFile {
+    pub path: String,
+    /// The key the links are stored with.
+    pub resolution_key: String,
+    /// The item each item in the file that had to be resolved was resolved
+    /// to.
+    pub links: Vec<(Locator, Locator)>,
+    /// The key the invariants and the certificate are stored with.
+    pub key: String,
+    pub invariants: Vec<(Locator, CachedInvariants)>,
+    /// The steps of a certificate for the file's invariant sets.
+    pub sets: Vec<Option<Vec<Step>>>,
+}
+
+/// What the cache knows about a file during a run.
+struct FileState {
+    path: String,
+    file_index: Option<usize>,
+    /// Whether the file belongs to the root, as opposed to being the file of
+    /// an item in another root that one of the root's sets is about.
+    own: bool,
+    resolution_key: String,
+    key: String,
+    invariants: Vec<(Locator, CachedInvariants)>,
+    /// The sets of the items in the file. Items without a position are
+    /// grouped under an empty path.
+    sets: Vec<InvariantSetPtr>,
+}
+
+struct RootState {
+    name: String,
+    dependency_hash: u64,
+    stored: Vec<CachedFile>,
+    files: Vec<FileState>,
+}
+
+impl RootState {
+    fn stored(&self, path: &str) -> Option<&CachedFile> {
+        self.stored.iter().find(|file| file.path == path)
+    }
+}
+
+/// Everything stored for the roots that are being analyzed, along with what
+/// is needed to store it again.
+pub struct Cache {
+    base: PathBuf,
+    locators: Locators,
+    reached: HashMap<usize, BTreeSet<usize>>,
+    roots: Vec<RootState>,
+}
+
+/// Works out a hash for each root which changes whenever a file in any root
+/// it imports from changes, directly or indirectly.
+fn dependency_hashes(files: &FileNode, dependencies: &[Vec<usize>]) -> Vec<u64> {
+    let own_hashes = files
+        .children
+        .iter()
+        .map(|(name, root)| {
+            let mut bytes = Vec::new();
+            for (index, path) in root.paths().iter().enumerate() {
+                let (_, content) = root.get_file(index + 1);
+                for part in [name.as_bytes(), path.as_bytes(), content.as_bytes()] {
+                    bytes.extend_from_slice(part);
+                    bytes.push(0);
+                }
+            }
+            fnv1a(&bytes)
+        })
+        .collect::<Vec<_>>();
+    (0..files.children.len())
+        .map(|root| {
+            let mut reachable = dependencies[root].clone();
+            let mut index = 0;
+            while index < reachable.len() {
+                for &dependency in &dependencies[reachable[index]] {
+                    if dependency != root && !reachable.contains(&dependency) {
+                        reachable.push(dependency);
+                    }
+                }
+                index += 1;
+            }
+            reachable.sort();
+            let mut bytes = Vec::new();
+            for root in reachable {
+                bytes.extend_from_slice(&own_hashes[root].to_le_bytes());
+            }
+            fnv1a(&bytes)
+        })
+        .collect()
+}
+
+/// Works out which other files the items in each file refer to, directly or
+/// through items in yet other files.
+fn reached_files(roots: &[ItemPtr]) -> HashMap<usize, BTreeSet<usize>> {
+    let mut items_in: HashMap<usize, Vec<ItemPtr>> = HashMap::new();
+    for root in roots {
+        root.for_self_and_deep_contents(&mut |item| {
+            if let Some(position) = item.borrow().position {
+                items_in
+                    .entry(position.file_index())
+                    .or_default()
+                    .push(item.ptr_clone());
+            }
+        });
+    }
+    items_in
+        .into_iter()
+        .map(|(file_index, items)| {
+            let mut reached = BTreeSet::new();
+            let mut visited = HashSet::new();
+            for item in items {
+                item.for_self_and_deep_contents_impl(
+                    &mut |other| {
+                        if let Some(position) = other.borrow().position {
+                            if position.file_index() != file_index {
+                                reached.insert(position.file_index());
+                            }
+                        }
+                    },
+                    &mut visited,
+                );
+            }
+            (file_index, reached)
+        })
+        .collect()
+}
+
+fn file_key(
+    file_tree: &FileNode,
+    path: &str,
+    content: &str,
+    dependency_hash: u64,
+    reached: Option<&BTreeSet<usize>>,
+) -> String {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(path.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(content.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(&dependency_hash.to_le_bytes());
+    for &file_index in reached.into_iter().flatten() {
+        let (path, content) = file_tree.get_file(file_index);
+        for part in [path.as_bytes(), content.as_bytes()] {
+            bytes.extend_from_slice(part);
+            bytes.push(0);
+        }
+    }
+    format!("{:016x}", fnv1a(&bytes))
+}
+
+fn resolution_key(path: &str, content: &str, dependency_hash: u64) -> String {
+    let mut bytes = Vec::new();
+    for part in [path.as_bytes(), content.as_bytes()] {
+        bytes.extend_from_slice(part);
+        bytes.push(0);
+    }
+    bytes.extend_from_slice(&dependency_hash.to_le_bytes());
+    format!("{:016x}", fnv1a(&bytes))
+}
+
+fn cache_path(base: &Path, root_name: &str) -> PathBuf {
+    base.join(CACHE_FOLDER).join(format!("{}.json", root_name))
+}
+
+/// Returns what was stored for a root, or nothing if it can't be read.
+fn read(base: &Path, root_name: &str) -> Vec<CachedFile> {
+    fs::read_to_string(cache_path(base, root_name))
+        .ok()
+        .and_then(|text| serde_json::from_str::<CachedRoot>(&text).ok())
+        .map(|cached| cached.files)
+        .unwrap_or_default()
+}
+
+impl Cache {
+    /// Reads what was stored for each root. This must happen before anything
+    /// is resolved, so that items can be numbered the same way they were
+    /// when it was stored.
+    pub fn new(base: &Path, sources: &Sources, file_tree: &FileNode, roots: &[ItemPtr]) -> Self {
+        let dependency_hashes = dependency_hashes(file_tree, &sources.root_dependencies());
+        let paths = file_tree.paths();
+        let states = file_tree
+            .children
+            .iter()
+            .zip(dependency_hashes)
+            .map(|((name, _), dependency_hash)| {
+                let mut files = Vec::new();
+                for (index, path) in paths.iter().enumerate() {
+                    if path.split('/').nth(1) != Some(name) {
+                        continue;
+                    }
+                    let (_, content) = file_tree.get_file(index + 1);
+                    files.push(FileState {
+                        path: path.clone(),
+                        file_index: Some(index + 1),
+                        own: true,
+                        resolution_key: resolution_key(path, content, dependency_hash),
+                        key: String::new(),
+                        invariants: Vec::new(),
+                        sets: Vec::new(),
+                    });
+                }
+                RootState {
+                    name: name.clone(),
+                    dependency_hash,
+                    stored: read(base, name),
+                    files,
+                }
+            })
+            .collect();
+        Self {
+            base: base.to_owned(),
+            locators: Locators::new(file_tree, roots),
+            reached: HashMap::new(),
+            roots: states,
+        }
+    }
+
+    /// Links up every item whose link was stored with a matching key, then
+    /// checks each link by resolving its item again. Links that don't hold
+    /// any more are undone, which can make others fail, so this repeats
+    /// until every remaining link holds. This must happen before
+    /// resolve_all, which resolves whatever is left.
+    pub fn reuse_links(&self, env: &mut Environment) {
+        let mut linked = Vec::new();
+        for root in &self.roots {
+            for file in &root.files {
+                let stored = match root.stored(&file.path) {
+                    Some(stored) if stored.resolution_key == file.resolution_key => stored,
+                    _ => continue,
+                };
+                for (from, to) in &stored.links {
+                    let (item, target) = match (self.locators.find(from), self.locators.find(to)) {
+                        (Some(item), Some(target)) if item.is_unresolved() => (item, target),
+                        _ => continue,
+                    };
+                    let original = item.borrow().definition.clone_into_box();
+                    item.redefine(DOther::new(target.ptr_clone()).clone_into_box());
+                    linked.push((item.ptr_clone(), original, target.ptr_clone()));
+                }
+            }
+        }
+        loop {
+            let mut undone = false;
+            linked.retain(|(item, original, target)| {
+                let resolvable = (&**original as &dyn Any)
+                    .downcast_ref::<DResolvable>()
+                    .expect("only unresolved items are linked")
+                    .resolvable();
+                let holds = match resolvable.resolve(env, item.ptr_clone(), item.clone_scope(), 0) {
+                    ResolveResult::Ok(definition) => matches!(
+                        (&*definition as &dyn Any).downcast_ref::<DOther>(),
+                        Some(other) if other.other().is_same_instance_as(target)
+                    ),
+                    ResolveResult::Err(..) => false,
+                };
+                if !holds {
+                    item.redefine(original.clone_into_box());
+                    undone = true;
+                }
+                holds
+            });
+            if !undone {
+                break;
+            }
+        }
+    }
+
+    /// Works out the keys of the files now that it is known which other
+    /// files they refer to, then gives items the invariant sets stored for
+    /// them. This must happen right after resolving, before anything works
+    /// out invariants itself.
+    pub fn reuse_invariants(&mut self, file_tree: &FileNode, roots: &[ItemPtr]) {
+        self.reached = reached_files(roots);
+        let all_reached = &self.reached;
+        for root in &mut self.roots {
+            for file in &mut root.files {
+                let file_index = file.file_index.expect("files of a root have an index");
+                let content = file_tree.get_file(file_index).1;
+                let reached = all_reached.get(&file_index);
+                file.key = file_key(
+                    file_tree,
+                    &file.path,
+                    content,
+                    root.dependency_hash,
+                    reached,
+                );
+            }
+        }
+        let decoder = Decoder::new(&self.locators);
+        let mut shared = Vec::new();
+        for root in &self.roots {
+            for file in &root.files {
+                let stored = match root.stored(&file.path) {
+                    Some(stored) if stored.key == file.key => stored,
+                    _ => continue,
+                };
+                for (locator, invariants) in &stored.invariants {
+                    let item = match self.locators.find(locator) {
+                        Some(item) if item.borrow().invariants.is_none() => item,
+                        _ => continue,
+                    };
+                    match invariants {
+                        CachedInvariants::SameAs(..) => shared.push((item, invariants)),
+                        CachedInvariants::Set { .. } => {
+                            if let Some(set) = decoder.invariants(item, invariants) {
+                                item.borrow_mut().invariants = Some(set);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // Sets are shared once the sets they are shared from are there.
+        for (item, invariants) in shared {
+            if item.borrow().invariants.is_none() {
+                if let Some(set) = decoder.invariants(item, invariants) {
+                    item.borrow_mut().invariants = Some(set);
+                }
+            }
+        }
+    }
+
+    /// Groups the invariant sets of each root by file and remembers the
+    /// invariant sets of every item, to be stored later. Returns the stored
+    /// steps for each file that hasn't changed, paired with the sets they are
+    /// for. This must happen before anything is justified, like
+    /// invariant_set_table.
+    pub fn reuse_steps(
+        &mut self,
+        env: &Environment,
+        file_tree: &FileNode,
+        roots: &[ItemPtr],
+    ) -> Vec<(Vec<InvariantSetPtr>, Vec<Option<Vec<Step>>>)> {
+        let encoder = Encoder::new(&self.locators);
+        for (locator, item) in self.locators.items() {
+            let file = self
+                .roots
+                .iter_mut()
+                .flat_map(|root| &mut root.files)
+                .find(|file| file.own && file.path == locator.0);
+            if let (Some(file), Some(invariants)) = (file, encoder.invariants(item)) {
+                file.invariants.push((locator.clone(), invariants));
+            }
+        }
+        let all_reached = &self.reached;
+        let mut result = Vec::new();
+        for (state, root) in self.roots.iter_mut().zip(roots) {
+            for set in env.invariant_set_table(&[root.ptr_clone()]) {
+                let position = set.borrow().context().borrow().position;
+                let file_index = position.map(|position| position.file_index());
+                let path = match file_index {
+                    Some(file_index) => file_tree.get_file(file_index).0,
+                    None => String::new(),
+                };
+                match state.files.iter_mut().find(|file| file.path == path) {
+                    Some(file) => file.sets.push(set),
+                    None => {
+                        let content = file_index.map_or("", |index| file_tree.get_file(index).1);
+                        let reached = file_index.and_then(|index| all_reached.get(&index));
+                        let key =
+                            file_key(file_tree, &path, content, state.dependency_hash, reached);
+                        state.files.push(FileState {
+                            path,
+                            file_index,
+                            own: false,
+                            resolution_key: String::new(),
+                            key,
+                            invariants: Vec::new(),
+                            sets: vec![set],
+                        });
+                    }
+                }
+            }
+            for file in &state.files {
+                if let Some(stored) = state.stored(&file.path) {
+                    if stored.key == file.key && stored.sets.len() == file.sets.len() {
+                        result.push((file.sets.clone(), stored.sets.clone()));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Stores everything about each root, replacing anything stored before.
+    /// The cache is only there to save time, so failing to write it is not
+    /// an error.
+    pub fn store(&self, env: &Environment) {
+        let links = self.locators.links();
+        for root in &self.roots {
+            let files = root
+                .files
+                .iter()
+                .map(|file| CachedFile {
+                    path: file.path.clone(),
+                    resolution_key: file.resolution_key.clone(),
+                    links: links
+                        .iter()
+                        .filter(|(from, _)| file.own && from.0 == file.path)
+                        .map(|&(from, to)| (from.clone(), to.clone()))
+                        .collect(),
+                    key: file.key.clone(),
+                    invariants: file.invariants.clone(),
+                    sets: env.certificate_steps(&file.sets),
+                })
+                .collect();
+            let path = cache_path(&self.base, &root.name);
+            if let Some(folder) = path.parent() {
+                let _ = fs::create_dir_all(folder);
+            }
+            let _ = fs::write(path, serde_json::to_string(&CachedRoot { files }).unwrap());
+        }
+    }
+}
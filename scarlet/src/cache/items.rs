@@ -0,0 +1,407 @@
+//! Refers to items in a way that stays the same between runs, so that what
+//! was worked out about them can be stored and matched back up with them.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file_tree::FileNode,
+    item::{
+        definitions::{
+            decision::DDecision,
+            other::DOther,
+            structt::{AtomicStructMember, DAtomicStructMember, DPopulatedStruct},
+            substitution::{DSubstitution, Substitutions},
+            variable::DVariable,
+        },
+        invariants::{InvariantSet, InvariantSetPtr},
+        util::{decision, unchecked_substitution_without_shortcuts},
+        Item, ItemDefinition, ItemPtr,
+    },
+    util::PtrExtension,
+};
+
+/// Identifies an item written in the source code by the path of its file,
+/// the range of the file it covers, and how many items covering the same
+/// range come before it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Locator(pub String, usize, usize, usize);
+
+/// Every item written in the source code, numbered before anything is
+/// resolved so that the numbering only depends on the source code.
+pub struct Locators {
+    items: Vec<(Locator, ItemPtr)>,
+    indexes: HashMap<ItemPtr, usize>,
+    by_locator: HashMap<Locator, usize>,
+    /// Whether each item still had to be resolved when it was numbered.
+    unresolved: Vec<bool>,
+}
+
+impl Locators {
+    pub fn new(file_tree: &FileNode, roots: &[ItemPtr]) -> Self {
+        let paths = file_tree.paths();
+        let mut result = Self {
+            items: Vec::new(),
+            indexes: HashMap::new(),
+            by_locator: HashMap::new(),
+            unresolved: Vec::new(),
+        };
+        let mut counts: HashMap<(usize, usize, usize), usize> = HashMap::new();
+        let mut visited = HashSet::new();
+        for root in roots {
+            root.for_self_and_deep_contents_impl(
+                &mut |item| {
+                    let position = match item.borrow().position {
+                        Some(position) if position.file_index() > 0 => position,
+                        _ => return,
+                    };
+                    let range = position.range();
+                    let count = counts
+                        .entry((position.file_index(), range.start, range.end))
+                        .or_default();
+                    let path = paths[position.file_index() - 1].clone();
+                    let locator = Locator(path, range.start, range.end, *count);
+                    *count += 1;
+                    result.indexes.insert(item.ptr_clone(), result.items.len());
+                    result
+                        .by_locator
+                        .insert(locator.clone(), result.items.len());
+                    result.unresolved.push(item.is_unresolved());
+                    result.items.push((locator, item.ptr_clone()));
+                },
+                &mut visited,
+            );
+        }
+        result
+    }
+
+    /// Every item in the order they were numbered in.
+    pub fn items(&self) -> &[(Locator, ItemPtr)] {
+        &self.items
+    }
+
+    pub fn locate(&self, item: &ItemPtr) -> Option<&Locator> {
+        self.indexes.get(item).map(|&index| &self.items[index].0)
+    }
+
+    pub fn find(&self, locator: &Locator) -> Option<&ItemPtr> {
+        self.by_locator
+            .get(locator)
+            .map(|&index| &self.items[index].1)
+    }
+
+    /// Returns the item each item that had to be resolved was resolved to, if
+    /// that is also an item written in the source code.
+    pub fn links(&self) -> Vec<(&Locator, &Locator)> {
+        let mut links = Vec::new();
+        for ((locator, item), &unresolved) in self.items.iter().zip(&self.unresolved) {
+            if !unresolved {
+                continue;
+            }
+            if let Some(other) = item.downcast_definition::<DOther>() {
+                if let Some(target) = self.locate(other.other()) {
+                    links.push((locator, target));
+                }
+            }
+        }
+        links
+    }
+}
+
+/// An item that invariants refer to. Items written in the source code are
+/// referred to by where they are, other items by their definition.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CachedItem {
+    At(Locator),
+    /// The item an item written in the source code was resolved to, like the
+    /// variable VAR creates.
+    TargetOf(Locator),
+    Other(Box<CachedItem>),
+    UncheckedSubstitution(Box<CachedItem>, Vec<(CachedItem, CachedItem)>),
+    Decision(Box<[CachedItem; 4]>),
+    Value(Box<CachedItem>),
+    Rest(Box<CachedItem>),
+    Struct(String, Box<CachedItem>, Box<CachedItem>),
+}
+
+/// What is stored about the invariant set of an item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CachedInvariants {
+    /// The item shares the set of the item the set was worked out for.
+    SameAs(Locator),
+    Set {
+        statements: Vec<CachedItem>,
+        justification_requirements: Vec<CachedItem>,
+        dependencies: Vec<CachedItem>,
+        required: bool,
+        connected_to_root: bool,
+    },
+}
+
+pub struct Encoder<'a> {
+    locators: &'a Locators,
+    /// Items that items written in the source code were resolved to, but that
+    /// weren't written in the source code themselves.
+    targets: HashMap<ItemPtr, &'a Locator>,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(locators: &'a Locators) -> Self {
+        let mut targets = HashMap::new();
+        for (locator, item) in locators.items() {
+            if let Some(other) = item.downcast_definition::<DOther>() {
+                if locators.locate(other.other()).is_none() {
+                    targets.entry(other.other().ptr_clone()).or_insert(locator);
+                }
+            }
+        }
+        Self { locators, targets }
+    }
+
+    /// Returns None if the item refers to something that can't be stored.
+    pub fn item(&self, item: &ItemPtr) -> Option<CachedItem> {
+        self.item_impl(item, &mut Vec::new())
+    }
+
+    fn item_impl(&self, item: &ItemPtr, stack: &mut Vec<ItemPtr>) -> Option<CachedItem> {
+        if let Some(locator) = self.locators.locate(item) {
+            return Some(CachedItem::At(locator.clone()));
+        } else if let Some(&locator) = self.targets.get(item) {
+            return Some(CachedItem::TargetOf(locator.clone()));
+        } else if stack.contains(item) {
+            return None;
+        }
+        stack.push(item.ptr_clone());
+        let result = self.definition(item, stack);
+        stack.pop();
+        result
+    }
+
+    fn definition(&self, item: &ItemPtr, stack: &mut Vec<ItemPtr>) -> Option<CachedItem> {
+        let mut boxed = |item: &ItemPtr| self.item_impl(item, stack).map(Box::new);
+        if let Some(other) = item.downcast_definition::<DOther>() {
+            Some(CachedItem::Other(boxed(other.other())?))
+        } else if let Some(sub) = item.downcast_definition::<DSubstitution>() {
+            let invs = sub.invs().borrow();
+            if invs.statements().len() > 0
+                || invs.justification_requirements().len() > 0
+                || invs.dependencies().len() > 0
+            {
+                // The invariants of checked substitutions come from resolving
+                // them, which can't be repeated here.
+                return None;
+            }
+            let base = boxed(sub.base())?;
+            let mut subs = Vec::new();
+            for (target, value) in sub.substitutions().iter() {
+                let target = self.item_impl(target.borrow().item(), stack)?;
+                subs.push((target, self.item_impl(value, stack)?));
+            }
+            Some(CachedItem::UncheckedSubstitution(base, subs))
+        } else if let Some(dec) = item.downcast_definition::<DDecision>() {
+            Some(CachedItem::Decision(Box::new([
+                self.item_impl(dec.left(), stack)?,
+                self.item_impl(dec.right(), stack)?,
+                self.item_impl(dec.when_equal(), stack)?,
+                self.item_impl(dec.when_not_equal(), stack)?,
+            ])))
+        } else if let Some(member) = item.downcast_definition::<DAtomicStructMember>() {
+            match member.member() {
+                AtomicStructMember::Value => Some(CachedItem::Value(boxed(member.base())?)),
+                AtomicStructMember::Rest => Some(CachedItem::Rest(boxed(member.base())?)),
+                AtomicStructMember::Label => None,
+            }
+        } else if let Some(structt) = item.downcast_definition::<DPopulatedStruct>() {
+            let value = boxed(structt.get_value())?;
+            let rest = boxed(structt.get_rest())?;
+            Some(CachedItem::Struct(
+                structt.get_label().to_owned(),
+                value,
+                rest,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Describes the invariant set an item has already worked out. Returns
+    /// None if there isn't one or it can't be stored.
+    pub fn invariants(&self, item: &ItemPtr) -> Option<CachedInvariants> {
+        let set_ptr = item.borrow().invariants.as_ref()?.ptr_clone();
+        let set = set_ptr.borrow();
+        if set.justified_by().is_some() || set.used_justification().is_some() {
+            return None;
+        }
+        let context = set.context();
+        if !context.is_same_instance_as(item) {
+            // The set is only shared with its context if it was taken from
+            // the context's own set, as opposed to being worked out again.
+            let shared = matches!(
+                &context.borrow().invariants,
+                Some(other) if other.is_same_instance_as(&set_ptr)
+            );
+            return if shared {
+                Some(CachedInvariants::SameAs(
+                    self.locators.locate(context)?.clone(),
+                ))
+            } else {
+                None
+            };
+        }
+        if item.downcast_definition::<DSubstitution>().is_some() {
+            // The set is part of the definition, so it comes back when the
+            // item is resolved.
+            return None;
+        }
+        let required = set.required();
+        let connected_to_root = set.connected_to_root();
+        if connected_to_root && (!required || set.justification_requirements().len() > 0) {
+            return None;
+        }
+        let items = |items: &[ItemPtr]| {
+            items
+                .iter()
+                .map(|item| self.item(item))
+                .collect::<Option<Vec<_>>>()
+        };
+        let statements = items(set.statements())?;
+        let justification_requirements = items(set.justification_requirements())?;
+        let mut dependencies = set
+            .dependencies()
+            .iter()
+            .map(|item| self.item(item))
+            .collect::<Option<Vec<_>>>()?;
+        // Keeps what is stored the same from one run to the next.
+        dependencies.sort();
+        Some(CachedInvariants::Set {
+            statements,
+            justification_requirements,
+            dependencies,
+            required,
+            connected_to_root,
+        })
+    }
+}
+
+pub struct Decoder<'a> {
+    locators: &'a Locators,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(locators: &'a Locators) -> Self {
+        Self { locators }
+    }
+
+    /// Rebuilds a stored item. Returns None if anything it refers to can't be
+    /// found any more.
+    pub fn item(&self, cached: &CachedItem) -> Option<ItemPtr> {
+        let new = |definition: Box<dyn ItemDefinition>, like: &ItemPtr| {
+            Item::new_boxed(definition, like.clone_scope())
+        };
+        Some(match cached {
+            CachedItem::At(locator) => self.locators.find(locator)?.ptr_clone(),
+            CachedItem::TargetOf(locator) => self
+                .locators
+                .find(locator)?
+                .downcast_definition::<DOther>()?
+                .other()
+                .ptr_clone(),
+            CachedItem::Other(other) => {
+                let other = self.item(other)?;
+                new(Box::new(DOther::new(other.ptr_clone())), &other)
+            }
+            CachedItem::UncheckedSubstitution(base, subs) => {
+                let base = self.item(base)?;
+                let mut substitutions = Substitutions::new();
+                for (target, value) in subs {
+                    let target = self.item(target)?;
+                    let target = target
+                        .downcast_definition::<DVariable>()?
+                        .get_variable()
+                        .ptr_clone();
+                    substitutions.insert_no_replace(target, self.item(value)?);
+                }
+                unchecked_substitution_without_shortcuts(base, &substitutions)
+            }
+            CachedItem::Decision(parts) => {
+                let [left, right, when_equal, when_not_equal] = &**parts;
+                decision(
+                    self.item(left)?,
+                    self.item(right)?,
+                    self.item(when_equal)?,
+                    self.item(when_not_equal)?,
+                )
+            }
+            CachedItem::Value(base) | CachedItem::Rest(base) => {
+                let member = match cached {
+                    CachedItem::Value(..) => AtomicStructMember::Value,
+                    _ => AtomicStructMember::Rest,
+                };
+                let base = self.item(base)?;
+                new(
+                    Box::new(DAtomicStructMember::new(base.ptr_clone(), member)),
+                    &base,
+                )
+            }
+            CachedItem::Struct(label, value, rest) => {
+                let value = self.item(value)?;
+                let rest = self.item(rest)?;
+                new(
+                    Box::new(DPopulatedStruct::new(
+                        label.clone(),
+                        value.ptr_clone(),
+                        rest,
+                    )),
+                    &value,
+                )
+            }
+        })
+    }
+
+    /// Rebuilds the invariant set stored for an item. Returns None if anything
+    /// it refers to can't be found any more.
+    pub fn invariants(&self, item: &ItemPtr, cached: &CachedInvariants) -> Option<InvariantSetPtr> {
+        match cached {
+            CachedInvariants::SameAs(context) => self.locators.find(context)?.get_invariants().ok(),
+            CachedInvariants::Set {
+                statements,
+                justification_requirements,
+                dependencies,
+                required,
+                connected_to_root,
+            } => {
+                let items = |items: &[CachedItem]| {
+                    items
+                        .iter()
+                        .map(|item| self.item(item))
+                        .collect::<Option<Vec<_>>>()
+                };
+                let statements = items(statements)?;
+                let justification_requirements = items(justification_requirements)?;
+                let dependencies = items(dependencies)?.into_iter().collect();
+                let context = item.ptr_clone();
+                Some(match (required, connected_to_root) {
+                    (true, false) => InvariantSet::new(
+                        context,
+                        statements,
+                        justification_requirements,
+                        dependencies,
+                    ),
+                    (false, false) => InvariantSet::new_not_required(
+                        context,
+                        statements,
+                        justification_requirements,
+                        dependencies,
+                    ),
+                    (true, true) => InvariantSet::new_root_statements_depending_on(
+                        context,
+                        statements,
+                        dependencies,
+                    ),
+                    (false, true) => return None,
+                })
+            }
+        }
+    }
+}
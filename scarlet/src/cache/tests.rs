@@ -0,0 +1,173 @@
+#![cfg(test)]
+
+use std::{collections::HashMap, fs, path::Path, rc::Rc};
+
+use super::{items::CachedInvariants, CachedRoot, CACHE_FOLDER};
+use crate::{
+    item::{
+        equality::Equal,
+        invariants::InvariantCalculationContext,
+        test_util::{TempFolder, AXIOM_CODE, LANGUAGE_ITEMS},
+        ItemPtr,
+    },
+    pipeline::{self, Stage},
+};
+
+fn code() -> String {
+    format!("{}{}b IS UNIQUE\n", LANGUAGE_ITEMS, AXIOM_CODE)
+}
+
+fn copy_folder(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_folder(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+fn stored(path: &Path) -> CachedRoot {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn find_named(root: &ItemPtr, name: &str) -> ItemPtr {
+    let mut found = None;
+    root.for_self_and_deep_contents(&mut |item| {
+        if found.is_none() && item.borrow().name.as_deref() == Some(name) {
+            found = Some(item.ptr_clone());
+        }
+    });
+    found.unwrap()
+}
+
+#[test]
+fn cached_justifications_are_reused() {
+    let folder = TempFolder::new("cache");
+    let root = folder.join("main.sr");
+    let stored = folder.join(CACHE_FOLDER).join("main.json");
+    fs::write(&root, code()).unwrap();
+
+    let first = pipeline::analyze_cached(&root);
+    assert_eq!(first.stage, Stage::Justified);
+    let cached = fs::read_to_string(&stored).unwrap();
+    let second = pipeline::analyze_cached(&root);
+    assert_eq!(second.stage, Stage::Justified);
+    assert_eq!(fs::read_to_string(&stored).unwrap(), cached);
+
+    fs::write(&root, code().replace("y(a)", "y(b)")).unwrap();
+    let changed = pipeline::analyze_cached(&root);
+    assert_eq!(changed.stage, Stage::Checked);
+
+    // The cache only saves time, so a broken one can't stop anything.
+    fs::write(&root, code()).unwrap();
+    fs::write(&stored, "not json").unwrap();
+    let broken = pipeline::analyze_cached(&root);
+    assert_eq!(broken.stage, Stage::Justified);
+}
+
+#[test]
+fn keys_follow_references_between_files() {
+    let folder = TempFolder::new("cache-refs");
+    fs::create_dir(folder.join("main")).unwrap();
+    let root = folder.join("main");
+    let stored = folder.join(CACHE_FOLDER).join("main.json");
+    fs::write(
+        folder.join("main.sr"),
+        format!("{}uses_lib IS y(lib.thing)\n", code()),
+    )
+    .unwrap();
+    fs::write(folder.join("main/lib.sr"), "thing IS a").unwrap();
+    fs::write(folder.join("main/other.sr"), "trivial_t(b)").unwrap();
+
+    let keys = || {
+        self::stored(&stored)
+            .files
+            .into_iter()
+            .map(|file| (file.path, file.key))
+            .collect::<HashMap<_, _>>()
+    };
+    assert_eq!(pipeline::analyze_cached(&root).stage, Stage::Justified);
+    let before = keys();
+    fs::write(folder.join("main/lib.sr"), "thing IS  a").unwrap();
+    assert_eq!(pipeline::analyze_cached(&root).stage, Stage::Justified);
+    let after = keys();
+    assert_ne!(before["/main"], after["/main"]);
+    assert_eq!(before["/main/other"], after["/main/other"]);
+}
+
+#[test]
+fn links_are_checked_again() {
+    let folder = TempFolder::new("cache-links");
+    fs::create_dir(folder.join("main")).unwrap();
+    let root = folder.join("main");
+    let main = |order: &str| format!("{}{}", code(), order);
+    fs::write(folder.join("main.sr"), main("c IS UNIQUE\nd IS UNIQUE\n")).unwrap();
+    fs::write(folder.join("main/lib.sr"), "e IS c\n").unwrap();
+
+    let first = pipeline::analyze_cached(&root);
+    assert_eq!(first.stage, Stage::Justified);
+    let cached = stored(&folder.join(CACHE_FOLDER).join("main.json"));
+    let lib = cached.files.iter().find(|file| file.path == "/main/lib");
+    assert_eq!(lib.unwrap().links.len(), 1);
+
+    // The link from e is still stored under the same key, but the item it
+    // points to is d now.
+    fs::write(folder.join("main.sr"), main("d IS UNIQUE\nc IS UNIQUE\n")).unwrap();
+    let second = pipeline::analyze_cached(&root);
+    assert_eq!(second.stage, Stage::Justified);
+    let e = find_named(second.root.as_ref().unwrap(), "e");
+    assert_eq!(e.dereference().borrow().name.as_deref(), Some("c"));
+}
+
+#[test]
+fn cached_invariants_match_worked_out_ones() {
+    let folder = TempFolder::new("cache-invariants");
+    let root = folder.join("full");
+    copy_folder(Path::new("full"), &root);
+
+    assert_eq!(pipeline::analyze_cached(&root).stage, Stage::Justified);
+    let cached = stored(&folder.join(CACHE_FOLDER).join("full.json"));
+    let sets = cached
+        .files
+        .iter()
+        .flat_map(|file| &file.invariants)
+        .filter(|(_, invariants)| matches!(invariants, CachedInvariants::Set { .. }))
+        .count();
+    assert!(sets > 0);
+
+    let second = pipeline::analyze_cached(&root);
+    assert_eq!(second.stage, Stage::Justified);
+    let equal = |left: &[ItemPtr], right: &[ItemPtr]| {
+        left.len() == right.len()
+            && left.iter().zip(right).all(|(left, right)| {
+                left.get_trimmed_equality(right)
+                    .as_ref()
+                    .map(Equal::is_trivial_yes)
+                    == Ok(true)
+            })
+    };
+    second
+        .root
+        .as_ref()
+        .unwrap()
+        .for_self_and_deep_contents(&mut |item| {
+            let used = match &item.borrow().invariants {
+                Some(used) => Rc::clone(used),
+                None => return,
+            };
+            let used = used.borrow();
+            let worked_out = InvariantCalculationContext::new()
+                .get_invariants(item)
+                .unwrap();
+            let worked_out = worked_out.borrow();
+            assert!(equal(used.statements(), worked_out.statements()));
+            assert!(equal(
+                used.justification_requirements(),
+                worked_out.justification_requirements()
+            ));
+        });
+}
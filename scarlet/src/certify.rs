@@ -0,0 +1,109 @@
+//! The `certify` and `verify` subcommands. The first records how everything
+//! was justified as a certificate, the second checks a certificate against the
+//! source code without searching for any justifications itself.
+
+mod tests;
+
+use std::{fs, path::Path};
+
+use crate::{
+    diagnostic::Diagnostic,
+    item::invariants::Certificate,
+    output::{Output, Verbosity},
+    pipeline::{self, Stage},
+    CommandArgs, USAGE_ERROR,
+};
+
+/// Justifies the root given in the arguments and prints a certificate for it,
+/// returning the exit code the process should use.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let root = match CommandArgs::parse(args, &[], 1) {
+        Ok(args) => args.path(0),
+        Err(code) => return code,
+    };
+    match certify(Path::new(&root), output) {
+        Ok(certificate) => {
+            println!("{}", serde_json::to_string_pretty(&certificate).unwrap());
+            0
+        }
+        Err(()) => 1,
+    }
+}
+
+/// Checks the certificate given in the arguments against a root, returning
+/// the exit code the process should use.
+pub fn run_verify(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let (root, certificate_path) = match CommandArgs::parse(args, &[], 2) {
+        Ok(args) if args.positional.len() == 2 => {
+            (args.positional[0].clone(), args.positional[1].clone())
+        }
+        Ok(_) => {
+            eprintln!("Usage: scarlet verify <root> <certificate>");
+            return USAGE_ERROR;
+        }
+        Err(code) => return code,
+    };
+    let certificate = match fs::read_to_string(&certificate_path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", certificate_path, err);
+            return 1;
+        }
+    };
+    let certificate = match serde_json::from_str(&certificate) {
+        Ok(certificate) => certificate,
+        Err(err) => {
+            eprintln!("{} is not a valid certificate: {}", certificate_path, err);
+            return 1;
+        }
+    };
+    if verify(Path::new(&root), &certificate, output) {
+        output.progress(Verbosity::Normal, "The certificate is valid.");
+        0
+    } else {
+        1
+    }
+}
+
+/// Prints any problems and returns Err if the root does not justify. They are
+/// printed to stderr, since the certificate goes to stdout.
+fn certify(root: &Path, output: Output) -> Result<Certificate, ()> {
+    let analysis = pipeline::analyze(root);
+    for diagnostic in &analysis.diagnostics {
+        output.report_to_stderr(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    if analysis.stage != Stage::Justified {
+        return Err(());
+    }
+    let sets = &analysis.invariant_sets;
+    Ok(analysis.env.certificate(sets, analysis.source_hash()))
+}
+
+/// Prints any problems and returns whether the certificate is valid for the
+/// root.
+fn verify(root: &Path, certificate: &Certificate, output: Output) -> bool {
+    let mut analysis = pipeline::analyze_until(root, Stage::Checked);
+    for diagnostic in &analysis.diagnostics {
+        output.report_to_stderr(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    if analysis.stage != Stage::Checked {
+        return false;
+    }
+    if analysis.source_hash() != certificate.source_hash {
+        let diagnostic = Diagnostic::new().with_text_error(format!(
+            "The certificate was made from different source code."
+        ));
+        output.report_to_stderr(&diagnostic, &analysis.file_tree, &analysis.base);
+        return false;
+    }
+    let sets = &analysis.invariant_sets;
+    match analysis.env.check_certificate(sets, certificate) {
+        Ok(()) => true,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                output.report_to_stderr(&diagnostic, &analysis.file_tree, &analysis.base);
+            }
+            false
+        }
+    }
+}
@@ -0,0 +1,18 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use super::{certify, verify};
+use crate::output::{MessageFormat, Output, Verbosity};
+
+#[test]
+fn certificate_verifies_against_its_source() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden/shown.sr");
+    let output = Output::new(Verbosity::Quiet, MessageFormat::Human);
+    let certificate = certify(&root, output).unwrap();
+    assert!(verify(&root, &certificate, output));
+
+    let mut other_source = certificate.clone();
+    other_source.source_hash = String::from("0000000000000000");
+    assert!(!verify(&root, &other_source, output));
+}
@@ -0,0 +1,137 @@
+//! The `check` and `show` subcommands, which run source code through the
+//! whole pipeline and report what came out of it.
+
+mod tests;
+
+use std::path::Path;
+
+use crate::{
+    diagnostic::Level,
+    output::{Output, Verbosity},
+    pipeline::{self, Analysis, Stage},
+    watch, CommandArgs,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Only report problems.
+    Check,
+    /// Report problems and print every item marked SHOWN.
+    Show,
+}
+
+#[derive(Clone, Debug)]
+struct Options {
+    path: String,
+    mode: Mode,
+    output: Output,
+    use_cache: bool,
+    watch: bool,
+}
+
+impl Options {
+    fn from_args(
+        args: impl Iterator<Item = String>,
+        mode: Mode,
+        output: Output,
+    ) -> Result<Self, i32> {
+        let args = CommandArgs::parse(args, &["--cache", "--watch"], 1)?;
+        Ok(Self {
+            path: args.path(0),
+            mode,
+            output,
+            use_cache: args.has("--cache"),
+            watch: args.has("--watch"),
+        })
+    }
+}
+
+/// Checks the root given in the arguments, returning the exit code the
+/// process should use. This is 1 if the code has any errors or could not be
+/// justified.
+pub fn run(args: impl Iterator<Item = String>, mode: Mode, output: Output) -> i32 {
+    let options = match Options::from_args(args, mode, output) {
+        Ok(options) => options,
+        Err(code) => return code,
+    };
+    if options.watch {
+        watch::run(Path::new(&options.path), || entry(&options));
+    }
+    let analysis = entry(&options);
+    let has_errors = analysis
+        .diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.level() == Level::Error);
+    if has_errors || analysis.stage != Stage::Justified {
+        1
+    } else {
+        0
+    }
+}
+
+fn entry(options: &Options) -> Analysis {
+    let path = &options.path;
+    options
+        .output
+        .progress(Verbosity::Verbose, &format!("Reading source from {}", path));
+
+    let mut analysis = if options.use_cache {
+        pipeline::analyze_cached(Path::new(path))
+    } else {
+        pipeline::analyze(Path::new(path))
+    };
+    for (reached, message) in [
+        (Stage::Parsed, "Parsed"),
+        (Stage::Resolved, "Resolved"),
+        (Stage::Checked, "Checked!"),
+        (Stage::Justified, "Justified!"),
+    ] {
+        if analysis.stage >= reached {
+            options.output.progress(Verbosity::Verbose, message);
+        }
+    }
+    for diagnostic in &analysis.diagnostics {
+        options
+            .output
+            .report(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    if options.mode == Mode::Show {
+        for (item, shown) in analysis.shown() {
+            let span = item
+                .borrow()
+                .position
+                .map(|position| position.span(&analysis.file_tree, &analysis.base));
+            options.output.report_shown(&shown, span);
+        }
+    }
+    options
+        .output
+        .progress(Verbosity::Normal, &summary(&analysis));
+    if options.watch {
+        options
+            .output
+            .progress(Verbosity::Normal, "Watching for changes...");
+    }
+    analysis
+}
+
+/// Describes how many problems were found, for when there might be so many
+/// that the end of the list is all that is visible.
+fn summary(analysis: &Analysis) -> String {
+    let count = |level: Level| {
+        analysis
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level() == level)
+            .count()
+    };
+    let plural = |count: usize, noun: &str| match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    };
+    format!(
+        "Found {} and {}.",
+        plural(count(Level::Error), "error"),
+        plural(count(Level::Warning), "warning")
+    )
+}
@@ -0,0 +1,50 @@
+#![cfg(test)]
+
+use std::{fs, path::Path};
+
+use super::{run, Mode};
+use crate::{
+    cache::CACHE_FOLDER,
+    item::test_util::TempFolder,
+    output::{MessageFormat, Output, Verbosity},
+};
+
+fn quiet() -> Output {
+    Output::new(Verbosity::Quiet, MessageFormat::Human)
+}
+
+fn exit_code(file: &str, mode: Mode) -> i32 {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden").join(file);
+    let args = vec![root.to_string_lossy().into_owned()];
+    run(args.into_iter(), mode, quiet())
+}
+
+#[test]
+fn exit_code_reports_errors() {
+    assert_eq!(exit_code("shown.sr", Mode::Check), 0);
+    assert_eq!(exit_code("shown.sr", Mode::Show), 0);
+    assert_eq!(exit_code("unresolved.sr", Mode::Check), 1);
+    assert_eq!(exit_code("unjustified.sr", Mode::Check), 1);
+    assert_eq!(exit_code("syntax_errors.sr", Mode::Show), 1);
+}
+
+#[test]
+fn unknown_options_are_usage_errors() {
+    let args = vec![String::from("--bogus")];
+    assert_eq!(run(args.into_iter(), Mode::Check, quiet()), 2);
+}
+
+#[test]
+fn cache_is_only_written_when_asked_for() {
+    let folder = TempFolder::new("check");
+    let root = folder.join("main.sr");
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+    fs::copy(golden.join("shown.sr"), &root).unwrap();
+    let root = root.to_string_lossy().into_owned();
+
+    assert_eq!(run(vec![root.clone()].into_iter(), Mode::Check, quiet()), 0);
+    assert!(!folder.join(CACHE_FOLDER).exists());
+    let args = vec![root, String::from("--cache")];
+    assert_eq!(run(args.into_iter(), Mode::Check, quiet()), 0);
+    assert!(folder.join(CACHE_FOLDER).join("main.json").is_file());
+}
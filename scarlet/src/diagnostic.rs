@@ -1,10 +1,20 @@
-use std::ops::Range;
+mod tests;
 
-use colored::{ColoredString, Colorize};
-
-use crate::{environment::Environment, file_tree::FileNode, item::ItemPtr};
+use std::{ops::Range, path::Path};
 
-#[derive(Clone, Copy, Debug)]
+use colored::{ColoredString, Colorize};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    environment::Environment,
+    file_tree::{self, FileNode},
+    item::ItemPtr,
+    util,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Level {
     Error,
     Warning,
@@ -57,6 +67,38 @@ impl Position {
             end: 0,
         }
     }
+
+    /// Works out which file and which lines and columns this position refers
+    /// to. Lines and columns start at 1, the end is exclusive.
+    pub fn span(&self, files: &FileNode, root: &Path) -> Span {
+        let (path, content) = files.get_file(self.file_index);
+        let (line_start, column_start) = find_line_and_column(self.start, content);
+        let (line_end, column_end) = find_line_and_column(self.end, content);
+        Span {
+            file: file_tree::source_path(root, &path)
+                .to_string_lossy()
+                .into_owned(),
+            byte_start: self.start,
+            byte_end: self.end,
+            line_start,
+            column_start,
+            line_end,
+            column_end,
+            text: content[self.range()].to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Span {
+    pub file: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+    pub text: String,
 }
 
 #[derive(Clone, Debug)]
@@ -157,6 +199,23 @@ impl Element {
     }
 }
 
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JsonElement<'a> {
+    Text { level: Level, text: &'a str },
+    GeneratedCode { level: Level, code: &'a str },
+    SourceCode { level: Level, span: Span },
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    #[serde(rename = "$message_type")]
+    message_type: &'static str,
+    level: Level,
+    message: &'a str,
+    elements: Vec<JsonElement<'a>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Diagnostic {
     elements: Vec<(Level, Element)>,
@@ -256,9 +315,88 @@ impl Diagnostic {
     pub fn with_item_info(self, item: &ItemPtr, ctx: &ItemPtr, env: &mut Environment) -> Self {
         self.with_item(Level::Info, item, ctx, env)
     }
+
+    /// Lists the names that are most similar to one that could not be found.
+    pub fn with_candidates_info(self, wanted: &str, candidates: Vec<String>) -> Self {
+        const MAX_CANDIDATES: usize = 8;
+        if candidates.len() == 0 {
+            return self.with_text_info(format!("There are no names available here."));
+        }
+        let total = candidates.len();
+        let closest = candidates
+            .into_iter()
+            .sorted_by_key(|candidate| util::edit_distance(wanted, candidate))
+            .take(MAX_CANDIDATES)
+            .join(", ");
+        if total > MAX_CANDIDATES {
+            self.with_text_info(format!(
+                "The closest of the {} names available here are: {}",
+                total, closest
+            ))
+        } else {
+            self.with_text_info(format!("The names available here are: {}", closest))
+        }
+    }
 }
 
 impl Diagnostic {
+    /// The most severe level of any element in this diagnostic.
+    pub fn level(&self) -> Level {
+        let levels = self.elements.iter().map(|(level, _)| *level).collect_vec();
+        if levels.contains(&Level::Error) {
+            Level::Error
+        } else if levels.contains(&Level::Warning) {
+            Level::Warning
+        } else {
+            Level::Info
+        }
+    }
+
+    /// The first piece of text in this diagnostic, which summarizes it.
+    pub fn message(&self) -> &str {
+        self.elements
+            .iter()
+            .find_map(|(_, element)| match element {
+                Element::Text(text) => Some(&text[..]),
+                _ => None,
+            })
+            .unwrap_or("")
+    }
+
+    pub fn elements(&self) -> &[(Level, Element)] {
+        &self.elements[..]
+    }
+
+    /// Formats this diagnostic as a single line of JSON, the paths of any
+    /// source code it refers to are relative to the given root.
+    pub fn format_json(&self, files: &FileNode, root: &Path) -> String {
+        let elements = self
+            .elements
+            .iter()
+            .map(|(level, element)| match element {
+                Element::Text(text) => JsonElement::Text {
+                    level: *level,
+                    text,
+                },
+                Element::GeneratedCodeBlock(code) => JsonElement::GeneratedCode {
+                    level: *level,
+                    code,
+                },
+                Element::SourceCodeBlock(position) => JsonElement::SourceCode {
+                    level: *level,
+                    span: position.span(files, root),
+                },
+            })
+            .collect();
+        serde_json::to_string(&JsonDiagnostic {
+            message_type: "diagnostic",
+            level: self.level(),
+            message: self.message(),
+            elements,
+        })
+        .unwrap()
+    }
+
     pub fn format_colorful(&self, files: &FileNode) -> String {
         let mut result = String::new();
         for (level, element) in &self.elements {
@@ -0,0 +1,70 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use super::{Diagnostic, Position};
+use crate::file_tree::FileNode;
+
+fn files() -> FileNode {
+    FileNode {
+        self_content: String::new(),
+        children: vec![(
+            String::from("main"),
+            FileNode {
+                self_content: String::from("a IS VAR()\nb IS c\n"),
+                children: Vec::new(),
+            },
+        )],
+    }
+}
+
+#[test]
+fn json_diagnostic_has_level_message_and_elements() {
+    let diagnostic = Diagnostic::new()
+        .with_text_error(format!("Could not resolve c."))
+        .with_source_code_block_error(Position::new(2, 16..17))
+        .with_generated_code_block_info(format!("a"));
+    let json = diagnostic.format_json(&files(), Path::new("root"));
+    assert!(!json.contains('\n'));
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["$message_type"], "diagnostic");
+    assert_eq!(value["level"], "error");
+    assert_eq!(value["message"], "Could not resolve c.");
+    let elements = value["elements"].as_array().unwrap();
+    assert_eq!(elements.len(), 3);
+    assert_eq!(
+        elements[0],
+        json!({ "kind": "text", "level": "error", "text": "Could not resolve c." })
+    );
+    assert_eq!(
+        elements[2],
+        json!({ "kind": "generated_code", "level": "info", "code": "a" })
+    );
+    assert_eq!(elements[1]["kind"], "source_code");
+    assert_eq!(elements[1]["level"], "error");
+}
+
+#[test]
+fn json_span_has_byte_offsets_and_one_based_lines_and_columns() {
+    let diagnostic = Diagnostic::new().with_source_code_block_warning(Position::new(2, 11..17));
+    let json = diagnostic.format_json(&files(), Path::new("root"));
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["level"], "warning");
+    assert_eq!(value["message"], "");
+    let file = Path::new("root").join("main.sr");
+    assert_eq!(
+        value["elements"][0]["span"],
+        json!({
+            "file": file.to_string_lossy(),
+            "byte_start": 11,
+            "byte_end": 17,
+            "line_start": 2,
+            "column_start": 1,
+            "line_end": 2,
+            "column_end": 7,
+            "text": "b IS c",
+        })
+    );
+}
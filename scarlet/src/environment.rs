@@ -1,7 +1,9 @@
+pub mod notation;
 pub mod vomit;
 
 use std::collections::HashMap;
 
+use self::notation::Notation;
 use crate::item::{definitions::other::DOther, Item, ItemDefinition, ItemPtr};
 
 #[cfg(not(feature = "no_axioms"))]
@@ -19,22 +21,42 @@ pub const LANGUAGE_ITEM_NAMES: &[&str] = &[
     "refl_t_statement",
     "decision_eq_t_statement",
     "decision_neq_t_statement",
+    "nat_zero",
+    "nat_succ",
+    "nat_add",
 ];
 
 #[cfg(feature = "no_axioms")]
-pub const LANGUAGE_ITEM_NAMES: &[&str] = &["true", "false", "void", "x", "and"];
+pub const LANGUAGE_ITEM_NAMES: &[&str] = &[
+    "true", "false", "void", "x", "and", "nat_zero", "nat_succ", "nat_add",
+];
 
 #[derive(Debug)]
 pub struct Environment {
     language_items: HashMap<&'static str, ItemPtr>,
-    pub(super) auto_theorems: Vec<ItemPtr>,
+    /// The auto theorems followed by the theorems named in BY hints.
+    theorems: Vec<ItemPtr>,
+    auto_theorem_count: usize,
+    /// Every item named in a BY hint, in the order they were written.
+    hints: Vec<ItemPtr>,
+    /// The root of every source tree that was loaded, by name.
+    modules: Vec<(String, ItemPtr)>,
+    imports: Vec<ItemPtr>,
+    /// Every notation declared in source code, in the order they were
+    /// declared.
+    notations: Vec<Notation>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         let mut this = Self {
             language_items: HashMap::new(),
-            auto_theorems: Vec::new(),
+            theorems: Vec::new(),
+            auto_theorem_count: 0,
+            hints: Vec::new(),
+            modules: Vec::new(),
+            imports: Vec::new(),
+            notations: Vec::new(),
         };
         for &name in LANGUAGE_ITEM_NAMES {
             let id = Item::placeholder();
@@ -66,11 +88,93 @@ impl Environment {
         self.get_language_item("void").unwrap()
     }
 
+    /// Returns the name of the language item that is the given item, if any.
+    pub(crate) fn language_item_name(&self, item: &ItemPtr) -> Option<&'static str> {
+        self.language_items
+            .iter()
+            .find(|(_, language_item)| language_item.is_same_instance_as(item))
+            .map(|(name, _)| *name)
+    }
+
     pub(crate) fn language_item_names(&self) -> impl Iterator<Item = &'static str> {
         LANGUAGE_ITEM_NAMES.iter().copied()
     }
 
     pub(crate) fn add_auto_theorem(&mut self, auto_theorem: ItemPtr) {
-        self.auto_theorems.push(auto_theorem)
+        self.theorems.insert(self.auto_theorem_count, auto_theorem);
+        self.auto_theorem_count += 1;
+    }
+
+    /// Hints aren't members of the structs they are written in, so they are
+    /// kept here to make sure they get resolved.
+    pub(crate) fn add_hint(&mut self, hint: ItemPtr) {
+        self.hints.push(hint)
+    }
+
+    pub(crate) fn hints(&self) -> &[ItemPtr] {
+        &self.hints
+    }
+
+    /// Adds the theorem each hint names to the instantiable theorems, unless
+    /// it is already one of them. Called once the hints are resolved, since
+    /// that is when it becomes clear which hints name the same theorem.
+    pub(crate) fn add_hinted_theorems(&mut self) {
+        for hint in self.hints.clone() {
+            if self.theorem_index(&hint).is_none() {
+                self.theorems.push(hint);
+            }
+        }
+    }
+
+    pub(crate) fn auto_theorem_count(&self) -> usize {
+        self.auto_theorem_count
+    }
+
+    /// Theorems which the justifier can instantiate, which are the auto
+    /// theorems followed by everything named in a BY hint. Certificates refer
+    /// to them by their index in this list.
+    pub(crate) fn instantiable_theorems(&self) -> &[ItemPtr] {
+        &self.theorems
+    }
+
+    /// The index in the instantiable theorems of the theorem the item refers
+    /// to, if it is one of them.
+    pub(crate) fn theorem_index(&self, item: &ItemPtr) -> Option<usize> {
+        let theorem = item.dereference();
+        self.theorems
+            .iter()
+            .position(|other| other.dereference().is_same_instance_as(&theorem))
+    }
+
+    pub(crate) fn define_module(&mut self, name: String, root: ItemPtr) {
+        self.modules.push((name, root))
+    }
+
+    pub(crate) fn get_module(&self, name: &str) -> Option<&ItemPtr> {
+        self.modules
+            .iter()
+            .find(|(module_name, _)| module_name == name)
+            .map(|(_, root)| root)
+    }
+
+    pub(crate) fn module_names(&self) -> Vec<String> {
+        self.modules.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub(crate) fn module_roots(&self) -> Vec<ItemPtr> {
+        self.modules
+            .iter()
+            .map(|(_, root)| root.ptr_clone())
+            .collect()
+    }
+
+    /// Imports aren't members of the structs they are written in, so they are
+    /// kept here to make sure they get resolved.
+    pub(crate) fn add_import(&mut self, import: ItemPtr) {
+        self.imports.push(import)
+    }
+
+    pub(crate) fn imports(&self) -> &[ItemPtr] {
+        &self.imports
     }
 }
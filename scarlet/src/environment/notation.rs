@@ -0,0 +1,135 @@
+use super::{vomit::VomitContext, Environment};
+use crate::{
+    item::{definitions::variable::DVariable, equality::Equal, ItemPtr},
+    parser::{Node, NodeChild, NotationPiece, NotationSyntax},
+    scope::Scope,
+    util::PtrExtension,
+};
+
+/// A notation declared in source code, kept so that items can be shown using
+/// it.
+#[derive(Debug)]
+pub struct Notation {
+    pub syntax: NotationSyntax,
+    /// What the notation means, with a variable in place of each operand.
+    pub template: ItemPtr,
+    /// The variable for each operand, in the same order as in the pattern.
+    pub operands: Vec<ItemPtr>,
+    /// The struct made from the file the notation was declared in. It can
+    /// only be used inside that file and the files in the folder next to it.
+    pub declared_in: ItemPtr,
+}
+
+/// Checks whether the scope is inside the item.
+fn is_inside(scope: &dyn Scope, item: &ItemPtr) -> bool {
+    let mut parent = scope.parent();
+    while let Some(next) = parent {
+        if next.is_same_instance_as(item) {
+            return true;
+        }
+        parent = next.borrow().scope.parent();
+    }
+    false
+}
+
+impl Environment {
+    pub(crate) fn add_notation(&mut self, notation: Notation) {
+        self.notations.push(notation)
+    }
+
+    pub(crate) fn notation_syntaxes(&self) -> Vec<NotationSyntax> {
+        self.notations
+            .iter()
+            .map(|notation| notation.syntax.clone())
+            .collect()
+    }
+
+    /// Templates aren't part of anything else, so they are kept here to make
+    /// sure they get resolved.
+    pub(crate) fn notation_items(&self) -> Vec<ItemPtr> {
+        self.notations
+            .iter()
+            .flat_map(|notation| {
+                std::iter::once(notation.template.ptr_clone())
+                    .chain(notation.operands.iter().map(ItemPtr::ptr_clone))
+            })
+            .collect()
+    }
+
+    /// Shows the item using a notation whose template it is an instance of,
+    /// trying them in order of priority. Only notations which can be used
+    /// where the item is being shown are considered.
+    pub(super) fn vomit_notation<'a>(
+        &mut self,
+        max_precedence: u8,
+        ctx: &mut VomitContext<'a, '_>,
+        item: &ItemPtr,
+    ) -> Option<Node<'a>> {
+        let mut candidates = self
+            .notations
+            .iter()
+            .filter(|notation| notation.syntax.precedence <= max_precedence)
+            .filter(|notation| is_inside(ctx.scope, &notation.declared_in))
+            .map(|notation| {
+                (
+                    notation.syntax.clone(),
+                    notation.template.ptr_clone(),
+                    notation.operands.iter().map(ItemPtr::ptr_clone).collect(),
+                )
+            })
+            .collect::<Vec<(NotationSyntax, _, Vec<_>)>>();
+        candidates.sort_by_key(|(syntax, _, _)| syntax.priority);
+        'notations: for (syntax, template, operands) in candidates {
+            let texts_can_be_shown = syntax.pieces.iter().all(|piece| match piece {
+                NotationPiece::Text(regex) => NotationSyntax::shown_text(regex).is_some(),
+                NotationPiece::Operand(..) => true,
+            });
+            if !texts_can_be_shown {
+                continue;
+            }
+            let subs = match template.get_trimmed_equality(item) {
+                Ok(Equal::Yes(subs, other_subs)) if other_subs.len() == 0 => subs,
+                _ => continue,
+            };
+            let mut values = Vec::new();
+            for operand in &operands {
+                let var = match operand.downcast_definition::<DVariable>() {
+                    Some(var) => var.get_variable().ptr_clone(),
+                    None => continue 'notations,
+                };
+                match subs.get(&var) {
+                    // An item that is an instance of a template with itself
+                    // as an operand would be shown forever.
+                    Some(value) if !value.dereference().is_same_instance_as(item) => {
+                        values.push(value.ptr_clone())
+                    }
+                    _ => continue 'notations,
+                }
+            }
+            if subs.len() != values.len() {
+                continue;
+            }
+            let mut values = values.into_iter();
+            let mut children = Vec::new();
+            for (index, piece) in syntax.pieces.iter().enumerate() {
+                match piece {
+                    NotationPiece::Operand(..) => {
+                        let value = values.next().unwrap();
+                        let prec = syntax.operand_precedence(index);
+                        children.push(NodeChild::Node(self.vomit(prec, ctx, value)));
+                    }
+                    NotationPiece::Text(regex) => {
+                        let text = NotationSyntax::shown_text(regex).unwrap();
+                        children.push(NodeChild::Text(ctx.code_arena.alloc(text)));
+                    }
+                }
+            }
+            return Some(Node {
+                phrase: syntax.name,
+                children,
+                ..Default::default()
+            });
+        }
+        None
+    }
+}
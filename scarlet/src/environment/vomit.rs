@@ -4,6 +4,7 @@ use super::{Environment, ItemPtr};
 use crate::{
     item::{
         definitions::variable::{SVariableInvariants, VariablePtr},
+        normalize::{out_of_shown_steps, SHOWN_NORMALIZE_STEPS},
         resolvable::DResolvable,
     },
     parser::{Node, NodeChild, ParseContext},
@@ -57,16 +58,29 @@ impl<'x, 'y> VomitContext<'x, 'y> {
 }
 
 impl Environment {
-    pub fn show_all_requested(&mut self, root: &ItemPtr) {
+    /// Returns every item marked with SHOWN alongside how it should be shown.
+    pub fn show_all_requested(&mut self, root: &ItemPtr) -> Vec<(ItemPtr, String)> {
         let mut to_vomit: Vec<(ItemPtr, ItemPtr)> = Vec::new();
         root.for_self_and_deep_contents(&mut |item| {
             if item.borrow().show && !to_vomit.iter().any(|x| x.0.is_same_instance_as(item)) {
                 to_vomit.push((item.ptr_clone(), item.ptr_clone()));
             }
         });
-        for (item_id, from) in to_vomit {
-            println!("{}", self.show(item_id, from));
-        }
+        to_vomit
+            .into_iter()
+            .map(|(item_id, from)| {
+                if !item_id.borrow().show_normalized {
+                    return (item_id.ptr_clone(), self.show(item_id, from));
+                }
+                let normalized = item_id.normalize(SHOWN_NORMALIZE_STEPS);
+                let mut shown = self.show(normalized.item, from);
+                if normalized.out_of_steps {
+                    shown.push('\n');
+                    shown.push_str(&out_of_shown_steps());
+                }
+                (item_id, shown)
+            })
+            .collect()
     }
 
     pub fn show(&mut self, item_id: ItemPtr, from_item: ItemPtr) -> String {
@@ -74,7 +88,7 @@ impl Environment {
 
         let from = from_item.clone_scope();
         let code_arena = Arena::new();
-        let pc = ParseContext::new();
+        let pc = ParseContext::with_notations(self.notation_syntaxes());
         let mut temp_names = OrderedMap::new();
         let mut ctx = VomitContext {
             pc: &pc,
@@ -150,6 +164,9 @@ impl Environment {
                 ..Default::default()
             };
         }
+        if let Some(node) = self.vomit_notation(max_precedence, ctx, &item_ptr) {
+            return node;
+        }
         for (_, phrase) in &ctx.pc.phrases_sorted_by_vomit_priority {
             if phrase.precedence > max_precedence {
                 continue;
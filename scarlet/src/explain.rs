@@ -0,0 +1,97 @@
+//! The `explain` subcommand, which shows the invariants of items with a
+//! particular name and what each of their requirements was justified by.
+
+use std::path::Path;
+
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    item::ItemPtr,
+    output::Output,
+    pipeline::{self, Stage},
+    CommandArgs, USAGE_ERROR,
+};
+
+/// Explains the items named in the arguments, returning the exit code the
+/// process should use. The code must justify successfully first.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let mut positional = match CommandArgs::parse(args, &[], 2) {
+        Ok(args) => args.positional,
+        Err(code) => return code,
+    };
+    if positional.len() < 2 {
+        eprintln!("Usage: scarlet explain <root> <name>");
+        return USAGE_ERROR;
+    }
+    let name = positional.pop().unwrap();
+    let root = positional.pop().unwrap();
+    let mut analysis = pipeline::analyze(Path::new(&root));
+    for diagnostic in &analysis.diagnostics {
+        output.report(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    let root = match (analysis.stage, &analysis.root) {
+        (Stage::Justified, Some(root)) => root.ptr_clone(),
+        _ => return 1,
+    };
+    let mut named = Vec::new();
+    root.for_self_and_deep_contents(&mut |item| {
+        if item.borrow().name.as_deref() == Some(&name[..]) {
+            named.push(item.ptr_clone());
+        }
+    });
+    if named.len() == 0 {
+        let diagnostic = Diagnostic::new()
+            .with_text_error(format!("Could not find anything named \"{}\"", name));
+        output.report(&diagnostic, &analysis.file_tree, &analysis.base);
+        return 1;
+    }
+    for item in named {
+        let diagnostic = explain(&item, &root, &mut analysis.env);
+        output.report(&diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    0
+}
+
+fn explain(item: &ItemPtr, root: &ItemPtr, env: &mut Environment) -> Diagnostic {
+    let mut d = Diagnostic::new()
+        .with_text_info(format!("This item:"))
+        .with_item_info(item, root, env);
+    let set = match item.get_invariants() {
+        Ok(set) => set.borrow().clone(),
+        Err(_) => return d.with_text_info(format!("Has invariants which could not be found.")),
+    };
+    if set.statements().len() == 0 {
+        d = d.with_text_info(format!("Has no invariants."));
+    } else {
+        d = d.with_text_info(format!("Has these invariants:"));
+        for statement in set.statements() {
+            d = d.with_item_info(statement, item, env);
+        }
+    }
+    if set.justification_requirements().len() == 0 {
+        return d;
+    }
+    let used = match set.used_justification() {
+        Some(used) => used,
+        None => {
+            d = d.with_text_info(format!(
+                "Which would hold if these were justified, but they were not:"
+            ));
+            for requirement in set.justification_requirements() {
+                d = d.with_item_info(requirement, item, env);
+            }
+            return d;
+        }
+    };
+    for (requirement, justification) in set.justification_requirements().iter().zip(used) {
+        d = d
+            .with_text_info(format!("Which relies on this being true:"))
+            .with_item_info(requirement, item, env)
+            .with_text_info(format!("Which it is because of the invariants of:"));
+        for other in justification {
+            let context = other.borrow().context().ptr_clone();
+            d = d.with_item_info(&context, root, env);
+        }
+    }
+    d
+}
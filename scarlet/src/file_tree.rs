@@ -1,4 +1,7 @@
-use std::{fs::FileType, path::Path};
+use std::{
+    fs::FileType,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Clone)]
 pub struct FileNode {
@@ -24,6 +27,18 @@ impl FileNode {
     pub fn get_file(&self, index: usize) -> (String, &str) {
         self.get_file_impl(&mut (index - 1)).unwrap()
     }
+
+    /// Returns the path of every file in the same order as the indexes used by
+    /// get_file, so the path at position 0 belongs to index 1.
+    pub fn paths(&self) -> Vec<String> {
+        let mut result = vec![String::new()];
+        for (name, child) in &self.children {
+            for path in child.paths() {
+                result.push(format!("/{}{}", name, path));
+            }
+        }
+        result
+    }
 }
 
 fn read_folder_contents(at: &Path) -> Vec<(String, FileNode)> {
@@ -79,6 +94,18 @@ fn read_path(at: &Path) -> Option<FileNode> {
     }
 }
 
+/// Converts a path returned by FileNode::get_file into the path of the file it
+/// was read from.
+pub fn source_path(root: &Path, path: &str) -> PathBuf {
+    let mut result = root.to_owned();
+    if path.len() > 0 {
+        result.push(&path[1..]);
+    }
+    let mut result = result.into_os_string();
+    result.push(".sr");
+    result.into()
+}
+
 pub fn read_root<'a>(at: impl AsRef<Path>) -> Option<FileNode> {
     let root_path = at.as_ref();
     read_path(&root_path)
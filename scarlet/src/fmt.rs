@@ -0,0 +1,86 @@
+//! The `fmt` subcommand, which rewrites every file under a root in the
+//! canonical layout.
+
+use std::path::Path;
+
+use crate::{
+    file_tree::{self, FileNode},
+    output::{Output, Verbosity},
+    parser::{self, ParseContext},
+    CommandArgs,
+};
+
+/// Formats every file under the root given in the arguments, returning the
+/// exit code the process should use. With `--check`, files are left alone and
+/// the exit code says whether any of them would have been changed.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let args = match CommandArgs::parse(args, &["--check"], 1) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+    let check = args.has("--check");
+    let root = args.path(0);
+    let root = Path::new(&root);
+    let file_tree = if let Some(file_tree) = file_tree::read_root(root) {
+        file_tree
+    } else {
+        eprintln!("Could not find any source code at {}", root.display());
+        return 1;
+    };
+    format_tree(&file_tree, root, check, output)
+}
+
+fn format_tree(file_tree: &FileNode, root: &Path, check: bool, output: Output) -> i32 {
+    // Notations declared in a file can be used in the folder next to it, so
+    // each file is parsed with the context of the closest file above it.
+    let mut contexts = vec![(String::new(), ParseContext::new())];
+    let mut code = 0;
+    for (index, path) in file_tree.paths().into_iter().enumerate() {
+        let file_index = index + 1;
+        let (_, content) = file_tree.get_file(file_index);
+        let above = contexts
+            .iter()
+            .rposition(|(above, _)| path.starts_with(&format!("{}/", above)))
+            .unwrap_or(0);
+        let (_, _, extended) =
+            parser::parse_with_notations(content, &contexts[above].1, file_index);
+        let ctx_index = match extended {
+            Some(extended) => {
+                contexts.push((path.clone(), extended));
+                contexts.len() - 1
+            }
+            None => above,
+        };
+        let source_path = file_tree::source_path(root, &path);
+        if !source_path.is_file() {
+            // Folders without a file of the same name.
+            continue;
+        }
+        let formatted = match parser::format_file(content, &contexts[ctx_index].1, file_index) {
+            Ok(formatted) => formatted,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    output.report(&diagnostic, file_tree, root);
+                }
+                code = 1;
+                continue;
+            }
+        };
+        if formatted == content {
+            continue;
+        }
+        if check {
+            output.report_unformatted(&source_path);
+            code = 1;
+        } else if let Err(err) = std::fs::write(&source_path, formatted) {
+            eprintln!("Could not write {}: {}", source_path.display(), err);
+            code = 1;
+        } else {
+            output.progress(
+                Verbosity::Normal,
+                &format!("Reformatted {}", source_path.display()),
+            );
+        }
+    }
+    code
+}
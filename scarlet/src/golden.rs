@@ -0,0 +1,261 @@
+//! The `test` subcommand, which runs every root in a folder and compares what
+//! comes out with annotations written in comments next to the code:
+//!
+//! ```text
+//! a IS b #~ ERROR Could not find b
+//! a SHOWN #~ SHOWN a
+//! #~^ WARNING Something about the line above
+//! ```
+//!
+//! Each annotation refers to the line it is on, or one line further up for
+//! each `^` after the `#~`. Errors and warnings match any diagnostic whose
+//! first source code block starts on that line and whose text contains the
+//! rest of the annotation. Shown items match if they are shown the same way,
+//! ignoring differences in whitespace. Anything reported that isn't
+//! annotated is a failure, as is any annotation that nothing matched.
+
+mod tests;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostic::{Diagnostic, Element, Level, Position},
+    output::{Output, Verbosity},
+    pipeline::{self, Analysis},
+    CommandArgs,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+    Error,
+    Warning,
+    Shown,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Error => "ERROR",
+            Kind::Warning => "WARNING",
+            Kind::Shown => "SHOWN",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Annotation {
+    kind: Kind,
+    file_index: usize,
+    line: usize,
+    text: String,
+}
+
+/// Runs every root in the folder given in the arguments, returning the exit
+/// code the process should use.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let folder = match CommandArgs::parse(args, &[], 1) {
+        Ok(args) => args.path(0),
+        Err(code) => return code,
+    };
+    let roots = match find_roots(Path::new(&folder)) {
+        Ok(roots) => roots,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", folder, err);
+            return 1;
+        }
+    };
+    let mut failed = 0;
+    for root in &roots {
+        let failures = check_root(root);
+        if !failures.is_empty() {
+            failed += 1;
+        }
+        output.report_test(root, &failures);
+    }
+    output.progress(
+        Verbosity::Normal,
+        &format!("{} passed, {} failed", roots.len() - failed, failed),
+    );
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+/// Finds every `.sr` file directly inside a folder, in alphabetical order.
+fn find_roots(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut roots = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map(|ext| ext == "sr").unwrap_or(false) {
+            roots.push(path);
+        }
+    }
+    roots.sort();
+    Ok(roots)
+}
+
+/// Runs a root through the whole pipeline, returning a description of each
+/// way the result didn't match the annotations.
+fn check_root(path: &Path) -> Vec<String> {
+    let mut analysis = pipeline::analyze(path);
+    let mut failures = Vec::new();
+    let mut annotations = find_annotations(&analysis, &mut failures);
+    for diagnostic in &analysis.diagnostics {
+        let kind = match diagnostic.level() {
+            Level::Error => Kind::Error,
+            Level::Warning => Kind::Warning,
+            Level::Info => continue,
+        };
+        let location = first_location(&analysis, diagnostic);
+        let text = diagnostic_text(diagnostic);
+        let found = take_annotation(&mut annotations, kind, location, |expected| {
+            text.contains(expected)
+        });
+        if !found {
+            failures.push(format!(
+                "Unexpected {} at {}: {}",
+                kind.name(),
+                describe(&analysis, location),
+                text
+            ));
+        }
+    }
+    let shown = analysis.shown();
+    for (item, text) in shown {
+        let position = item.borrow().position;
+        let location = position.map(|position| location_of(&analysis, position));
+        let text = collapse_whitespace(&text);
+        let found = take_annotation(&mut annotations, Kind::Shown, location, |expected| {
+            collapse_whitespace(expected) == text
+        });
+        if !found {
+            failures.push(format!(
+                "Unexpected SHOWN at {}: {}",
+                describe(&analysis, location),
+                text
+            ));
+        }
+    }
+    for annotation in annotations {
+        failures.push(format!(
+            "Expected {} at {} but nothing matched: {}",
+            annotation.kind.name(),
+            describe(&analysis, Some((annotation.file_index, annotation.line))),
+            annotation.text
+        ));
+    }
+    failures
+}
+
+/// Removes the first annotation of the given kind at the given location which
+/// accepts the text. Returns false if there wasn't one.
+fn take_annotation(
+    annotations: &mut Vec<Annotation>,
+    kind: Kind,
+    location: Option<(usize, usize)>,
+    accepts: impl Fn(&str) -> bool,
+) -> bool {
+    let index = annotations.iter().position(|annotation| {
+        annotation.kind == kind
+            && Some((annotation.file_index, annotation.line)) == location
+            && accepts(&annotation.text)
+    });
+    match index {
+        Some(index) => {
+            annotations.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+fn find_annotations(analysis: &Analysis, failures: &mut Vec<String>) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    let file_count = analysis.file_tree.paths().len();
+    for file_index in 1..=file_count {
+        let (_, content) = analysis.file_tree.get_file(file_index);
+        for (index, line) in content.lines().enumerate() {
+            let rest = match line.find("#~") {
+                Some(start) => &line[start + 2..],
+                None => continue,
+            };
+            let up = rest.chars().take_while(|&c| c == '^').count();
+            let rest = rest[up..].trim();
+            let (kind, text) = match rest.split_once(' ') {
+                Some((kind, text)) => (kind, text.trim()),
+                None => (rest, ""),
+            };
+            let line = (index + 1).saturating_sub(up);
+            let kind = match kind {
+                "ERROR" => Kind::Error,
+                "WARNING" => Kind::Warning,
+                "SHOWN" => Kind::Shown,
+                _ => {
+                    failures.push(format!(
+                        "Unknown annotation at {}: {}",
+                        describe(analysis, Some((file_index, line))),
+                        rest
+                    ));
+                    continue;
+                }
+            };
+            annotations.push(Annotation {
+                kind,
+                file_index,
+                line,
+                text: text.to_owned(),
+            });
+        }
+    }
+    annotations
+}
+
+/// The file and line where the first source code block in a diagnostic
+/// starts.
+fn first_location(analysis: &Analysis, diagnostic: &Diagnostic) -> Option<(usize, usize)> {
+    diagnostic
+        .elements()
+        .iter()
+        .find_map(|(_, element)| match element {
+            Element::SourceCodeBlock(position) => Some(location_of(analysis, *position)),
+            _ => None,
+        })
+}
+
+fn location_of(analysis: &Analysis, position: Position) -> (usize, usize) {
+    let file_index = position.file_index();
+    let (_, content) = analysis.file_tree.get_file(file_index);
+    let start = position.range().start.min(content.len());
+    (file_index, content[..start].matches('\n').count() + 1)
+}
+
+fn diagnostic_text(diagnostic: &Diagnostic) -> String {
+    let texts = diagnostic
+        .elements()
+        .iter()
+        .filter_map(|(_, element)| match element {
+            Element::Text(text) => Some(&text[..]),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    texts.join(" ")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn describe(analysis: &Analysis, location: Option<(usize, usize)>) -> String {
+    match location {
+        Some((file_index, line)) => {
+            let (path, _) = analysis.file_tree.get_file(file_index);
+            format!("{}:{}", path, line)
+        }
+        None => format!("an unknown location"),
+    }
+}
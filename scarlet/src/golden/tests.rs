@@ -0,0 +1,19 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use super::{check_root, find_roots};
+
+#[test]
+fn golden_files() {
+    let folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+    let roots = find_roots(&folder).unwrap();
+    assert!(roots.len() > 0);
+    let mut failures = Vec::new();
+    for root in roots {
+        for failure in check_root(&root) {
+            failures.push(format!("{}: {}", root.display(), failure));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
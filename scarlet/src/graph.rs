@@ -0,0 +1,350 @@
+//! The `graph` subcommand, which writes the dependencies of the items in a
+//! root and how their invariants were justified as Graphviz DOT files.
+
+mod tests;
+
+use std::{fs, path::Path};
+
+use crate::{
+    environment::Environment,
+    file_tree::FileNode,
+    item::{definitions::variable::DVariable, invariants::InvariantSetPtr, ItemPtr},
+    output::{Output, Verbosity},
+    pipeline::{self, Stage},
+    util::PtrExtension,
+    CommandArgs,
+};
+
+const DEPENDENCIES_FILE: &str = "dependencies.dot";
+const JUSTIFICATIONS_FILE: &str = "justifications.dot";
+/// Labels made from shown code are cut short after this many characters.
+const MAX_LABEL_LENGTH: usize = 40;
+
+/// A directed graph which only knows how to write itself as DOT.
+struct Graph {
+    name: &'static str,
+    /// The label and extra attributes of each node.
+    nodes: Vec<(String, &'static str)>,
+    /// The nodes each edge goes between, with its label and extra attributes.
+    edges: Vec<(usize, usize, String, &'static str)>,
+}
+
+impl Graph {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut result = format!("digraph {} {{\n", self.name);
+        result.push_str("    node [shape=box];\n");
+        for (index, (label, attributes)) in self.nodes.iter().enumerate() {
+            result.push_str(&format!("    n{} [label=\"{}\"", index, escape(label)));
+            push_attributes(&mut result, attributes);
+        }
+        for (from, to, label, attributes) in &self.edges {
+            result.push_str(&format!("    n{} -> n{}", from, to));
+            if label.len() > 0 {
+                result.push_str(&format!(" [label=\"{}\"", escape(label)));
+                push_attributes(&mut result, attributes);
+            } else if attributes.len() > 0 {
+                result.push_str(&format!(" [{}];\n", attributes));
+            } else {
+                result.push_str(";\n");
+            }
+        }
+        result.push_str("}\n");
+        result
+    }
+}
+
+/// Finishes a list of attributes that has already been started.
+fn push_attributes(to: &mut String, attributes: &str) {
+    if attributes.len() > 0 {
+        to.push_str(", ");
+        to.push_str(attributes);
+    }
+    to.push_str("];\n");
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Labels items with their names, or their source code if they don't have
+/// one, followed by where they are in it. Items which were generated rather
+/// than written are labelled with how they are shown.
+struct Labeller<'a> {
+    env: &'a mut Environment,
+    root: &'a ItemPtr,
+    file_tree: &'a FileNode,
+    base: &'a Path,
+    /// Every named item in the root alongside what it dereferences to, so
+    /// that variables can be labelled with the names they were declared with.
+    named: Vec<(ItemPtr, ItemPtr)>,
+}
+
+impl<'a> Labeller<'a> {
+    fn new(
+        env: &'a mut Environment,
+        root: &'a ItemPtr,
+        file_tree: &'a FileNode,
+        base: &'a Path,
+    ) -> Self {
+        let mut named = Vec::new();
+        root.for_self_and_deep_contents(&mut |item| {
+            if item.borrow().name.is_some() {
+                named.push((item.dereference(), item.ptr_clone()));
+            }
+        });
+        Self {
+            env,
+            root,
+            file_tree,
+            base,
+            named,
+        }
+    }
+
+    fn label(&mut self, item: &ItemPtr) -> String {
+        let declared = match self
+            .named
+            .iter()
+            .find(|(target, _)| target.is_same_instance_as(item))
+        {
+            Some((_, named)) if item.borrow().name.is_none() => named.ptr_clone(),
+            _ => item.ptr_clone(),
+        };
+        let (name, position) = {
+            let declared = declared.borrow();
+            (declared.name.clone(), declared.position)
+        };
+        let span = position.map(|position| position.span(self.file_tree, self.base));
+        let mut label = match (name, &span) {
+            (Some(name), _) => name,
+            (None, Some(span)) => shorten(span.text.lines().next().unwrap_or("")),
+            (None, None) => {
+                let shown = self.env.show(item.ptr_clone(), self.root.ptr_clone());
+                shorten(shown.lines().next().unwrap_or(""))
+            }
+        };
+        if let Some(span) = span {
+            label.push_str(&format!(
+                "\n{}:{}:{}",
+                span.file, span.line_start, span.column_start
+            ));
+        }
+        label
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() > MAX_LABEL_LENGTH {
+        let mut result = text.chars().take(MAX_LABEL_LENGTH).collect::<String>();
+        result.push_str("...");
+        result
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Finds where the value is in the list, adding it to the end if it is not
+/// there yet. Also returns whether it was added.
+fn index_of<T: Clone>(list: &mut Vec<T>, value: &T, same: fn(&T, &T) -> bool) -> (usize, bool) {
+    match list.iter().position(|other| same(other, value)) {
+        Some(index) => (index, false),
+        None => {
+            list.push(value.clone());
+            (list.len() - 1, true)
+        }
+    }
+}
+
+/// Every named item in the root is connected to the variables it depends on,
+/// and each of those to the variables it must be given after, which are the
+/// ones its own dependencies depend on.
+fn dependency_graph(labeller: &mut Labeller) -> String {
+    let mut graph = Graph::new("dependencies");
+    let mut items = Vec::new();
+    let named = labeller
+        .named
+        .iter()
+        .map(|(_, item)| item.ptr_clone())
+        .collect::<Vec<_>>();
+    for item in named {
+        let (index, new) = index_of(&mut items, &item, ItemPtr::is_same_instance_as);
+        if !new {
+            continue;
+        }
+        graph.nodes.push((labeller.label(&item), ""));
+        for dep in item.get_dependencies().as_variables() {
+            let var_item = dep.var.borrow().item().ptr_clone();
+            let (var_index, new) = index_of(&mut items, &var_item, ItemPtr::is_same_instance_as);
+            if new {
+                graph
+                    .nodes
+                    .push((labeller.label(&var_item), "shape=ellipse"));
+            }
+            let attributes = if dep.affects_return_value {
+                ""
+            } else {
+                "style=dotted"
+            };
+            graph
+                .edges
+                .push((index, var_index, String::new(), attributes));
+        }
+    }
+    // Variables are only added after the items above, so this reaches every
+    // variable that the variables depend on as well.
+    let mut index = 0;
+    while index < items.len() {
+        let var = match items[index].downcast_definition::<DVariable>() {
+            Some(var) => var.get_variable().ptr_clone(),
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+        let var_dependencies = var.borrow().dependencies().to_vec();
+        for dependency in var_dependencies {
+            for dep in dependency.get_dependencies().as_variables() {
+                let var_item = dep.var.borrow().item().ptr_clone();
+                let (dep_index, new) =
+                    index_of(&mut items, &var_item, ItemPtr::is_same_instance_as);
+                if new {
+                    graph
+                        .nodes
+                        .push((labeller.label(&var_item), "shape=ellipse"));
+                }
+                if dep_index != index {
+                    graph
+                        .edges
+                        .push((index, dep_index, String::new(), "style=dashed"));
+                }
+            }
+        }
+        index += 1;
+    }
+    graph.to_dot()
+}
+
+/// The invariant set of every named item in the root is connected to the sets
+/// that justified each of its requirements, with the edges labelled by the
+/// requirement. Sets outside the root are included but not followed any
+/// further, and sets which could not be justified are colored red.
+fn justification_graph(labeller: &mut Labeller) -> String {
+    let mut graph = Graph::new("justifications");
+    let mut in_root = Vec::new();
+    for (_, item) in &labeller.named {
+        if let Ok(set) = item.get_invariants() {
+            let is_empty = {
+                let set = set.borrow();
+                set.statements().len() == 0 && set.justification_requirements().len() == 0
+            };
+            if !is_empty {
+                in_root.push(set);
+            }
+        }
+    }
+    let mut sets: Vec<InvariantSetPtr> = Vec::new();
+    for set in in_root {
+        let (index, new) = index_of(&mut sets, &set, InvariantSetPtr::is_same_instance_as);
+        if !new {
+            continue;
+        }
+        let set = set.borrow().clone();
+        let unjustified =
+            set.justification_requirements().len() > 0 && set.used_justification().is_none();
+        let attributes = if unjustified { "color=red" } else { "" };
+        graph.nodes.push((
+            set_label(labeller, set.context(), set.statements()),
+            attributes,
+        ));
+        let used = match set.used_justification() {
+            Some(used) => used,
+            None => continue,
+        };
+        for (requirement, justification) in set.justification_requirements().iter().zip(used) {
+            let shown = labeller
+                .env
+                .show(requirement.ptr_clone(), set.context().ptr_clone());
+            let label = shorten(shown.lines().next().unwrap_or(""));
+            for other in justification {
+                let (other_index, new) =
+                    index_of(&mut sets, other, InvariantSetPtr::is_same_instance_as);
+                if new {
+                    let other = other.borrow();
+                    let label = set_label(labeller, other.context(), other.statements());
+                    graph.nodes.push((label, "style=dashed"));
+                }
+                graph.edges.push((index, other_index, label.clone(), ""));
+            }
+        }
+    }
+    graph.to_dot()
+}
+
+fn set_label(labeller: &mut Labeller, context: &ItemPtr, statements: &[ItemPtr]) -> String {
+    let mut label = labeller.label(context);
+    for statement in statements {
+        let shown = labeller
+            .env
+            .show(statement.ptr_clone(), context.ptr_clone());
+        label.push_str(&format!(
+            "\n{}",
+            shorten(shown.lines().next().unwrap_or(""))
+        ));
+    }
+    label
+}
+
+/// Writes the graphs for the root given in the arguments, returning the exit
+/// code the process should use. The graphs are written even if justification
+/// fails, since they are most useful for finding out why it did.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let args = match CommandArgs::parse(args, &["--out="], 1) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+    let root = args.path(0);
+    let out = args.value("--out=").unwrap_or(".");
+    let mut analysis = pipeline::analyze(Path::new(&root));
+    for diagnostic in &analysis.diagnostics {
+        output.report(diagnostic, &analysis.file_tree, &analysis.base);
+    }
+    let root = match (analysis.stage, &analysis.root) {
+        (stage, Some(root)) if stage >= Stage::Checked => root.ptr_clone(),
+        _ => return 1,
+    };
+    let mut labeller = Labeller::new(
+        &mut analysis.env,
+        &root,
+        &analysis.file_tree,
+        &analysis.base,
+    );
+    let graphs = [
+        (DEPENDENCIES_FILE, dependency_graph(&mut labeller)),
+        (JUSTIFICATIONS_FILE, justification_graph(&mut labeller)),
+    ];
+    let out = Path::new(out);
+    if let Err(err) = fs::create_dir_all(out) {
+        eprintln!("Could not create {}: {}", out.display(), err);
+        return 1;
+    }
+    for (name, graph) in graphs {
+        let path = out.join(name);
+        if let Err(err) = fs::write(&path, graph) {
+            eprintln!("Could not write {}: {}", path.display(), err);
+            return 1;
+        }
+        output.progress(Verbosity::Normal, &format!("Wrote {}", path.display()));
+    }
+    0
+}
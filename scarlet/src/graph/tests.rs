@@ -0,0 +1,91 @@
+#![cfg(test)]
+
+use std::fs;
+
+use super::{dependency_graph, escape, justification_graph, Labeller};
+use crate::{
+    item::test_util::{TempFolder, AXIOM_CODE, LANGUAGE_ITEMS},
+    pipeline::{self, Analysis, Stage},
+};
+
+fn code() -> String {
+    format!(
+        "{}{}\np IS VAR()\nq IS VAR(DEP p)\npair IS p = q\n",
+        LANGUAGE_ITEMS, AXIOM_CODE
+    )
+}
+
+fn graphs(analysis: &mut Analysis) -> (String, String) {
+    let root = analysis.root.clone().unwrap();
+    let mut labeller = Labeller::new(
+        &mut analysis.env,
+        &root,
+        &analysis.file_tree,
+        &analysis.base,
+    );
+    (
+        dependency_graph(&mut labeller),
+        justification_graph(&mut labeller),
+    )
+}
+
+/// The id of the only node in the graph with the label and attributes.
+fn node(graph: &str, label: &str, attributes: &str) -> String {
+    let line = format!("[label=\"{}\"{}];", escape(label), attributes);
+    let ids = graph
+        .lines()
+        .filter_map(|node| node.trim().strip_suffix(&line))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), 1, "{}\n{}", line, graph);
+    ids[0].to_owned()
+}
+
+fn has_edge(graph: &str, from: &str, to: &str, attributes: &str) -> bool {
+    let edge = format!("{} -> {}{};", from, to, attributes);
+    graph.lines().any(|line| line.trim() == edge)
+}
+
+#[test]
+fn graphs_describe_the_root() {
+    let folder = TempFolder::new("graph");
+    let root = folder.join("main.sr");
+    fs::write(&root, code()).unwrap();
+    let mut analysis = pipeline::analyze(&root);
+    assert_eq!(analysis.stage, Stage::Justified);
+    let at = |line: usize, column: usize| format!("\n{}:{}:{}", root.display(), line, column);
+
+    let (dependencies, justifications) = graphs(&mut analysis);
+    assert!(dependencies.starts_with("digraph dependencies {"));
+    let pair = node(&dependencies, &format!("pair{}", at(17, 9)), "");
+    let p = node(&dependencies, &format!("p{}", at(15, 6)), ", shape=ellipse");
+    let q = node(&dependencies, &format!("q{}", at(16, 6)), ", shape=ellipse");
+    assert!(has_edge(&dependencies, &pair, &p, ""));
+    assert!(has_edge(&dependencies, &pair, &q, ""));
+    // q must be given after p, since its dependencies depend on p.
+    assert!(has_edge(&dependencies, &q, &p, " [style=dashed]"));
+
+    assert!(justifications.starts_with("digraph justifications {"));
+    let uses_axiom = node(
+        &justifications,
+        &format!("uses_axiom{}\ny(a)", at(13, 15)),
+        "",
+    );
+    let axiom_instance = node(
+        &justifications,
+        &format!("trivial_t(a){}\nstatement(a)", at(10, 1)),
+        ", style=dashed",
+    );
+    assert!(has_edge(
+        &justifications,
+        &uses_axiom,
+        &axiom_instance,
+        " [label=\"y(a)\"]"
+    ));
+    assert!(!justifications.contains("color=red"));
+}
+
+#[test]
+fn labels_are_escaped() {
+    assert_eq!(escape("a \"b\"\n\\c"), "a \\\"b\\\"\\n\\\\c");
+}
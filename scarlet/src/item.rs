@@ -1,7 +1,7 @@
 pub mod base;
 pub mod definitions;
 pub mod resolvable;
-mod test_util;
+pub(crate) mod test_util;
 pub mod util;
 
 pub use base::{definition::*, item::*, *};
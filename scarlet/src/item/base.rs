@@ -5,5 +5,7 @@ pub mod equality;
 mod from;
 pub mod invariants;
 pub mod item;
+pub mod normalize;
 pub mod resolve;
+pub mod structure;
 pub mod util;
@@ -1,10 +1,80 @@
-use crate::item::ItemPtr;
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    item::{
+        definitions::{
+            number::{DInteger, DNatural},
+            structt::DPopulatedStruct,
+            unique::DUnique,
+        },
+        ItemPtr,
+    },
+};
 
-pub type CheckResult = Result<(), ()>;
+pub type CheckResult = Result<(), Diagnostic>;
 
 pub trait CheckFeature {
+    /// Looks for mistakes that can be spotted without justifying anything,
+    /// like taking a member of something that can't be a struct.
     #[allow(unused_variables)]
-    fn check_self(&self, this: &ItemPtr) -> CheckResult {
+    fn check_self(&self, this: &ItemPtr, env: &mut Environment) -> CheckResult {
         Ok(())
     }
 }
+
+/// The sort of value an item is, for items where that is obvious from their
+/// definition alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Unique,
+    Struct,
+    Natural,
+    Integer,
+}
+
+impl Kind {
+    pub fn of(item: &ItemPtr) -> Option<Self> {
+        let item = item.dereference();
+        if item.downcast_definition::<DUnique>().is_some() {
+            Some(Kind::Unique)
+        } else if item.downcast_definition::<DPopulatedStruct>().is_some() {
+            Some(Kind::Struct)
+        } else if item.downcast_definition::<DNatural>().is_some() {
+            Some(Kind::Natural)
+        } else if item.downcast_definition::<DInteger>().is_some() {
+            Some(Kind::Integer)
+        } else {
+            None
+        }
+    }
+
+    /// Whether nothing of this kind can be the same as anything of the other
+    /// kind. Natural numbers are the same as their encodings using nat_zero
+    /// and nat_succ, which could be made of anything, and nothing says how
+    /// integers relate to naturals, so neither naturals nor integers are
+    /// different from anything. Equality says items are not equal whenever
+    /// this does, so that mistakes reported because of it can be proven.
+    pub fn is_different_from(self, other: Self) -> bool {
+        match (self, other) {
+            (Kind::Natural, _) | (_, Kind::Natural) => false,
+            (Kind::Integer, _) | (_, Kind::Integer) => false,
+            _ => self != other,
+        }
+    }
+
+    /// Like is_different_from, for an item whose kind might not be obvious.
+    pub fn is_different_from_item(self, item: &ItemPtr) -> bool {
+        Kind::of(item)
+            .map(|other| self.is_different_from(other))
+            .unwrap_or(false)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Kind::Unique => "a unique item",
+            Kind::Struct => "a struct",
+            Kind::Natural => "a natural number",
+            Kind::Integer => "an integer",
+        }
+    }
+}
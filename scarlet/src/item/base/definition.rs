@@ -2,7 +2,7 @@ use std::{any::Any, fmt::Debug};
 
 use super::{
     check::CheckFeature, dependencies::DependenciesFeature, equality::EqualityFeature,
-    invariants::InvariantsFeature,
+    invariants::InvariantsFeature, structure::Structure,
 };
 use crate::{item::ItemPtr, shared::AnyEq};
 
@@ -23,4 +23,11 @@ pub trait ItemDefinition:
     fn contents(&self) -> Vec<(ContainmentType, ItemPtr)> {
         vec![]
     }
+
+    /// Describes the definition for hash consing. Returns None if items with
+    /// this definition are only structurally identical to themselves, which
+    /// is the case for anything with an identity of its own like UNIQUE.
+    fn structure(&self) -> Option<Structure> {
+        None
+    }
 }
@@ -1,7 +1,8 @@
+mod cache;
 mod context;
 mod equal;
 mod feature;
 mod tests;
 mod trim;
 
-pub use self::{context::*, equal::*, feature::*};
+pub use self::{cache::clear_equality_cache, context::*, equal::*, feature::*};
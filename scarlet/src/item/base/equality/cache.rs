@@ -0,0 +1,40 @@
+//! Remembers the result of every equality check for the rest of the run.
+//! Justification compares the same pairs of statements over and over, once for
+//! each limit it tries, and the comparisons underneath those repeat even more.
+//! A result only depends on the definitions of the items involved, so the
+//! whole cache is thrown away whenever any item is redefined.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use super::Equal;
+use crate::item::ItemPtr;
+
+/// Identifies an equality check by the items on each side and the
+/// substitutions waiting to be applied to them. Variables are identified by
+/// their items. Holding the items keeps them alive, so their addresses can't
+/// be reused by other items while they are in the cache.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(super) struct EqualityKey {
+    pub(super) lhs: ItemPtr,
+    pub(super) lhs_subs: Vec<Vec<(ItemPtr, ItemPtr)>>,
+    pub(super) rhs: ItemPtr,
+    pub(super) rhs_subs: Vec<Vec<(ItemPtr, ItemPtr)>>,
+}
+
+thread_local! {
+    static CACHE: RefCell<HashMap<EqualityKey, Equal>> = RefCell::new(HashMap::new());
+}
+
+pub(super) fn get(key: &EqualityKey) -> Option<Equal> {
+    CACHE.with(|cache| cache.borrow().get(key).cloned())
+}
+
+pub(super) fn insert(key: EqualityKey, result: Equal) {
+    CACHE.with(|cache| cache.borrow_mut().insert(key, result));
+}
+
+/// Forgets every result, because an item changed or because the items they
+/// were about are no longer needed.
+pub fn clear_equality_cache() {
+    CACHE.with(|cache| cache.borrow_mut().clear());
+}
@@ -1,6 +1,10 @@
-use std::collections::HashSet;
+use std::{cell::Cell, collections::HashSet};
 
-use super::{trim::trim_result, Equal};
+use super::{
+    cache::{self, EqualityKey},
+    trim::trim_result,
+    Equal,
+};
 use crate::{
     item::{
         definitions::{
@@ -17,6 +21,17 @@ use crate::{
 
 const TRACE: bool = false;
 
+thread_local! {
+    static COPIES_FOUND: Cell<usize> = Cell::new(0);
+}
+
+/// How many equality checks so far were settled by finding that both sides
+/// are copies of the same expression.
+#[cfg(test)]
+pub fn copies_found() -> usize {
+    COPIES_FOUND.with(Cell::get)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum EqualityTestSide {
     Left,
@@ -190,8 +205,29 @@ impl EqualityCalculationContext {
 
     /// Computes equality by querying the left element whether it is equal to
     /// the right element. Tries get_equality_right as a backup if that does not
-    /// produce a conclusive answer.
+    /// produce a conclusive answer. Results are remembered until an item is
+    /// redefined, since the same checks come up many times.
     pub fn get_equality_left(&mut self) -> Result<Equal, UnresolvedItemError> {
+        let key = self.cache_key();
+        if let Some(result) = cache::get(&key) {
+            return Ok(result);
+        }
+        // Copies of the same expression are equal without substituting
+        // anything. Variables are only identical to themselves, so this holds
+        // for copies which depend on variables too.
+        let result = if self.no_subs() && self.lhs.is_structurally_identical_to(&self.rhs) {
+            COPIES_FOUND.with(|found| found.set(found.get() + 1));
+            Ok(Equal::yes())
+        } else {
+            self.get_equality_left_uncached()
+        };
+        if let Ok(result) = &result {
+            cache::insert(key, result.clone());
+        }
+        result
+    }
+
+    fn get_equality_left_uncached(&mut self) -> Result<Equal, UnresolvedItemError> {
         if TRACE {
             println!("{:#?} =<= {:#?}", self.lhs, self.rhs);
             println!("LHS Subs: {:#?}", self.lhs_subs);
@@ -235,6 +271,27 @@ impl EqualityCalculationContext {
         Ok(rhs.get_equality_using_context(self, OnlyCalledByEcc(()))?)
     }
 
+    fn cache_key(&self) -> EqualityKey {
+        let key_subs = |sub_list: &[Substitutions]| {
+            sub_list
+                .iter()
+                .map(|subs| {
+                    subs.iter()
+                        .map(|(target, value)| {
+                            (target.borrow().item().ptr_clone(), value.ptr_clone())
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        EqualityKey {
+            lhs: self.lhs.ptr_clone(),
+            lhs_subs: key_subs(&self.lhs_subs),
+            rhs: self.rhs.ptr_clone(),
+            rhs_subs: key_subs(&self.rhs_subs),
+        }
+    }
+
     pub fn other_with_subs(&self) -> ItemPtr {
         let mut other = self.other().ptr_clone();
         let sub_list = match self.self_side {
@@ -4,10 +4,14 @@ use std::assert_matches::assert_matches;
 
 use crate::{
     item::{
-        definitions::substitution::{DSubstitution, Substitutions},
-        equality::Equal,
+        definitions::{
+            other::DOther,
+            substitution::{DSubstitution, Substitutions},
+        },
+        equality::{copies_found, Equal},
         test_util::*,
         util::*,
+        ItemDefinition,
     },
     util::PtrExtension,
 };
@@ -52,6 +56,21 @@ fn var_sub_something_equals_something() {
     assert_eq!(another.get_trimmed_equality(&var_sub_thing), Ok(Equal::No));
 }
 
+#[test]
+fn redefining_an_item_forgets_old_results() {
+    let thing = unique();
+    let another = unique();
+    let (var_con, var_id) = variable_full();
+    let var_sub_thing =
+        unchecked_substitution_without_shortcuts(var_con, &subs(vec![(var_id, thing.ptr_clone())]));
+    assert_eq!(var_sub_thing.get_trimmed_equality(&another), Ok(Equal::No));
+    thing.redefine(DOther::new(another.ptr_clone()).clone_into_box());
+    assert_eq!(
+        var_sub_thing.get_trimmed_equality(&another),
+        Ok(Equal::yes())
+    );
+}
+
 #[test]
 fn decision_equals_identical_decision() {
     let a = unique();
@@ -1285,7 +1304,7 @@ fn complex_fx_sub_is_arg_env() {
     v2 IS v
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1323,7 +1342,7 @@ fn advanced_equality_symmetry_env() {
     v2 IS v = u
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1408,7 +1427,7 @@ fn fx_eq_a_is_self_sub_y_env() {
     v2 IS statement(x IS t)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let x = get_member(&root, "x");
         let t = get_member(&root, "t");
@@ -1440,7 +1459,7 @@ fn fx_eq_a_sub_y_is_self_env() {
     s2 IS statement(x IS t)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let fx_eq_a_sub_y_1 = get_member(&root, "s1");
         let fx_eq_a_sub_y_2 = get_member(&root, "s2");
@@ -1479,7 +1498,7 @@ fn subbed_eq_ext_rev_is_eq_ext() {
     v2 IS fx(u) = fx(v)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1508,7 +1527,7 @@ fn fx_sub_x_is_x() {
     v2 IS x
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1542,7 +1561,7 @@ fn separated_fx_sub_x_is_x() {
     v2 IS x
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1583,7 +1602,7 @@ fn fx_a_inv_eq_result_is_fx_a_env() {
     v2 IS fx(v)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1615,7 +1634,7 @@ fn fx_sub_s_and_r_x_is_r_s_env() {
     v2 IS r = s
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1664,7 +1683,7 @@ fn try_build_trans_result_env() {
     v2 IS inv_eq_req(r = s   r = t)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1694,7 +1713,7 @@ fn fx_sub_y_sub_x_is_fx() {
     v2 IS fx
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1724,7 +1743,7 @@ fn fx_sub_y_is_fx() {
     v2 IS fx
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let x = get_member(&root, "x").dereference();
         let y = get_member(&root, "y").dereference();
@@ -1767,7 +1786,7 @@ fn function_invariant_statement_env() {
     v2 IS u
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1803,7 +1822,7 @@ fn equality_function_statement_env() {
     v2 IS u = v
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         let v1 = get_member(&root, "v1");
         let v2 = get_member(&root, "v2");
@@ -1827,3 +1846,104 @@ fn equality_function_statement_env() {
         );
     });
 }
+
+#[test]
+fn literals_equal_themselves() {
+    let code = r"
+    one IS 1
+    other_one IS 1
+    two IS 2
+    minus_two IS -2
+    plus_two IS +2
+    ";
+    with_env_from_code(code, |_env, root| {
+        let one = get_member(&root, "one");
+        let other_one = get_member(&root, "other_one");
+        let two = get_member(&root, "two");
+        let minus_two = get_member(&root, "minus_two");
+        let plus_two = get_member(&root, "plus_two");
+        assert_eq!(one.get_trimmed_equality(&other_one), Ok(Equal::yes()));
+        assert_eq!(one.get_trimmed_equality(&two), Ok(Equal::No));
+        assert_eq!(minus_two.get_trimmed_equality(&plus_two), Ok(Equal::No));
+        assert_eq!(two.get_trimmed_equality(&plus_two), Ok(Equal::Unknown));
+        assert_eq!(plus_two.get_trimmed_equality(&two), Ok(Equal::Unknown));
+    });
+}
+
+#[test]
+fn literals_equal_their_encoding() {
+    let code = r"
+    zero IS UNIQUE AS_LANGUAGE_ITEM(nat_zero)
+    succ IS { UNIQUE  VAR() } AS_LANGUAGE_ITEM(nat_succ)
+    add IS { UNIQUE  VAR()  VAR() } AS_LANGUAGE_ITEM(nat_add)
+
+    two IS 2
+    encoded_two IS succ(succ(zero))
+    sum IS add(1 2)
+    three IS 3
+    plus_two IS +2
+    ";
+    with_env_from_code(code, |_env, root| {
+        let two = get_member(&root, "two");
+        let encoded_two = get_member(&root, "encoded_two");
+        let sum = get_member(&root, "sum");
+        let three = get_member(&root, "three");
+        let plus_two = get_member(&root, "plus_two");
+        assert_eq!(two.get_trimmed_equality(&encoded_two), Ok(Equal::yes()));
+        assert_eq!(encoded_two.get_trimmed_equality(&two), Ok(Equal::yes()));
+        assert_eq!(sum.get_trimmed_equality(&three), Ok(Equal::yes()));
+        assert_eq!(sum.get_trimmed_equality(&two), Ok(Equal::No));
+        assert_eq!(
+            plus_two.get_trimmed_equality(&encoded_two),
+            Ok(Equal::Unknown)
+        );
+        assert_eq!(
+            encoded_two.get_trimmed_equality(&plus_two),
+            Ok(Equal::Unknown)
+        );
+    });
+}
+
+#[test]
+fn open_copies_are_compared_by_pointer_env() {
+    let code = r"
+    true IS UNIQUE
+    x IS VAR(SELF = true)
+    y IS VAR(SELF)
+    uses IS y(x = true)
+
+    a IS x = true
+    b IS x = true
+    ";
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        let before = copies_found();
+        env.justify_all(&root).unwrap();
+        assert!(copies_found() > before);
+
+        let a = get_member(&root, "a");
+        let b = get_member(&root, "b");
+        let before = copies_found();
+        assert_eq!(a.get_trimmed_equality(&b), Ok(Equal::yes()));
+        assert_eq!(copies_found(), before + 1);
+    });
+}
+
+#[test]
+fn kinds_that_are_checked_as_different_are_not_equal() {
+    let code = r"
+    a IS UNIQUE
+    pair IS { a  a }
+    one IS +1
+    ";
+    with_env_from_code(code, |_env, root| {
+        let a = get_member(&root, "a");
+        let pair = get_member(&root, "pair");
+        let one = get_member(&root, "one");
+        assert_eq!(a.get_trimmed_equality(&pair), Ok(Equal::No));
+        assert_eq!(pair.get_trimmed_equality(&a), Ok(Equal::No));
+        // Integers might be naturals, which might be encoded as anything.
+        assert_eq!(one.get_trimmed_equality(&a), Ok(Equal::Unknown));
+        assert_eq!(pair.get_trimmed_equality(&one), Ok(Equal::Unknown));
+    });
+}
@@ -1,7 +1,11 @@
+mod audit;
+mod auto_theorem;
+mod certificate;
 mod context;
 mod feature;
+mod hints;
 mod invariant_set;
 pub mod justify;
 mod tests;
 
-pub use self::{context::*, feature::*, invariant_set::*};
+pub use self::{certificate::*, context::*, feature::*, invariant_set::*};
@@ -0,0 +1,132 @@
+use super::{InvariantSet, InvariantSetPtr};
+use crate::{
+    environment::Environment,
+    item::{
+        definitions::{axiom::DAxiom, decision::DDecision, substitution::DSubstitution},
+        ItemPtr,
+    },
+    util::PtrExtension,
+};
+
+/// What the invariants of an item rely on once everything has been justified.
+#[derive(Clone, Debug)]
+pub struct AxiomAudit {
+    pub item: ItemPtr,
+    /// Names of the axioms used, like `trivial_t`, sorted and without
+    /// duplicates.
+    pub axioms: Vec<&'static str>,
+    pub auto_theorems: Vec<ItemPtr>,
+}
+
+/// Items whose invariants the invariants of the given item are made from,
+/// so that they hold for the same reasons.
+fn derived_from(item: &ItemPtr) -> Vec<ItemPtr> {
+    if let Some(sub) = item.downcast_definition::<DSubstitution>() {
+        vec![sub.base().ptr_clone()]
+    } else if let Some(decision) = item.downcast_definition::<DDecision>() {
+        vec![
+            decision.when_equal().ptr_clone(),
+            decision.when_not_equal().ptr_clone(),
+        ]
+    } else {
+        vec![]
+    }
+}
+
+struct AuditContext<'a> {
+    env: &'a mut Environment,
+    visited: Vec<InvariantSetPtr>,
+    axioms: Vec<&'static str>,
+    auto_theorems: Vec<ItemPtr>,
+}
+
+impl<'a> AuditContext<'a> {
+    fn visit(&mut self, set_ptr: &InvariantSetPtr) {
+        if self
+            .visited
+            .iter()
+            .any(|other| other.is_same_instance_as(set_ptr))
+        {
+            return;
+        }
+        self.visited.push(set_ptr.ptr_clone());
+        let set = set_ptr.borrow().clone();
+        let context = set.context.dereference();
+        if let Some(axiom) = context.downcast_definition::<DAxiom>() {
+            let axiom = axiom.clone();
+            let statement = axiom.get_statement(self.env);
+            let name = statement.strip_suffix("_statement").unwrap_or(statement);
+            if !self.axioms.contains(&name) {
+                self.axioms.push(name);
+            }
+        }
+        let is_auto_theorem = matches!(
+            self.env.theorem_index(&context),
+            Some(index) if index < self.env.auto_theorem_count()
+        );
+        if is_auto_theorem
+            && !self
+                .auto_theorems
+                .iter()
+                .any(|theorem| theorem.is_same_instance_as(&context))
+        {
+            self.auto_theorems.push(context.ptr_clone());
+        }
+        for derived_from in derived_from(&context) {
+            if let Ok(other_set) = derived_from.get_invariants() {
+                self.visit(&other_set);
+            }
+        }
+        self.visit_justification(&set);
+    }
+
+    fn visit_justification(&mut self, set: &InvariantSet) {
+        for justification in set.used_justification().into_iter().flatten() {
+            for set in justification {
+                self.visit(set);
+            }
+        }
+    }
+}
+
+impl Environment {
+    /// Lists the axioms and auto theorems that each item's invariants rely
+    /// on, directly or through other invariants. Only items which needed
+    /// justifying or were marked SHOWN are listed. This should be called
+    /// after justify_all, otherwise nothing will have been justified yet.
+    pub fn audit_axioms(&mut self, root: &ItemPtr) -> Vec<AxiomAudit> {
+        let mut items = Vec::new();
+        root.for_self_and_deep_contents(&mut |item| items.push(item.ptr_clone()));
+        let mut audited: Vec<InvariantSetPtr> = Vec::new();
+        let mut result = Vec::new();
+        for item in items {
+            let set = match item.get_invariants() {
+                Ok(set) => set,
+                Err(_) => continue,
+            };
+            let needed_justifying = set.borrow().justification_requirements().len() > 0;
+            if !needed_justifying && !item.borrow().show {
+                continue;
+            }
+            if audited.iter().any(|other| other.is_same_instance_as(&set)) {
+                continue;
+            }
+            audited.push(set.ptr_clone());
+            let mut ctx = AuditContext {
+                env: self,
+                visited: Vec::new(),
+                axioms: Vec::new(),
+                auto_theorems: Vec::new(),
+            };
+            ctx.visit(&set);
+            let mut axioms = ctx.axioms;
+            axioms.sort();
+            result.push(AxiomAudit {
+                item,
+                axioms,
+                auto_theorems: ctx.auto_theorems,
+            });
+        }
+        result
+    }
+}
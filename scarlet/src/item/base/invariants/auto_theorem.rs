@@ -0,0 +1,66 @@
+//! Auto theorems are applied wherever they are needed instead of by hand. When
+//! a requirement can't be justified by the statements available to it, or
+//! only in circles, each statement of each auto theorem is compared with the
+//! requirement. If they match after substituting some of the theorem's
+//! variables, the theorem is instantiated with those values, and the
+//! requirement holds if the premises of that instance do.
+
+use super::InvariantSetPtr;
+use crate::item::{
+    definitions::substitution::DSubstitution, equality::Equal, resolvable::checked_substitution,
+    ItemPtr,
+};
+
+/// Substitutes into the auto theorem so that the statement at the given index
+/// in its invariant set becomes the requirement. Returns the invariant set of
+/// the instance, whose justification requirements are the premises, along
+/// with the invariant set of the theorem itself, since the instance only holds
+/// if the theorem does.
+pub(super) fn instantiate(
+    theorem: &ItemPtr,
+    statement: usize,
+    requirement: &ItemPtr,
+    context: &ItemPtr,
+) -> Option<(InvariantSetPtr, InvariantSetPtr)> {
+    let theorem = theorem.dereference();
+    let theorem_set = theorem.get_invariants().ok()?;
+    let theorem_statement = theorem_set
+        .borrow()
+        .statements()
+        .get(statement)?
+        .ptr_clone();
+    let subs = match theorem_statement.get_trimmed_equality(requirement) {
+        // Only the theorem's variables can be given values, anything the
+        // requirement depends on has to stay as it is.
+        Ok(Equal::Yes(subs, requirement_subs)) if requirement_subs.len() == 0 => subs,
+        _ => return None,
+    };
+    let instance = checked_substitution(theorem, subs, context.clone_scope()).ok()?;
+    let instance_set = instance.get_invariants().ok()?;
+    let matches = instance_set
+        .borrow()
+        .statements()
+        .get(statement)?
+        .get_trimmed_equality(requirement)
+        .as_ref()
+        .map(Equal::is_trivial_yes)
+        == Ok(true);
+    if matches {
+        Some((instance_set, theorem_set))
+    } else {
+        None
+    }
+}
+
+/// If the invariant set belongs to an instance of one of the theorems, returns
+/// the index of that theorem.
+pub(super) fn instance_of(theorems: &[ItemPtr], set: &InvariantSetPtr) -> Option<usize> {
+    let context = set.borrow().context().ptr_clone();
+    let base = context
+        .downcast_definition::<DSubstitution>()?
+        .base()
+        .dereference();
+    theorems
+        .iter()
+        .position(|theorem| theorem.dereference().is_same_instance_as(&base))
+}
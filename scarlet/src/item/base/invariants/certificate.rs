@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    auto_theorem,
+    justify::{collect_invariant_sets, propogate_root_connectedness},
+    InvariantSet, InvariantSetPtr, StatementJustification,
+};
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    item::{equality::Equal, ItemPtr},
+    util::PtrExtension,
+};
+
+/// Bumped whenever the meaning of a certificate changes.
+pub const CERTIFICATE_VERSION: u32 = 2;
+
+/// A record of how every invariant set was justified, which can be checked
+/// without searching for justifications again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Certificate {
+    pub version: u32,
+    /// Identifies the source code the certificate was made from.
+    pub source_hash: String,
+    /// One entry for every invariant set, in the order they are found by
+    /// walking through the roots. Sets that were never justified are None.
+    pub sets: Vec<Option<Vec<Step>>>,
+}
+
+/// How a single justification requirement was met.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    /// Indexes into the invariant sets available to the item that has the
+    /// requirement. All of them must be justified for this step to hold.
+    pub sets: Vec<usize>,
+    /// Which of the sets above contains the statement that is the same as the
+    /// requirement, and the index of that statement in it. For steps which
+    /// instantiate an auto theorem or a hint, this is its index among the
+    /// environment's instantiable theorems, and the index of the statement in
+    /// it that became the requirement instead.
+    pub statement: (usize, usize),
+    /// How each premise of the instance was met, for steps which
+    /// instantiate one. Those steps have no sets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub premises: Option<Vec<Step>>,
+}
+
+fn index_of(sets: &[InvariantSetPtr], set: &InvariantSetPtr) -> Option<usize> {
+    sets.iter().position(|other| other.is_same_instance_as(set))
+}
+
+fn certify_set(theorems: &[ItemPtr], set_ptr: &InvariantSetPtr) -> Option<Vec<Step>> {
+    let set = set_ptr.borrow();
+    let used = set.used_justification()?;
+    let available = set.context.clone_scope().get_invariant_sets();
+    let mut steps = Vec::new();
+    for (requirement, justification) in set.justification_requirements().iter().zip(used) {
+        let sets = justification
+            .iter()
+            .map(|set| index_of(&available, set))
+            .collect::<Option<Vec<_>>>();
+        let sets = match sets {
+            Some(sets) => sets,
+            None => {
+                steps.push(certify_instance(theorems, requirement, justification)?);
+                continue;
+            }
+        };
+        let statement = justification.iter().enumerate().find_map(|(which, set)| {
+            set.borrow()
+                .statements()
+                .iter()
+                .position(|statement| is_same_statement(requirement, statement))
+                .map(|index| (which, index))
+        })?;
+        steps.push(Step {
+            sets,
+            statement,
+            premises: None,
+        });
+    }
+    Some(steps)
+}
+
+/// Makes a step for a requirement that was met by instantiating an auto
+/// theorem or a hint, whose instance is the first set of the justification.
+fn certify_instance(
+    theorems: &[ItemPtr],
+    requirement: &ItemPtr,
+    justification: &[InvariantSetPtr],
+) -> Option<Step> {
+    let instance = justification.first()?;
+    let theorem = auto_theorem::instance_of(theorems, instance)?;
+    let statement = instance
+        .borrow()
+        .statements()
+        .iter()
+        .position(|statement| is_same_statement(requirement, statement))?;
+    let premises = certify_set(theorems, instance)?;
+    Some(Step {
+        sets: Vec::new(),
+        statement: (theorem, statement),
+        premises: Some(premises),
+    })
+}
+
+fn is_same_statement(requirement: &ItemPtr, statement: &ItemPtr) -> bool {
+    requirement
+        .get_trimmed_equality(statement)
+        .as_ref()
+        .map(Equal::is_trivial_yes)
+        == Ok(true)
+}
+
+impl Environment {
+    /// Every invariant set under the roots, without duplicates. Certificates
+    /// refer to sets by their index in this table, so it must be made before
+    /// anything is justified.
+    pub fn invariant_set_table(&self, roots: &[ItemPtr]) -> Vec<InvariantSetPtr> {
+        let mut result: Vec<InvariantSetPtr> = Vec::new();
+        for root in roots {
+            for set in collect_invariant_sets(root) {
+                if !result.iter().any(|other| other.is_same_instance_as(&set)) {
+                    result.push(set);
+                }
+            }
+        }
+        result
+    }
+
+    /// Records how each set in the table was justified. This should be called
+    /// after justify_all has succeeded on every root.
+    pub fn certificate(&self, sets: &[InvariantSetPtr], source_hash: String) -> Certificate {
+        Certificate {
+            version: CERTIFICATE_VERSION,
+            source_hash,
+            sets: self.certificate_steps(sets),
+        }
+    }
+
+    /// The steps a certificate for the sets would contain.
+    pub fn certificate_steps(&self, sets: &[InvariantSetPtr]) -> Vec<Option<Vec<Step>>> {
+        sets.iter()
+            .map(|set| certify_set(self.instantiable_theorems(), set))
+            .collect()
+    }
+
+    /// Checks that a certificate justifies every invariant set in the table
+    /// that needs to be justified. Each step is checked with a single
+    /// equality test, nothing is searched for.
+    pub fn check_certificate(
+        &mut self,
+        sets: &[InvariantSetPtr],
+        certificate: &Certificate,
+    ) -> Result<(), Vec<Diagnostic>> {
+        if certificate.version != CERTIFICATE_VERSION {
+            return Err(vec![Diagnostic::new().with_text_error(format!(
+                "This certificate is version {}, but only version {} is supported.",
+                certificate.version, CERTIFICATE_VERSION
+            ))]);
+        }
+        if certificate.sets.len() != sets.len() {
+            return Err(vec![Diagnostic::new().with_text_error(format!(
+                "This certificate has {} invariant sets, but the code has {}.",
+                certificate.sets.len(),
+                sets.len()
+            ))]);
+        }
+
+        let mut diagnostics = Vec::new();
+        // For each set, the sets it depends on if all of its steps were valid.
+        let mut depends_on = Vec::new();
+        // Sets with invalid steps, which have already been reported.
+        let mut invalid = vec![false; sets.len()];
+        for (index, (set_ptr, steps)) in sets.iter().zip(&certificate.sets).enumerate() {
+            let steps = match steps {
+                Some(steps) => steps,
+                None => {
+                    depends_on.push(None);
+                    continue;
+                }
+            };
+            match self.check_steps(set_ptr, steps) {
+                Ok(dependencies) => depends_on.push(Some(dependencies)),
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    depends_on.push(None);
+                    invalid[index] = true;
+                }
+            }
+        }
+
+        // Work out which sets are connected to the root from scratch, without
+        // trusting anything the prover worked out.
+        let mut connected = sets
+            .iter()
+            .map(|set| set.borrow().connected_to_root)
+            .collect::<Vec<_>>();
+        loop {
+            let mut progress = false;
+            for index in 0..sets.len() {
+                if connected[index] {
+                    continue;
+                }
+                if let Some(dependencies) = &depends_on[index] {
+                    let all_connected = dependencies.iter().all(|dep| match index_of(sets, dep) {
+                        Some(dep_index) => connected[dep_index],
+                        None => dep.borrow().connected_to_root,
+                    });
+                    if all_connected {
+                        connected[index] = true;
+                        progress = true;
+                    }
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        for (index, set_ptr) in sets.iter().enumerate() {
+            let set = set_ptr.borrow();
+            if !set.required
+                || connected[index]
+                || invalid[index]
+                || set.justification_requirements().len() == 0
+            {
+                continue;
+            }
+            let message = if depends_on[index].is_some() {
+                "The certificate justifies these statements using others that it does not justify:"
+            } else {
+                "The certificate does not justify these statements:"
+            };
+            let mut d = Diagnostic::new().with_text_error(String::from(message));
+            for requirement in set.justification_requirements() {
+                d = d.with_item_error(requirement, &set.context, self);
+            }
+            d = d.with_text_info(format!("Required by this substitution:"));
+            d = d.with_item_info(&set.context, &set.context, self);
+            diagnostics.push(d);
+        }
+        if diagnostics.len() == 0 {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    /// Uses steps from earlier certificates to justify each set they are
+    /// still valid for, then connects whatever that justifies to the root.
+    /// Each table of sets is paired with the steps recorded for it. Sets
+    /// whose steps are no longer valid are left for justify_all to work out.
+    pub fn reuse_steps(&mut self, tables: &[(Vec<InvariantSetPtr>, Vec<Option<Vec<Step>>>)]) {
+        let mut all_sets = Vec::new();
+        for (sets, steps) in tables {
+            for (set_ptr, steps) in sets.iter().zip(steps) {
+                let steps = match steps {
+                    Some(steps) => steps,
+                    None => continue,
+                };
+                if set_ptr.borrow().connected_to_root {
+                    continue;
+                }
+                let mut instances = Vec::new();
+                let justification = valid_justification(
+                    self.instantiable_theorems(),
+                    &set_ptr.borrow(),
+                    steps,
+                    &mut instances,
+                );
+                if let Some(justification) = justification {
+                    set_ptr.borrow_mut().set_justification = Some(justify_with(justification));
+                    all_sets.append(&mut instances);
+                }
+            }
+            all_sets.extend(sets.iter().map(PtrExtension::ptr_clone));
+        }
+        propogate_root_connectedness(&all_sets);
+    }
+
+    /// Checks that each step refers to a statement which is the same as the
+    /// requirement it is for, returning the sets the steps rely on.
+    fn check_steps(
+        &mut self,
+        set_ptr: &InvariantSetPtr,
+        steps: &[Step],
+    ) -> Result<Vec<InvariantSetPtr>, Diagnostic> {
+        let set = set_ptr.borrow().clone();
+        let mut instances = Vec::new();
+        match valid_justification(self.instantiable_theorems(), &set, steps, &mut instances) {
+            Some(justification) => {
+                let mut relied_on = Vec::new();
+                for set in justification.iter().flatten() {
+                    push_relied_on(set, &instances, &mut relied_on);
+                }
+                Ok(relied_on)
+            }
+            None => {
+                let mut d = Diagnostic::new().with_text_error(format!(
+                    "The certificate does not justify these statements correctly:"
+                ));
+                for requirement in set.justification_requirements() {
+                    d = d.with_item_error(requirement, &set.context, self);
+                }
+                d = d.with_text_info(format!("Required by this substitution:"));
+                d = d.with_item_info(&set.context, &set.context, self);
+                Err(d)
+            }
+        }
+    }
+}
+
+fn justify_with(justification: Vec<StatementJustification>) -> Vec<Vec<StatementJustification>> {
+    justification.into_iter().map(|just| vec![just]).collect()
+}
+
+/// Adds the set to the list, unless it is an auto theorem instance made while
+/// checking steps, in which case the sets its premises rely on are added
+/// instead.
+fn push_relied_on(
+    set: &InvariantSetPtr,
+    instances: &[InvariantSetPtr],
+    to: &mut Vec<InvariantSetPtr>,
+) {
+    if instances
+        .iter()
+        .any(|instance| instance.is_same_instance_as(set))
+    {
+        let set = set.borrow();
+        for premise_set in set.set_justification.iter().flatten().flatten().flatten() {
+            push_relied_on(premise_set, instances, to);
+        }
+    } else {
+        to.push(set.ptr_clone());
+    }
+}
+
+/// Returns the sets each step relies on, or None if any step does not refer
+/// to a statement which is the same as its requirement. Auto theorem
+/// instances are made again for steps that use them, justified by the steps
+/// for their premises, and added to the list of instances.
+fn valid_justification(
+    theorems: &[ItemPtr],
+    set: &InvariantSet,
+    steps: &[Step],
+    instances: &mut Vec<InvariantSetPtr>,
+) -> Option<Vec<StatementJustification>> {
+    let requirements = set.justification_requirements();
+    if steps.len() != requirements.len() {
+        return None;
+    }
+    let available = set.context.clone_scope().get_invariant_sets();
+    let mut justification = Vec::new();
+    for (requirement, step) in requirements.iter().zip(steps) {
+        if let Some(premises) = &step.premises {
+            let (theorem, statement) = step.statement;
+            let (instance, theorem_set) = auto_theorem::instantiate(
+                theorems.get(theorem)?,
+                statement,
+                requirement,
+                &set.context,
+            )?;
+            let instance_copy = instance.borrow().clone();
+            let premises = valid_justification(theorems, &instance_copy, premises, instances)?;
+            instance.borrow_mut().set_justification = Some(justify_with(premises));
+            instances.push(instance.ptr_clone());
+            justification.push(vec![instance, theorem_set]);
+            continue;
+        }
+        let step_sets = step
+            .sets
+            .iter()
+            .map(|&index| available.get(index).map(PtrExtension::ptr_clone))
+            .collect::<Option<Vec<_>>>()?;
+        let (which, index) = step.statement;
+        let statement = step_sets[..]
+            .get(which)?
+            .borrow()
+            .statements()
+            .get(index)?
+            .ptr_clone();
+        if !is_same_statement(requirement, &statement) {
+            return None;
+        }
+        justification.push(step_sets);
+    }
+    Some(justification)
+}
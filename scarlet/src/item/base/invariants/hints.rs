@@ -0,0 +1,141 @@
+//! Hints name theorems which should be tried first when justifying the
+//! requirements of a substitution, or the requirements that come from
+//! substituting into a statement. A hint is instantiated the same way as an
+//! auto theorem, so it can be used anywhere regardless of whether it is in
+//! scope.
+
+use super::{auto_theorem, justify::collect_invariant_sets};
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    item::{definitions::substitution::DSubstitution, equality::Equal, ItemPtr},
+};
+
+/// Returns the index in the environment's instantiable theorems of each hint
+/// that applies to the requirement. Those are hints given for the item the
+/// requirement comes from, for the requirement itself and for the statement
+/// it was substituted from.
+pub(super) fn hints_for(env: &Environment, context: &ItemPtr, requirement: &ItemPtr) -> Vec<usize> {
+    let mut sources = vec![context.ptr_clone(), requirement.ptr_clone()];
+    let base = requirement
+        .downcast_definition::<DSubstitution>()
+        .map(|sub| sub.base().ptr_clone());
+    if let Some(base) = base {
+        // The statement might be a reference, with the hint given on either
+        // side of it.
+        sources.push(base.dereference());
+        sources.push(base);
+    }
+    let mut hints = Vec::new();
+    for source in sources {
+        for hint in &source.borrow().hints {
+            if let Some(index) = env.theorem_index(hint) {
+                if !hints.contains(&index) {
+                    hints.push(index);
+                }
+            }
+        }
+    }
+    hints
+}
+
+/// Checks whether any statement of the theorem matches the requirement,
+/// ignoring whether its premises hold.
+fn matches(theorem: &ItemPtr, requirement: &ItemPtr, context: &ItemPtr) -> bool {
+    let statement_count = match theorem.dereference().get_invariants() {
+        Ok(set) => set.borrow().statements().len(),
+        Err(_) => return false,
+    };
+    (0..statement_count).any(|statement| {
+        auto_theorem::instantiate(theorem, statement, requirement, context).is_some()
+    })
+}
+
+impl Environment {
+    /// Warns about every hint which doesn't match any of the requirements it
+    /// was given for.
+    pub fn check_hints(&mut self, roots: &[ItemPtr]) -> Vec<Diagnostic> {
+        // For each hint, whether it has matched anything yet and what it was
+        // compared with.
+        let mut uses: Vec<(bool, Vec<(ItemPtr, ItemPtr)>)> =
+            vec![(false, Vec::new()); self.instantiable_theorems().len()];
+        for root in roots {
+            for set in collect_invariant_sets(root) {
+                let set = set.borrow();
+                for requirement in set.justification_requirements() {
+                    for hint in hints_for(self, &set.context, requirement) {
+                        let (matched, compared_with) = &mut uses[hint];
+                        if *matched {
+                            continue;
+                        }
+                        let theorem = &self.instantiable_theorems()[hint];
+                        if matches(theorem, requirement, &set.context) {
+                            *matched = true;
+                        } else {
+                            compared_with.push((requirement.ptr_clone(), set.context.ptr_clone()));
+                        }
+                    }
+                }
+            }
+        }
+        let mut diagnostics = Vec::new();
+        for (index, (matched, compared_with)) in uses.into_iter().enumerate() {
+            if matched || compared_with.len() == 0 {
+                continue;
+            }
+            let hint = self.instantiable_theorems()[index].ptr_clone();
+            let mut d = Diagnostic::new()
+                .with_text_warning(format!(
+                    "This hint does not match anything it was given for:"
+                ))
+                .with_item_warning(&hint, &hint, self);
+            d = d.with_text_info(format!(
+                "None of its statements can be made into these requirements by substituting \
+                 its variables:"
+            ));
+            for (requirement, context) in &compared_with {
+                d = d.with_item_info(requirement, context, self);
+            }
+            diagnostics.push(d);
+        }
+        diagnostics
+    }
+}
+
+/// Describes why a hint didn't justify a requirement, for diagnostics about
+/// requirements that could not be justified.
+pub(super) fn explain_hint(
+    env: &mut Environment,
+    hint: &ItemPtr,
+    requirement: &ItemPtr,
+    context: &ItemPtr,
+) -> String {
+    let shown = env.show(hint.ptr_clone(), context.ptr_clone());
+    if matches(hint, requirement, context) {
+        format!(
+            "The hint {} matches, but its premises could not be justified.",
+            shown
+        )
+    } else {
+        let direct = hint
+            .dereference()
+            .get_invariants()
+            .map(|set| {
+                set.borrow().statements().iter().any(|statement| {
+                    matches!(
+                        statement.get_trimmed_equality(requirement),
+                        Ok(Equal::Yes(..))
+                    )
+                })
+            })
+            .unwrap_or(false);
+        if direct {
+            format!(
+                "The hint {} would match after substituting something the requirement depends on.",
+                shown
+            )
+        } else {
+            format!("The hint {} does not match this requirement.", shown)
+        }
+    }
+}
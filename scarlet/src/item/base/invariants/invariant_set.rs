@@ -19,6 +19,9 @@ pub struct InvariantSet {
     /// must also hold.
     pub(super) justification_requirements: Vec<ItemPtr>,
     pub(super) set_justification: Option<SetJustification>,
+    /// The justification each statement was connected to the root by, once it
+    /// has been.
+    pub(super) used_justification: Option<Vec<StatementJustification>>,
     pub(super) connected_to_root: bool,
     pub(super) required: bool,
     pub(super) dependencies: HashSet<ItemPtr>,
@@ -58,6 +61,7 @@ impl InvariantSet {
             statements,
             justification_requirements,
             set_justification: None,
+            used_justification: None,
             connected_to_root: false,
             required: true,
             dependencies,
@@ -75,6 +79,7 @@ impl InvariantSet {
             statements,
             justification_requirements,
             set_justification: None,
+            used_justification: None,
             connected_to_root: false,
             required: false,
             dependencies,
@@ -91,6 +96,7 @@ impl InvariantSet {
             statements,
             justification_requirements: Vec::new(),
             set_justification: Some(justified_by),
+            used_justification: None,
             connected_to_root: false,
             required: false,
             dependencies: HashSet::new(),
@@ -106,6 +112,7 @@ impl InvariantSet {
             statements: Vec::new(),
             justification_requirements: Vec::new(),
             set_justification: None,
+            used_justification: None,
             connected_to_root: true,
             required: false,
             dependencies,
@@ -122,12 +129,19 @@ impl InvariantSet {
             statements,
             justification_requirements: Vec::new(),
             set_justification: None,
+            used_justification: None,
             connected_to_root: true,
             required: true,
             dependencies,
         })
     }
 
+    /// Get a reference to the item the invariant set belongs to.
+    #[must_use]
+    pub fn context(&self) -> &ItemPtr {
+        &self.context
+    }
+
     /// Get a reference to the invariant set's statements.
     #[must_use]
     pub fn statements(&self) -> &[ItemPtr] {
@@ -146,6 +160,25 @@ impl InvariantSet {
         self.set_justification.as_ref()
     }
 
+    /// Get a reference to the justifications that connected the invariant
+    /// set to the root.
+    #[must_use]
+    pub fn used_justification(&self) -> Option<&[StatementJustification]> {
+        self.used_justification.as_deref()
+    }
+
+    /// Get whether the invariant set has to be justified.
+    #[must_use]
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    /// Get whether the invariant set has been connected to the root.
+    #[must_use]
+    pub fn connected_to_root(&self) -> bool {
+        self.connected_to_root
+    }
+
     /// Get a reference to the invariant set's dependencies.
     #[must_use]
     pub fn dependencies(&self) -> &HashSet<ItemPtr> {
@@ -1,8 +1,11 @@
-use super::{InvariantSetPtr, SetJustification, StatementJustifications};
+use super::{auto_theorem, hints, InvariantSetPtr, SetJustification, StatementJustifications};
 use crate::{
     diagnostic::Diagnostic,
     environment::Environment,
-    item::{definitions::substitution::Substitutions, equality::Equal, ItemPtr},
+    item::{
+        definitions::substitution::Substitutions, equality::Equal, resolvable::UnresolvedItemError,
+        ItemPtr,
+    },
     scope::{LookupInvariantError, Scope},
     util::PtrExtension,
 };
@@ -19,25 +22,17 @@ pub struct JustifyStackFrame {
 
 pub type JustifyStack = Vec<JustifyStackFrame>;
 
-fn is_any_statement_justification_connected_to_root(
-    justifications: &StatementJustifications,
-) -> bool {
-    for justification in justifications {
-        let mut all_connected = true;
-        for justification in justification {
-            if !justification.borrow().connected_to_root {
-                all_connected = false;
-                break;
-            }
-        }
-        if all_connected {
-            return true;
-        }
-    }
-    false
+/// Returns the index of the first justification whose invariant sets are all
+/// connected to the root.
+fn connected_statement_justification(justifications: &StatementJustifications) -> Option<usize> {
+    justifications.iter().position(|justification| {
+        justification
+            .iter()
+            .all(|set| set.borrow().connected_to_root)
+    })
 }
 
-fn propogate_root_connectedness(of: &[InvariantSetPtr]) {
+pub(super) fn propogate_root_connectedness(of: &[InvariantSetPtr]) {
     loop {
         let mut progress = false;
         for set_ptr in of {
@@ -45,23 +40,25 @@ fn propogate_root_connectedness(of: &[InvariantSetPtr]) {
             if set.connected_to_root {
                 continue;
             }
-            let mut all_statements_connected = true;
-            if let Some(just) = &set.set_justification {
-                for statement_justifications in just {
-                    if !is_any_statement_justification_connected_to_root(statement_justifications) {
-                        all_statements_connected = false;
-                        break;
-                    }
+            let just = match &set.set_justification {
+                Some(just) => just,
+                None => continue,
+            };
+            let mut used = Vec::new();
+            for statement_justifications in just {
+                match connected_statement_justification(statement_justifications) {
+                    Some(index) => used.push(statement_justifications[index].clone()),
+                    None => break,
                 }
-            } else {
-                all_statements_connected = false;
             }
-            drop(set);
-            if all_statements_connected {
-                let mut set = set_ptr.borrow_mut();
-                set.connected_to_root = true;
-                progress = true;
+            if used.len() < just.len() {
+                continue;
             }
+            drop(set);
+            let mut set = set_ptr.borrow_mut();
+            set.connected_to_root = true;
+            set.used_justification = Some(used);
+            progress = true;
         }
         if !progress {
             break;
@@ -69,7 +66,56 @@ fn propogate_root_connectedness(of: &[InvariantSetPtr]) {
     }
 }
 
-fn collect_invariant_sets(root: &ItemPtr) -> Vec<InvariantSetPtr> {
+/// A statement which was available to justify a requirement but didn't.
+struct NearMiss {
+    statement: ItemPtr,
+    result: Result<Equal, UnresolvedItemError>,
+}
+
+impl NearMiss {
+    /// Lower ranks came closer to justifying the requirement.
+    fn rank(&self) -> u8 {
+        match &self.result {
+            Ok(Equal::Yes(..)) => 0,
+            Ok(Equal::Unknown) => 1,
+            Err(_) => 2,
+            Ok(Equal::No) => 3,
+        }
+    }
+}
+
+/// Compares a requirement with every statement available to justify it, most
+/// promising first. Only statements which have been justified themselves are
+/// considered. Returns None if one of them justifies it exactly.
+fn near_misses(context: &ItemPtr, requirement: &ItemPtr) -> Option<Vec<NearMiss>> {
+    let mut misses: Vec<NearMiss> = Vec::new();
+    for set in context.clone_scope().get_invariant_sets() {
+        let set = set.borrow();
+        if !set.connected_to_root {
+            continue;
+        }
+        for statement in set.statements() {
+            if misses
+                .iter()
+                .any(|miss| miss.statement.is_same_instance_as(statement))
+            {
+                continue;
+            }
+            let result = requirement.get_trimmed_equality(statement);
+            if result.as_ref().map(Equal::is_trivial_yes) == Ok(true) {
+                return None;
+            }
+            misses.push(NearMiss {
+                statement: statement.ptr_clone(),
+                result,
+            });
+        }
+    }
+    misses.sort_by_key(NearMiss::rank);
+    Some(misses)
+}
+
+pub(super) fn collect_invariant_sets(root: &ItemPtr) -> Vec<InvariantSetPtr> {
     let mut result = Vec::new();
     root.for_self_and_deep_contents(&mut |item| {
         result.push(item.get_invariants().unwrap());
@@ -77,9 +123,24 @@ fn collect_invariant_sets(root: &ItemPtr) -> Vec<InvariantSetPtr> {
     result
 }
 
+/// An auto theorem or hint that was instantiated to meet a requirement, kept
+/// so that it is only instantiated once.
+struct Instance {
+    theorem: usize,
+    statement: usize,
+    requirement: ItemPtr,
+    context: ItemPtr,
+    /// The invariant sets of the instance and of the theorem.
+    sets: (InvariantSetPtr, InvariantSetPtr),
+}
+
 struct JustificationContext<'a> {
     stack: JustifyStack,
     sets: Vec<InvariantSetPtr>,
+    instances: Vec<Instance>,
+    /// Instances whose premises are being justified, which can't be used to
+    /// justify their own premises.
+    justifying: Vec<InvariantSetPtr>,
     env: &'a mut Environment,
 }
 
@@ -89,6 +150,8 @@ impl Environment {
         JustificationContext {
             stack: Vec::new(),
             sets: all_sets,
+            instances: Vec::new(),
+            justifying: Vec::new(),
             env: self,
         }
         .justify_all()
@@ -105,6 +168,8 @@ impl Environment {
         JustificationContext {
             stack: Vec::new(),
             sets: all_sets,
+            instances: Vec::new(),
+            justifying: Vec::new(),
             env: self,
         }
         .justify_statement(context, statement, limit)
@@ -116,7 +181,9 @@ impl<'a> JustificationContext<'a> {
         const MAX_LIMIT: u32 = 16;
         let mut diagnostics = Vec::new();
         for limit in 0..MAX_LIMIT {
-            println!("{}/{}", limit, MAX_LIMIT);
+            if TRACE {
+                println!("{}/{}", limit, MAX_LIMIT);
+            }
             for set_ptr in self.sets.clone() {
                 let set = set_ptr.borrow();
                 if set.connected_to_root {
@@ -137,10 +204,24 @@ impl<'a> JustificationContext<'a> {
                     if res.is_ok() {
                         d = d.with_text_info(format!("There exists circular reasoning that justifies these statements, but circular reasoning is not allowed."));
                     }
+                    for requirement in &set.justification_requirements {
+                        d = self.with_hint_explanations(d, &set.context, requirement);
+                        if let Some(misses) = near_misses(&set.context, requirement) {
+                            d = self.with_near_misses(d, &set.context, requirement, misses);
+                        }
+                    }
                     diagnostics.push(d);
                 }
             }
-            propogate_root_connectedness(&self.sets);
+            // Instances are connected to the root like any other set once their
+            // premises are, but unlike the others they don't have to be.
+            let mut all_sets = self.sets.clone();
+            all_sets.extend(
+                self.instances
+                    .iter()
+                    .map(|instance| instance.sets.0.ptr_clone()),
+            );
+            propogate_root_connectedness(&all_sets);
             let mut all_connected = true;
             for set_ptr in &self.sets {
                 let set = set_ptr.borrow();
@@ -159,6 +240,65 @@ impl<'a> JustificationContext<'a> {
         }
     }
 
+    fn with_near_misses(
+        &mut self,
+        mut d: Diagnostic,
+        context: &ItemPtr,
+        requirement: &ItemPtr,
+        misses: Vec<NearMiss>,
+    ) -> Diagnostic {
+        const MAX_NEAR_MISSES: usize = 3;
+        let shown = self.env.show(requirement.ptr_clone(), context.ptr_clone());
+        if misses.len() == 0 {
+            return d.with_text_info(format!(
+                "There are no statements available here that could justify {}.",
+                shown
+            ));
+        }
+        d = d.with_text_info(format!(
+            "These statements came closest to justifying {}:",
+            shown
+        ));
+        for miss in misses.into_iter().take(MAX_NEAR_MISSES) {
+            d = d.with_item_info(&miss.statement, context, self.env);
+            let explanation = match miss.result {
+                Ok(Equal::Yes(left, right)) => {
+                    let subs = left
+                        .into_iter()
+                        .chain(right.into_iter())
+                        .map(|(target, value)| {
+                            format!(
+                                "{} IS {}",
+                                self.env.show_var(target, context.ptr_clone()),
+                                self.env.show(value, context.ptr_clone())
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    format!("This would match after substituting {}.", subs.join(", "))
+                }
+                Ok(Equal::Unknown) => format!("It could not be decided whether this matches."),
+                Ok(Equal::No) => format!("This does not match."),
+                Err(_) => format!("This could not be compared because it is not resolved yet."),
+            };
+            d = d.with_text_info(explanation);
+        }
+        d
+    }
+
+    fn with_hint_explanations(
+        &mut self,
+        mut d: Diagnostic,
+        context: &ItemPtr,
+        requirement: &ItemPtr,
+    ) -> Diagnostic {
+        for hint in hints::hints_for(self.env, context, requirement) {
+            let theorem = self.env.instantiable_theorems()[hint].ptr_clone();
+            let explanation = hints::explain_hint(self.env, &theorem, requirement, context);
+            d = d.with_text_info(explanation);
+        }
+        d
+    }
+
     fn justify_set(
         &mut self,
         set: &InvariantSetPtr,
@@ -188,7 +328,16 @@ impl<'a> JustificationContext<'a> {
                 limit,
             );
         }
-        let mut result = Vec::new();
+        // Hints are tried first, and if one of them works nothing else is.
+        let hints = hints::hints_for(self.env, context, statement);
+        let mut result = if hints.len() > 0 && limit > 0 {
+            self.justify_with_theorems(context, statement, hints, limit - 1)
+        } else {
+            Vec::new()
+        };
+        if connected_statement_justification(&result).is_some() {
+            return Ok(result);
+        }
         let ctx_scope = context.clone_scope();
         let available_invariant_sets = ctx_scope.get_invariant_sets();
         let iterate_over = available_invariant_sets;
@@ -211,10 +360,92 @@ impl<'a> JustificationContext<'a> {
                 }
             }
         }
+        // Justifications which aren't connected to the root might be circular,
+        // so auto theorems are worth a try until one is.
+        if connected_statement_justification(&result).is_none() && limit > 0 {
+            let auto_theorems = (0..self.env.auto_theorem_count()).collect();
+            let mut instances =
+                self.justify_with_theorems(context, statement, auto_theorems, limit - 1);
+            result.append(&mut instances);
+        }
         if result.len() == 0 {
             Err(LookupInvariantError::MightNotExist)
         } else {
             Ok(result)
         }
     }
+
+    /// Instantiates each of the given theorems that has a statement matching
+    /// the requirement, keeping the instances whose premises can be justified
+    /// within the limit. Theorems are indexes into the environment's
+    /// instantiable theorems.
+    fn justify_with_theorems(
+        &mut self,
+        context: &ItemPtr,
+        statement: &ItemPtr,
+        theorems: Vec<usize>,
+        limit: u32,
+    ) -> StatementJustifications {
+        let mut result = Vec::new();
+        for theorem_index in theorems {
+            let theorem = self.env.instantiable_theorems()[theorem_index].ptr_clone();
+            let statement_count = match theorem.dereference().get_invariants() {
+                Ok(set) => set.borrow().statements().len(),
+                Err(_) => continue,
+            };
+            for statement_index in 0..statement_count {
+                let (instance_set, theorem_set) =
+                    match self.instance(theorem_index, statement_index, statement, context) {
+                        Some(sets) => sets,
+                        None => continue,
+                    };
+                if self
+                    .justifying
+                    .iter()
+                    .any(|set| set.is_same_instance_as(&instance_set))
+                {
+                    continue;
+                }
+                self.justifying.push(instance_set.ptr_clone());
+                let justified = self.justify_set(&instance_set, limit);
+                self.justifying.pop();
+                if justified.is_ok() {
+                    result.push(vec![instance_set, theorem_set]);
+                }
+            }
+        }
+        result
+    }
+
+    fn instance(
+        &mut self,
+        theorem: usize,
+        statement: usize,
+        requirement: &ItemPtr,
+        context: &ItemPtr,
+    ) -> Option<(InvariantSetPtr, InvariantSetPtr)> {
+        let existing = self.instances.iter().find(|instance| {
+            instance.theorem == theorem
+                && instance.statement == statement
+                && instance.requirement.is_same_instance_as(requirement)
+                && instance.context.is_same_instance_as(context)
+        });
+        if let Some(existing) = existing {
+            return Some(existing.sets.clone());
+        }
+        let sets = auto_theorem::instantiate(
+            &self.env.instantiable_theorems()[theorem],
+            statement,
+            requirement,
+            context,
+        )?;
+        self.instances.push(Instance {
+            theorem,
+            statement,
+            requirement: requirement.ptr_clone(),
+            context: context.ptr_clone(),
+            sets: sets.clone(),
+        });
+        Some(sets)
+    }
 }
@@ -1,7 +1,11 @@
 #![cfg(test)]
 
 use crate::{
-    item::{definitions::variable::DVariable, test_util::*, util::unchecked_substitution},
+    diagnostic::{Element, Level},
+    item::{
+        definitions::variable::DVariable, invariants::Certificate, test_util::*,
+        util::unchecked_substitution, ItemPtr,
+    },
     util::PtrExtension,
 };
 
@@ -15,7 +19,7 @@ fn basic_invariant() {
     with_env_from_code(code, |mut env, root| {
         let y_statement = get_member(&root, "y_statement");
         env.justify(&root, &y_statement, &y_statement, 2).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -38,7 +42,7 @@ fn sub_invariant() {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 10)
             .unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -59,7 +63,7 @@ fn sub_fx_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "statement");
         let context = get_member(&root, "other");
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
         env.justify(&root, &context, &justify_this, 2).unwrap();
     });
@@ -90,7 +94,7 @@ fn moderate_invariant() {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 10)
             .unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -123,7 +127,7 @@ fn basic_theorem_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -144,7 +148,7 @@ fn subbed_theorem_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -168,7 +172,7 @@ fn function_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -193,7 +197,7 @@ fn indirect_function_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -220,7 +224,7 @@ fn equality_function_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -247,7 +251,7 @@ fn full_equality_function_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -271,7 +275,7 @@ fn equality_theorem_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -294,7 +298,7 @@ fn theorem_verbatim() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -320,7 +324,7 @@ fn simplified_real_theorem_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -350,7 +354,7 @@ fn real_theorem_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -376,7 +380,7 @@ fn real_theorem_separated_invariant() {
     b IS VAR(a = SELF)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -409,7 +413,7 @@ fn real_theorem_rewritten_invariant() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -429,7 +433,7 @@ fn subbed_statement() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -444,7 +448,7 @@ fn simpler_justified_substitution() {
     a(c)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -466,7 +470,7 @@ fn justify_unchecked_sub() {
         let b = get_member(&root, "b");
         let a_sub_b = unchecked_substitution(a, &subs(vec![(a_var, b.ptr_clone())]));
         env.justify(&root, &b, &a_sub_b, 10).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -483,7 +487,7 @@ fn justified_substitution() {
     {a b}(c d)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -499,7 +503,7 @@ fn scope_separated_substitution() {
     a IS amod.a
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -517,7 +521,7 @@ fn unjustified_substitution() {
     {a b}(c d)
     ";
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -553,7 +557,7 @@ fn t_just_after_theorem() {
     with_env_from_code(code, |mut env, root| {
         let justify_this = get_member(&root, "justify_this");
         env.justify(&root, &justify_this, &justify_this, 5).unwrap();
-        root.check_all();
+        root.check_all(&mut env).unwrap();
     });
 }
 
@@ -575,7 +579,7 @@ fn mysterious_hang() {
     ";
 
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -591,7 +595,7 @@ fn fx_asserting_self_sub_a() {
     ";
 
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -619,7 +623,7 @@ fn eq_ext_simplified() {
     ";
 
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -652,7 +656,7 @@ fn eq_ext_full() {
     ";
 
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
@@ -704,7 +708,313 @@ fn eq_ext_full_separated() {
     ";
 
     with_env_from_code(code, |mut env, root| {
-        root.check_all();
+        root.check_all(&mut env).unwrap();
         env.justify_all(&root).unwrap();
     });
 }
+
+#[test]
+fn audit_finds_axioms() {
+    let code = format!("{}z IS VAR(SELF)\nuses_nothing IS y(z)\n", AXIOM_CODE);
+    with_env_from_code(&code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        env.justify_all(&root).unwrap();
+        let uses_axiom = get_member(&root, "uses_axiom");
+        let uses_nothing = get_member(&root, "uses_nothing");
+        let audits = env.audit_axioms(&root);
+        let axioms_of = |item: &ItemPtr| {
+            audits
+                .iter()
+                .find(|audit| audit.item.is_same_instance_as(item))
+                .map(|audit| audit.axioms.clone())
+        };
+        assert_eq!(axioms_of(&uses_axiom), Some(vec!["trivial_t"]));
+        assert_eq!(axioms_of(&uses_nothing), Some(vec![]));
+    });
+}
+
+fn certify_code(code: &str) -> Certificate {
+    let mut certificate = None;
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        let sets = env.invariant_set_table(&[root.ptr_clone()]);
+        env.justify_all(&root).unwrap();
+        certificate = Some(env.certificate(&sets, String::new()));
+    });
+    certificate.unwrap()
+}
+
+fn check_certificate(code: &str, certificate: &Certificate) -> bool {
+    let mut valid = false;
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        let sets = env.invariant_set_table(&[root]);
+        valid = env.check_certificate(&sets, certificate).is_ok();
+    });
+    valid
+}
+
+#[test]
+fn certificate_checks() {
+    let certificate = certify_code(AXIOM_CODE);
+    assert!(check_certificate(AXIOM_CODE, &certificate));
+}
+
+#[test]
+fn tampered_certificate_does_not_check() {
+    let certificate = certify_code(AXIOM_CODE);
+    let justified = certificate
+        .sets
+        .iter()
+        .position(|steps| steps.as_ref().map(|steps| steps.len() > 0) == Some(true))
+        .unwrap();
+
+    let mut wrong_statement = certificate.clone();
+    let step = &mut wrong_statement.sets[justified].as_mut().unwrap()[0];
+    step.statement.1 += 1;
+    assert!(!check_certificate(AXIOM_CODE, &wrong_statement));
+
+    let mut missing = certificate.clone();
+    missing.sets[justified] = None;
+    assert!(!check_certificate(AXIOM_CODE, &missing));
+}
+
+#[test]
+fn auto_theorem_invariant() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS 
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t) AS_AUTO_THEOREM
+
+    y IS VAR(SELF = a)
+    justify_this IS y
+    ";
+    with_env_from_code(code, |mut env, root| {
+        let justify_this = get_member(&root, "justify_this");
+        env.justify(&root, &justify_this, &justify_this, 0)
+            .unwrap_err();
+        env.justify(&root, &justify_this, &justify_this, 1)
+            .unwrap();
+        root.check_all(&mut env).unwrap();
+    });
+}
+
+#[test]
+fn auto_theorem_needs_premises() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS 
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t) AS_AUTO_THEOREM
+
+    y IS VAR()
+    justify_this IS y
+    ";
+    with_env_from_code(code, |mut env, root| {
+        let justify_this = get_member(&root, "justify_this");
+        env.justify(&root, &justify_this, &justify_this, 5)
+            .unwrap_err();
+    });
+}
+
+#[test]
+fn only_auto_theorems_are_applied() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS 
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t)
+
+    y IS VAR(SELF = a)
+    justify_this IS y
+    ";
+    with_env_from_code(code, |mut env, root| {
+        let justify_this = get_member(&root, "justify_this");
+        env.justify(&root, &justify_this, &justify_this, 5)
+            .unwrap_err();
+    });
+}
+
+const AUTO_THEOREM_CODE: &str = r"
+a IS UNIQUE
+x IS VAR(SELF = a)
+
+statement IS
+x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+eq_ext_rev_t IS AXIOM(eq_ext_rev_t) AS_AUTO_THEOREM
+
+y IS VAR(SELF = a)
+w IS VAR(SELF)
+uses_auto_theorem IS w(w IS y)
+";
+
+#[test]
+fn auto_theorems_are_used_by_justify_all() {
+    with_env_from_code(AUTO_THEOREM_CODE, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        env.justify_all(&root).unwrap();
+        let uses_auto_theorem = get_member(&root, "uses_auto_theorem");
+        let audits = env.audit_axioms(&root);
+        let audit = audits
+            .iter()
+            .find(|audit| audit.item.is_same_instance_as(&uses_auto_theorem))
+            .unwrap();
+        assert_eq!(audit.axioms, vec!["eq_ext_rev_t"]);
+        assert_eq!(audit.auto_theorems.len(), 1);
+    });
+}
+
+#[test]
+fn auto_theorem_certificate_checks() {
+    let certificate = certify_code(AUTO_THEOREM_CODE);
+    let premises = certificate
+        .sets
+        .iter()
+        .flatten()
+        .flatten()
+        .find_map(|step| step.premises.as_ref())
+        .unwrap();
+    assert_eq!(premises.len(), 1);
+    assert!(check_certificate(AUTO_THEOREM_CODE, &certificate));
+
+    let mut wrong_theorem = certificate.clone();
+    for step in wrong_theorem.sets.iter_mut().flatten().flatten() {
+        if step.premises.is_some() {
+            step.statement.0 += 1;
+        }
+    }
+    assert!(!check_certificate(AUTO_THEOREM_CODE, &wrong_theorem));
+}
+
+
+const HINT_CODE: &str = r"
+a IS UNIQUE
+x IS VAR(SELF = a)
+
+statement IS
+x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+eq_ext_rev_t IS AXIOM(eq_ext_rev_t)
+
+y IS VAR(SELF = a)
+w IS VAR(SELF)
+uses_hint IS w(w IS y) BY(eq_ext_rev_t)
+";
+
+#[test]
+fn hint_is_used() {
+    with_env_from_code(HINT_CODE, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        assert!(env.check_hints(&[root.ptr_clone()]).is_empty());
+        env.justify_all(&root).unwrap();
+        let uses_hint = get_member(&root, "uses_hint");
+        let audits = env.audit_axioms(&root);
+        let audit = audits
+            .iter()
+            .find(|audit| audit.item.is_same_instance_as(&uses_hint))
+            .unwrap();
+        assert_eq!(audit.axioms, vec!["eq_ext_rev_t"]);
+        assert_eq!(audit.auto_theorems.len(), 0);
+    });
+}
+
+#[test]
+fn hint_certificate_checks() {
+    let certificate = certify_code(HINT_CODE);
+    assert!(certificate
+        .sets
+        .iter()
+        .flatten()
+        .flatten()
+        .any(|step| step.premises.is_some()));
+    assert!(check_certificate(HINT_CODE, &certificate));
+}
+
+#[test]
+fn hint_on_statement_is_used() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t)
+
+    y IS VAR(SELF = a)
+    w IS VAR(SELF BY(eq_ext_rev_t))
+    uses_hint IS w(w IS y)
+    ";
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        env.justify_all(&root).unwrap();
+    });
+}
+
+#[test]
+fn hint_that_does_not_match_is_reported() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t)
+
+    y IS VAR(SELF = a)
+    w IS VAR(SELF)
+    uses_hint IS w(w IS y) BY(a)
+    ";
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        let warnings = env.check_hints(&[root.ptr_clone()]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].level(), Level::Warning);
+        let errors = env.justify_all(&root).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        let explained = errors[0].elements().iter().any(|(_, element)| match element {
+            Element::Text(text) => text.contains("does not match this requirement"),
+            _ => false,
+        });
+        assert!(explained);
+    });
+}
+
+#[test]
+fn hints_naming_the_same_theorem_are_checked_together() {
+    let code = r"
+    a IS UNIQUE
+    x IS VAR(SELF = a)
+
+    statement IS
+    x AS_LANGUAGE_ITEM(eq_ext_rev_t_statement)
+
+    eq_ext_rev_t IS AXIOM(eq_ext_rev_t)
+
+    y IS VAR(SELF = a)
+    w IS VAR(SELF)
+    uses_hint IS w(w IS y) BY(a)
+    uses_hint_again IS w(w IS y) BY(a)
+    ";
+    with_env_from_code(code, |mut env, root| {
+        root.check_all(&mut env).unwrap();
+        let hints = env.hints().to_vec();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(env.theorem_index(&hints[0]), env.theorem_index(&hints[1]));
+        assert_eq!(env.instantiable_theorems().len(), 1);
+        let warnings = env.check_hints(&[root.ptr_clone()]);
+        assert_eq!(warnings.len(), 1);
+    });
+}
@@ -16,6 +16,8 @@ use owning_ref::{OwningRef, OwningRefMut};
 
 use super::{
     dependencies::{DepResult, DependencyCalculationContext},
+    equality::clear_equality_cache,
+    structure::clear_structure_table,
     from::create_from_dex,
     invariants::{InvariantCalculationContext, InvariantSetPtr, InvariantsResult},
     util::Stack,
@@ -32,7 +34,7 @@ use crate::{
         ContainmentType, ItemDefinition,
     },
     scope::{LookupIdentResult, SRoot, Scope},
-    util::PtrExtension, diagnostic::Position,
+    util::PtrExtension, diagnostic::{Diagnostic, Position},
 };
 
 pub struct ItemPtr(Rc<RefCell<Item>>);
@@ -91,9 +93,13 @@ impl ItemPtr {
         self.0.borrow_mut()
     }
 
+    /// Replaces the definition of the item, which makes every remembered
+    /// equality result and structural comparison potentially wrong.
     #[track_caller]
     pub(crate) fn redefine(&self, new_definition: Box<dyn ItemDefinition>) {
         self.borrow_mut().definition = new_definition;
+        clear_equality_cache();
+        clear_structure_table();
     }
 }
 
@@ -239,10 +245,33 @@ impl ItemPtr {
         }
     }
 
-    pub fn check_all(&self) {
+    /// Runs every check on this item and everything it contains, returning
+    /// a diagnostic for each problem that was found.
+    pub fn check_all(&self, env: &mut Environment) -> Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
         self.for_self_and_deep_contents(&mut |item| {
-            item.borrow().definition.check_self(item).unwrap();
-        })
+            let definition = item.borrow().definition.clone_into_box();
+            if let Err(diagnostic) = definition.check_self(item, env) {
+                diagnostics.push(diagnostic);
+            }
+            let borrowed = item.borrow();
+            if borrowed.show_normalized && !borrowed.show {
+                drop(borrowed);
+                diagnostics.push(
+                    Diagnostic::new()
+                        .with_text_error(format!(
+                            "NORMALIZED only changes how an item is shown, so it has to be \
+                             used along with SHOWN:"
+                        ))
+                        .with_item_error(item, item, env),
+                );
+            }
+        });
+        if diagnostics.len() == 0 {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
     }
 
     pub fn for_self_and_deep_contents_impl(
@@ -286,6 +315,14 @@ pub struct Item {
     pub from_dex: Option<ItemPtr>,
     pub name: Option<String>,
     pub show: bool,
+    /// Whether the item should be normalized before it is shown.
+    pub show_normalized: bool,
+    /// Whether the item can only be reached through a member access from
+    /// inside the struct it is a member of.
+    pub private: bool,
+    /// Theorems to try first when justifying requirements that come from
+    /// this item, given with BY.
+    pub hints: Vec<ItemPtr>,
 }
 
 impl Item {
@@ -310,6 +347,9 @@ impl Item {
             from_dex: None,
             name: None,
             show: false,
+            show_normalized: false,
+            private: false,
+            hints: Vec::new(),
         })))
     }
 
@@ -326,6 +366,9 @@ impl Item {
             from_dex: None,
             name: None,
             show: false,
+            show_normalized: false,
+            private: false,
+            hints: Vec::new(),
         })));
         let this2 = this.ptr_clone();
         let mut inner = this.downcast_definition_mut().unwrap();
@@ -0,0 +1,283 @@
+//! Computes with items by reducing them as far as possible. Decisions whose
+//! sides are closed and decidably equal or unequal are replaced by the
+//! matching branch, substitutions are applied and struct members are
+//! projected out of populated structs. Everything else is left as it is, so
+//! the result always means the same thing as what it was made from.
+
+mod tests;
+
+use crate::{
+    item::{
+        definitions::{
+            decision::DDecision,
+            structt::{AtomicStructMember, DAtomicStructMember, DPopulatedStruct},
+            substitution::{DSubstitution, Substitutions},
+            variable::DVariable,
+        },
+        equality::Equal,
+        util::unchecked_substitution,
+        Item, ItemDefinition, ItemPtr,
+    },
+    util::PtrExtension,
+};
+
+/// How many steps SHOWN NORMALIZED takes before giving up.
+pub const SHOWN_NORMALIZE_STEPS: u32 = 10_000;
+
+/// Shown after an item which SHOWN NORMALIZED could not fully reduce.
+pub fn out_of_shown_steps() -> String {
+    format!(
+        "(Stopped normalizing after {} steps.)",
+        SHOWN_NORMALIZE_STEPS
+    )
+}
+
+#[derive(Clone, Debug)]
+pub struct Normalized {
+    pub item: ItemPtr,
+    /// True if there were no steps left before the item was fully reduced.
+    pub out_of_steps: bool,
+}
+
+impl ItemPtr {
+    /// Reduces the item as far as possible, taking at most the given number
+    /// of steps. Each decision decided, substitution applied or member
+    /// projected is one step.
+    pub fn normalize(&self, steps: u32) -> Normalized {
+        let mut normalizer = Normalizer {
+            steps_left: steps,
+            out_of_steps: false,
+            visiting: Vec::new(),
+        };
+        let item = normalizer.normalize(self);
+        Normalized {
+            item,
+            out_of_steps: normalizer.out_of_steps,
+        }
+    }
+}
+
+/// What reducing an item once gave.
+enum Reduced {
+    /// The item to use in its place, which is as reduced as it can be.
+    Done(ItemPtr),
+    /// An item to use in its place, which still needs to be reduced.
+    Again(ItemPtr),
+}
+
+struct Normalizer {
+    steps_left: u32,
+    out_of_steps: bool,
+    /// Items which are part way through being normalized, so that items which
+    /// contain themselves are left alone instead of recursing forever.
+    visiting: Vec<ItemPtr>,
+}
+
+impl Normalizer {
+    fn take_step(&mut self) -> bool {
+        if self.steps_left == 0 {
+            self.out_of_steps = true;
+            false
+        } else {
+            self.steps_left -= 1;
+            true
+        }
+    }
+
+    /// Reducing an item often gives another item to reduce. That happens in a
+    /// loop rather than by recursing, so that taking lots of steps doesn't
+    /// take lots of stack.
+    fn normalize(&mut self, item: &ItemPtr) -> ItemPtr {
+        let mut item = item.dereference();
+        loop {
+            if self.visiting.contains(&item) {
+                return item;
+            }
+            self.visiting.push(item.ptr_clone());
+            let reduced = self.reduce(&item);
+            self.visiting.pop();
+            match reduced {
+                Reduced::Done(result) => return result,
+                Reduced::Again(next) => item = next.dereference(),
+            }
+        }
+    }
+
+    fn reduce(&mut self, item: &ItemPtr) -> Reduced {
+        let decision = item.downcast_definition::<DDecision>().map(|d| d.clone());
+        let sub = item
+            .downcast_definition::<DSubstitution>()
+            .map(|d| d.clone());
+        let structt = item
+            .downcast_definition::<DPopulatedStruct>()
+            .map(|d| d.clone());
+        let member = item
+            .downcast_definition::<DAtomicStructMember>()
+            .map(|d| d.clone());
+        if let Some(decision) = decision {
+            let left = self.normalize(decision.left());
+            let right = self.normalize(decision.right());
+            match decide(&left, &right) {
+                Some(true) if self.take_step() => {
+                    return Reduced::Again(decision.when_equal().ptr_clone())
+                }
+                Some(false) if self.take_step() => {
+                    return Reduced::Again(decision.when_not_equal().ptr_clone())
+                }
+                _ => (),
+            }
+            let when_equal = self.normalize(decision.when_equal());
+            let when_not_equal = self.normalize(decision.when_not_equal());
+            if unchanged(&[
+                (decision.left(), &left),
+                (decision.right(), &right),
+                (decision.when_equal(), &when_equal),
+                (decision.when_not_equal(), &when_not_equal),
+            ]) {
+                return Reduced::Done(item.ptr_clone());
+            }
+            Reduced::Done(rebuild(
+                item,
+                DDecision::new(left, right, when_equal, when_not_equal),
+            ))
+        } else if let Some(sub) = sub {
+            let base = self.normalize(sub.base());
+            let subs: Substitutions = sub
+                .substitutions()
+                .iter()
+                .map(|(target, value)| (target.ptr_clone(), self.normalize(value)))
+                .collect();
+            match substitute(&base, &subs) {
+                Some(substituted) if self.take_step() => Reduced::Again(substituted),
+                // Substitutions this can't apply are as reduced as they get, so
+                // they don't use up any steps.
+                _ if unchanged(&[(sub.base(), &base)])
+                    && sub
+                        .substitutions()
+                        .iter()
+                        .zip(subs.iter())
+                        .all(|((_, old), (_, new))| unchanged(&[(old, new)])) =>
+                {
+                    Reduced::Done(item.ptr_clone())
+                }
+                _ => Reduced::Done(unchecked_substitution(base, &subs)),
+            }
+        } else if let Some(structt) = structt {
+            let value = self.normalize(structt.get_value());
+            let rest = self.normalize(structt.get_rest());
+            if unchanged(&[(structt.get_value(), &value), (structt.get_rest(), &rest)]) {
+                return Reduced::Done(item.ptr_clone());
+            }
+            let label = structt.get_label().to_owned();
+            Reduced::Done(rebuild(item, DPopulatedStruct::new(label, value, rest)))
+        } else if let Some(member) = member {
+            let base = self.normalize(member.base());
+            let projected = base
+                .downcast_definition::<DPopulatedStruct>()
+                .and_then(|structt| match member.member() {
+                    AtomicStructMember::Label => None,
+                    AtomicStructMember::Value => Some(structt.get_value().ptr_clone()),
+                    AtomicStructMember::Rest => Some(structt.get_rest().ptr_clone()),
+                });
+            match projected {
+                Some(projected) if self.take_step() => Reduced::Again(projected),
+                _ if unchanged(&[(member.base(), &base)]) => Reduced::Done(item.ptr_clone()),
+                _ => Reduced::Done(rebuild(
+                    item,
+                    DAtomicStructMember::new(base, member.member()),
+                )),
+            }
+        } else {
+            Reduced::Done(item.ptr_clone())
+        }
+    }
+}
+
+/// Returns Some(true) or Some(false) if the items are closed and decidably
+/// equal or unequal.
+fn decide(left: &ItemPtr, right: &ItemPtr) -> Option<bool> {
+    if !is_closed(left) || !is_closed(right) {
+        return None;
+    }
+    match left.get_trimmed_equality(right) {
+        Ok(equal) if equal.is_trivial_yes() => Some(true),
+        Ok(Equal::No) => Some(false),
+        _ => None,
+    }
+}
+
+fn is_closed(item: &ItemPtr) -> bool {
+    let dependencies = item.get_dependencies();
+    dependencies.as_variables().next().is_none() && dependencies.error().is_none()
+}
+
+/// Checks if normalizing left the contents of an item alone, in which case
+/// the item itself is kept rather than copied.
+fn unchanged(contents: &[(&ItemPtr, &ItemPtr)]) -> bool {
+    contents
+        .iter()
+        .all(|(old, new)| old.dereference().is_same_instance_as(new))
+}
+
+fn rebuild(original: &ItemPtr, definition: impl ItemDefinition) -> ItemPtr {
+    Item::new_boxed(Box::new(definition), original.clone_scope())
+}
+
+/// Replaces the variables in the item, all at once. Returns None if this
+/// doesn't know how to look inside the item, like when it is a variable with
+/// dependencies. Such items inside the item are left wrapped in a
+/// substitution for equality to deal with.
+fn substitute(item: &ItemPtr, subs: &Substitutions) -> Option<ItemPtr> {
+    let item = item.dereference();
+    if is_closed(&item) {
+        return Some(item);
+    }
+    let var = item
+        .downcast_definition::<DVariable>()
+        .map(|var| var.get_variable().ptr_clone());
+    let decision = item.downcast_definition::<DDecision>().map(|d| d.clone());
+    let structt = item
+        .downcast_definition::<DPopulatedStruct>()
+        .map(|d| d.clone());
+    let member = item
+        .downcast_definition::<DAtomicStructMember>()
+        .map(|d| d.clone());
+    let inner = |item: &ItemPtr| {
+        substitute(item, subs).unwrap_or_else(|| unchecked_substitution(item.ptr_clone(), subs))
+    };
+    if let Some(var) = var {
+        if var.borrow().dependencies().is_empty() {
+            return Some(
+                subs.iter()
+                    .find(|(target, _)| target.is_same_instance_as(&var))
+                    .map(|(_, value)| value.ptr_clone())
+                    .unwrap_or(item.ptr_clone()),
+            );
+        }
+    } else if let Some(decision) = decision {
+        return Some(rebuild(
+            &item,
+            DDecision::new(
+                inner(decision.left()),
+                inner(decision.right()),
+                inner(decision.when_equal()),
+                inner(decision.when_not_equal()),
+            ),
+        ));
+    } else if let Some(structt) = structt {
+        return Some(rebuild(
+            &item,
+            DPopulatedStruct::new(
+                structt.get_label().to_owned(),
+                inner(structt.get_value()),
+                inner(structt.get_rest()),
+            ),
+        ));
+    } else if let Some(member) = member {
+        return Some(rebuild(
+            &item,
+            DAtomicStructMember::new(inner(member.base()), member.member()),
+        ));
+    }
+    None
+}
@@ -0,0 +1,113 @@
+#![cfg(test)]
+
+use crate::item::{test_util::*, util::*};
+
+const CODE: &str = r"
+    t IS UNIQUE
+    f IS UNIQUE
+    y IS VAR()
+    z IS VAR()
+    and IS DECISION(y t z f)
+    t_and_f IS and(t f)
+    t_and_t IS and(t t)
+    t_and_y IS and(t y)
+    pair IS { t_and_f t_and_t }
+    first IS pair.VALUE
+    ";
+
+#[test]
+fn decisions_are_decided() {
+    with_env_from_code(CODE, |_env, root| {
+        let t = get_member(&root, "t");
+        let f = get_member(&root, "f");
+        let t_and_f = get_member(&root, "t_and_f").normalize(100);
+        assert!(!t_and_f.out_of_steps);
+        assert!(t_and_f.item.is_same_instance_as(&f));
+        let t_and_t = get_member(&root, "t_and_t").normalize(100);
+        assert!(t_and_t.item.is_same_instance_as(&t));
+    });
+}
+
+#[test]
+fn members_are_projected() {
+    with_env_from_code(CODE, |_env, root| {
+        let f = get_member(&root, "f");
+        let first = get_member(&root, "first").normalize(100);
+        assert!(first.item.is_same_instance_as(&f));
+    });
+}
+
+#[test]
+fn open_terms_are_left_open() {
+    with_env_from_code(CODE, |_env, root| {
+        let y = get_member(&root, "y");
+        let t_and_y = get_member(&root, "t_and_y").normalize(100);
+        assert!(!t_and_y.out_of_steps);
+        // There is no way to tell if y is t, so only the substitution is
+        // applied.
+        assert!(t_and_y.item.get_dependencies().as_variables().count() == 1);
+        assert!(!t_and_y.item.is_same_instance_as(&y));
+    });
+}
+
+#[test]
+fn normal_forms_are_not_copied() {
+    let a = unique();
+    let b = unique();
+    let x = variable();
+    let dec = decision(x.ptr_clone(), a.ptr_clone(), a.ptr_clone(), b.ptr_clone());
+    let normalized = dec.normalize(100);
+    assert!(!normalized.out_of_steps);
+    assert!(normalized.item.is_same_instance_as(&dec));
+}
+
+#[test]
+fn stops_when_out_of_steps() {
+    with_env_from_code(CODE, |_env, root| {
+        let t_and_f = get_member(&root, "t_and_f");
+        let normalized = t_and_f.normalize(0);
+        assert!(normalized.out_of_steps);
+        let normalized = t_and_f.normalize(1);
+        assert!(normalized.out_of_steps);
+        let normalized = t_and_f.normalize(2);
+        assert!(!normalized.out_of_steps);
+    });
+}
+
+#[test]
+fn substituted_dependent_variables_are_left_alone() {
+    let code = r"
+    u IS UNIQUE
+    x IS VAR()
+    f IS VAR(DEP x)
+    applied IS f(x IS u)
+    ";
+    with_env_from_code(code, |_env, root| {
+        let applied = get_member(&root, "applied");
+        // Nothing can be done with f(u), so it shouldn't use up any steps.
+        for steps in [0, 10, 1_000_000] {
+            let normalized = applied.normalize(steps);
+            assert!(!normalized.out_of_steps);
+            assert!(normalized.item.is_same_instance_as(&applied.dereference()));
+        }
+    });
+}
+
+#[test]
+fn shown_normalized_items_are_shown_normalized() {
+    let code = r"
+    t IS UNIQUE
+    f IS UNIQUE
+    y IS VAR()
+    z IS VAR()
+    and IS DECISION(y t z f)
+    normalized IS and(t f) SHOWN NORMALIZED
+    not_normalized IS and(t f) SHOWN
+    ";
+    with_env_from_code(code, |mut env, root| {
+        let shown = env.show_all_requested(&root);
+        assert_eq!(shown.len(), 2);
+        assert_eq!(shown[0].1, "f");
+        assert_ne!(shown[1].1, "f");
+    });
+}
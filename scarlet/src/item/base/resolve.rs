@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 
@@ -8,14 +8,26 @@ use crate::{
     file_tree::FileNode,
     item::{
         definitions::placeholder::DPlaceholder,
-        resolvable::{DResolvable, ResolveError, ResolveResult},
+        resolvable::{DResolvable, ResolveError, ResolveResult, UnresolvedItemError},
         ItemPtr,
     },
 };
 
-pub fn resolve_all(env: &mut Environment, root: ItemPtr) -> Result<(), Vec<Diagnostic>> {
+/// Resolves everything in the given roots, along with every import, hint and
+/// notation that was created.
+pub fn resolve_all(env: &mut Environment, roots: &[ItemPtr]) -> Result<(), Vec<Diagnostic>> {
+    let mut roots = roots.to_vec();
+    roots.extend(env.imports().iter().map(ItemPtr::ptr_clone));
+    roots.extend(env.hints().iter().map(ItemPtr::ptr_clone));
+    roots.extend(env.notation_items());
+    let for_everything = |visitor: &mut dyn FnMut(&ItemPtr)| {
+        let mut visited = HashSet::new();
+        for root in &roots {
+            root.for_self_and_deep_contents_impl(&mut |item| visitor(item), &mut visited);
+        }
+    };
     let mut unresolved = HashSet::new();
-    root.for_self_and_deep_contents(&mut |item| {
+    for_everything(&mut |item| {
         if item.is_unresolved() {
             unresolved.insert(item.ptr_clone());
         }
@@ -57,23 +69,43 @@ pub fn resolve_all(env: &mut Environment, root: ItemPtr) -> Result<(), Vec<Diagn
             break;
         }
     }
-    let mut problems = Vec::new();
-    root.for_self_and_deep_contents(&mut |item| {
+    let mut failures = Vec::new();
+    for_everything(&mut |item| {
         if let Err(err) = resolve(env, item.ptr_clone(), limit) {
-            let diagnostic = match err {
-                ResolveError::Unresolved(err) => {
-                    todo!("Nice error, it relies on {:#?}", err.0);
-                }
-                ResolveError::InvariantDeadEnd(err) => todo!("Nice error, {}", err),
-                ResolveError::MaybeInvariantDoesNotExist => {
-                    todo!("Nice error, Recursion limit exceeded while searching for invariants")
-                }
-                ResolveError::Placeholder => todo!("Nice error, placeholder"),
-                ResolveError::Diagnostic(diagnostic) => diagnostic,
-            };
-            problems.push(diagnostic);
+            failures.push((item.ptr_clone(), err));
         }
     });
+    let failed: HashSet<_> = failures.iter().map(|(item, _)| item.ptr_clone()).collect();
+    let mut problems = Vec::new();
+    let mut waiting_on_each_other = Vec::new();
+    for (item, err) in failures {
+        match err {
+            ResolveError::Unresolved(UnresolvedItemError(dependency))
+                if failed.contains(&dependency) =>
+            {
+                // The dependency gets its own diagnostic, which is more
+                // useful than one for every item that relies on it.
+                waiting_on_each_other.push((item, dependency))
+            }
+            err => problems.push(resolve_error_diagnostic(env, &roots, &item, err)),
+        }
+    }
+    for cycle in cycles(&waiting_on_each_other) {
+        let mut diagnostic = if cycle.len() == 1 {
+            Diagnostic::new().with_text_error(format!(
+                "This item could not be resolved because it depends on itself:"
+            ))
+        } else {
+            Diagnostic::new().with_text_error(format!(
+                "These items could not be resolved because they depend on each other:"
+            ))
+        };
+        for item in &cycle {
+            diagnostic = diagnostic.with_item_error(item, item, env);
+        }
+        problems.push(diagnostic);
+    }
+    env.add_hinted_theorems();
     if problems.len() == 0 {
         Ok(())
     } else {
@@ -81,6 +113,107 @@ pub fn resolve_all(env: &mut Environment, root: ItemPtr) -> Result<(), Vec<Diagn
     }
 }
 
+/// Finds the cycles among items that are each waiting on one other item. Since
+/// every item only waits on one other, each strongly connected component with
+/// more than one item (or with an item waiting on itself) is a single cycle.
+/// Items which only wait on a cycle, or on an item that failed for some other
+/// reason, are not part of any.
+fn cycles(waiting: &[(ItemPtr, ItemPtr)]) -> Vec<Vec<ItemPtr>> {
+    let waits_on: HashMap<_, _> = waiting.iter().cloned().collect();
+    let mut visited = HashSet::new();
+    let mut cycles = Vec::new();
+    for (start, _) in waiting {
+        let mut path = Vec::new();
+        let mut current = start.ptr_clone();
+        while !visited.contains(&current) {
+            if let Some(index) = path.iter().position(|item| item == &current) {
+                cycles.push(path[index..].to_vec());
+                break;
+            }
+            path.push(current.ptr_clone());
+            match waits_on.get(&current) {
+                Some(next) => current = next.ptr_clone(),
+                None => break,
+            }
+        }
+        visited.extend(path);
+    }
+    cycles
+}
+
+fn resolve_error_diagnostic(
+    env: &mut Environment,
+    roots: &[ItemPtr],
+    item: &ItemPtr,
+    err: ResolveError,
+) -> Diagnostic {
+    match err {
+        ResolveError::Unresolved(UnresolvedItemError(dependency)) => Diagnostic::new()
+            .with_text_error(format!("Failed to resolve this item:"))
+            .with_item_error(item, item, env)
+            .with_text_info(format!(
+                "It relies on the following item, which could not be resolved:"
+            ))
+            .with_item_info(&dependency, item, env),
+        ResolveError::InvariantDeadEnd(info) => Diagnostic::new()
+            .with_text_error(format!(
+                "Failed to find an invariant required to resolve this item:"
+            ))
+            .with_item_error(item, item, env)
+            .with_text_info(info),
+        ResolveError::MaybeInvariantDoesNotExist => Diagnostic::new()
+            .with_text_error(format!(
+                "Failed to find an invariant required to resolve this item:"
+            ))
+            .with_item_error(item, item, env)
+            .with_text_info(format!(
+                "The search for it exceeded the recursion limit, it might not exist."
+            )),
+        ResolveError::Placeholder => placeholder_diagnostic(env, roots, item),
+        ResolveError::Diagnostic(diagnostic) => diagnostic,
+    }
+}
+
+/// Placeholders that survive until resolution are language items that are
+/// used but never defined.
+fn placeholder_diagnostic(env: &mut Environment, roots: &[ItemPtr], item: &ItemPtr) -> Diagnostic {
+    let mut diagnostic = if let Some(name) = env.language_item_name(item) {
+        Diagnostic::new()
+            .with_text_error(format!(
+                "The language item \"{}\" is used but never defined.",
+                name
+            ))
+            .with_text_info(format!(
+                "Define it by writing AS_LANGUAGE_ITEM({}) after its definition.",
+                name
+            ))
+    } else {
+        Diagnostic::new().with_text_error(format!("An item was never given a definition."))
+    };
+    let mut user = None;
+    for root in roots {
+        root.for_self_and_deep_contents(&mut |candidate| {
+            if user.is_none()
+                && candidate.borrow().position.is_some()
+                && candidate
+                    .borrow()
+                    .definition
+                    .contents()
+                    .iter()
+                    .any(|(_, content)| content.is_same_instance_as(item))
+            {
+                user = Some(candidate.ptr_clone());
+            }
+        });
+    }
+    if let Some(user) = user {
+        diagnostic = diagnostic
+            .with_text_info(format!("It is used here:"))
+            .with_item_info(&user, &user, env);
+    }
+    diagnostic
+}
+
 /// Returns Ok(true) if the resolution was successful, or Ok(false) if it
 /// was already resolved.
 fn resolve(env: &mut Environment, item: ItemPtr, limit: u32) -> Result<bool, ResolveError> {
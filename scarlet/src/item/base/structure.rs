@@ -0,0 +1,124 @@
+//! Hash consing for items. Every phrase creates its own item, so the same
+//! expression written twice ends up as two separate trees which equality
+//! would have to walk in full. Instead, each resolved item can be mapped to a
+//! canonical item which every structurally identical item shares, making the
+//! common case of comparing an item with a copy of itself a pointer compare.
+//!
+//! Items themselves are left alone, since they also carry names, positions
+//! and scopes which differ between copies. Like remembered equality results,
+//! the mapping is thrown away whenever an item is redefined.
+
+mod tests;
+
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+};
+
+use crate::item::ItemPtr;
+
+/// Everything two definitions of the same kind must have in common for their
+/// items to be structurally identical.
+#[derive(Clone, Debug)]
+pub struct Structure {
+    /// Anything besides items, like the label of a struct field.
+    pub data: String,
+    /// The items the definition refers to. These only have to be structurally
+    /// identical rather than the same items.
+    pub items: Vec<ItemPtr>,
+}
+
+impl Structure {
+    pub fn new(data: impl Into<String>, items: Vec<ItemPtr>) -> Self {
+        Self {
+            data: data.into(),
+            items,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct StructureKey {
+    kind: TypeId,
+    data: String,
+    /// The canonical versions of the items.
+    items: Vec<ItemPtr>,
+}
+
+#[derive(Default)]
+struct Table {
+    shared: HashMap<StructureKey, ItemPtr>,
+    canonical: HashMap<ItemPtr, ItemPtr>,
+}
+
+thread_local! {
+    static TABLE: RefCell<Table> = RefCell::new(Table::default());
+}
+
+/// Forgets which items are structurally identical, because an item changed or
+/// because the items involved are no longer needed.
+pub fn clear_structure_table() {
+    TABLE.with(|table| *table.borrow_mut() = Table::default());
+}
+
+impl ItemPtr {
+    /// Returns the item shared by every item structurally identical to this
+    /// one, after dereferencing. Unresolved items and items with an identity
+    /// of their own, like variables, are only identical to themselves.
+    pub fn canonical(&self) -> ItemPtr {
+        canonical(&self.dereference(), &mut Vec::new())
+    }
+
+    pub fn is_structurally_identical_to(&self, other: &ItemPtr) -> bool {
+        self.canonical().is_same_instance_as(&other.canonical())
+    }
+}
+
+fn canonical(item: &ItemPtr, visiting: &mut Vec<ItemPtr>) -> ItemPtr {
+    if let Some(known) = TABLE.with(|table| table.borrow().canonical.get(item).cloned()) {
+        return known;
+    }
+    // An item containing itself is only identical to itself, which also keeps
+    // this from recursing forever.
+    if visiting.contains(item) {
+        return item.ptr_clone();
+    }
+    let (kind, structure) = {
+        let borrowed = item.borrow();
+        let kind = (&*borrowed.definition as &dyn Any).type_id();
+        (kind, borrowed.definition.structure())
+    };
+    let result = match structure {
+        Some(structure) => {
+            visiting.push(item.ptr_clone());
+            let items = structure
+                .items
+                .iter()
+                .map(|contained| canonical(&contained.dereference(), visiting))
+                .collect();
+            visiting.pop();
+            let key = StructureKey {
+                kind,
+                data: structure.data,
+                items,
+            };
+            TABLE.with(|table| {
+                table
+                    .borrow_mut()
+                    .shared
+                    .entry(key)
+                    .or_insert_with(|| item.ptr_clone())
+                    .ptr_clone()
+            })
+        }
+        _ => item.ptr_clone(),
+    };
+    TABLE.with(|table| {
+        table
+            .borrow_mut()
+            .canonical
+            .insert(item.ptr_clone(), result.ptr_clone())
+    });
+    result
+}
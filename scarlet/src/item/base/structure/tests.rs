@@ -0,0 +1,63 @@
+#![cfg(test)]
+
+use crate::item::{definitions::other::DOther, test_util::*, util::*, ItemDefinition};
+
+#[test]
+fn copies_share_a_canonical_item() {
+    let a = unique();
+    let b = unique();
+    let c = unique();
+    let d = unique();
+    let dec1 = decision(a.ptr_clone(), b.ptr_clone(), c.ptr_clone(), d.ptr_clone());
+    let dec2 = decision(a.ptr_clone(), b.ptr_clone(), c.ptr_clone(), d.ptr_clone());
+    let dec3 = decision(a.ptr_clone(), b.ptr_clone(), d.ptr_clone(), c.ptr_clone());
+    assert!(dec1.is_structurally_identical_to(&dec2));
+    assert!(dec1.canonical().is_same_instance_as(&dec2.canonical()));
+    assert!(!dec1.is_structurally_identical_to(&dec3));
+    // Copies made from copies are still copies.
+    let outer1 = decision(
+        dec1.ptr_clone(),
+        a.ptr_clone(),
+        b.ptr_clone(),
+        c.ptr_clone(),
+    );
+    let outer2 = decision(
+        dec2.ptr_clone(),
+        a.ptr_clone(),
+        b.ptr_clone(),
+        c.ptr_clone(),
+    );
+    assert!(outer1.is_structurally_identical_to(&outer2));
+    assert!(other(outer1).is_structurally_identical_to(&outer2));
+}
+
+#[test]
+fn items_with_identities_are_only_identical_to_themselves() {
+    let a = unique();
+    let b = unique();
+    assert!(a.is_structurally_identical_to(&a));
+    assert!(!a.is_structurally_identical_to(&b));
+    let x = variable();
+    let y = variable();
+    assert!(!x.is_structurally_identical_to(&y));
+    let dec1 = decision(x.ptr_clone(), a.ptr_clone(), a.ptr_clone(), a.ptr_clone());
+    let dec2 = decision(y.ptr_clone(), a.ptr_clone(), a.ptr_clone(), a.ptr_clone());
+    assert!(!dec1.is_structurally_identical_to(&dec2));
+}
+
+#[test]
+fn redefining_an_item_forgets_old_structure() {
+    let a = unique();
+    let b = unique();
+    let item = other(a.ptr_clone());
+    let dec1 = decision(
+        item.ptr_clone(),
+        a.ptr_clone(),
+        a.ptr_clone(),
+        a.ptr_clone(),
+    );
+    let dec2 = decision(a.ptr_clone(), a.ptr_clone(), a.ptr_clone(), a.ptr_clone());
+    assert!(dec1.is_structurally_identical_to(&dec2));
+    item.redefine(DOther::new(b).clone_into_box());
+    assert!(!dec1.is_structurally_identical_to(&dec2));
+}
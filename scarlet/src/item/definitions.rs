@@ -1,8 +1,10 @@
 pub mod axiom;
 pub mod decision;
 pub mod is_populated_struct;
+pub mod number;
 pub mod other;
 pub mod placeholder;
+pub mod stand_in;
 pub mod structt;
 pub mod substitution;
 pub mod unique;
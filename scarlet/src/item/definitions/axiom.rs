@@ -8,6 +8,7 @@ use crate::{
         dependencies::{Dcc, DepResult, DependenciesFeature, OnlyCalledByDcc},
         equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
         invariants::{Icc, InvariantSet, InvariantsFeature, InvariantsResult, OnlyCalledByIcc},
+        structure::Structure,
         ContainmentType, ItemDefinition, ItemPtr,
     },
 };
@@ -55,6 +56,10 @@ impl ItemDefinition for DAxiom {
     fn contents(&self) -> Vec<(ContainmentType, ItemPtr)> {
         vec![(ContainmentType::Definitional, self.statement.ptr_clone())]
     }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new("", vec![self.statement.ptr_clone()]))
+    }
 }
 
 impl CheckFeature for DAxiom {}
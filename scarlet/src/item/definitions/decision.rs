@@ -1,14 +1,16 @@
 use crate::{
+    diagnostic::Diagnostic,
     environment::Environment,
     impl_any_eq_from_regular_eq,
     item::{
-        check::CheckFeature,
+        check::{CheckFeature, CheckResult, Kind},
         dependencies::{Dcc, DepResult, DependenciesFeature, OnlyCalledByDcc},
         equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
         invariants::{
             Icc, InvariantSet, InvariantSetPtr, InvariantsFeature, InvariantsResult,
             OnlyCalledByIcc,
         },
+        structure::Structure,
         ContainmentType, ItemDefinition, ItemPtr,
     },
     scope::{LookupIdentResult, ReverseLookupIdentResult, Scope},
@@ -77,9 +79,49 @@ impl ItemDefinition for DDecision {
             ),
         ]
     }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(
+            "",
+            vec![
+                self.left.ptr_clone(),
+                self.right.ptr_clone(),
+                self.when_equal.ptr_clone(),
+                self.when_not_equal.ptr_clone(),
+            ],
+        ))
+    }
 }
 
-impl CheckFeature for DDecision {}
+impl CheckFeature for DDecision {
+    fn check_self(&self, this: &ItemPtr, env: &mut Environment) -> CheckResult {
+        let void = env.get_void().dereference();
+        let has_void = [&self.left, &self.right]
+            .iter()
+            .any(|side| side.dereference().is_same_instance_as(&void));
+        // Void is a unique item, but it is more helpful to point out that it
+        // is the empty struct.
+        let describe = |side: &ItemPtr, kind: Kind| {
+            if side.dereference().is_same_instance_as(&void) {
+                "void, which is the empty struct,"
+            } else if has_void && kind == Kind::Struct {
+                "a struct with members"
+            } else {
+                kind.describe()
+            }
+        };
+        match (Kind::of(&self.left), Kind::of(&self.right)) {
+            (Some(left), Some(right)) if left.is_different_from(right) => Err(Diagnostic::new()
+                .with_text_error(format!(
+                    "This compares {} with {}, so the two sides can never be equal:",
+                    describe(&self.left, left),
+                    describe(&self.right, right)
+                ))
+                .with_item_error(this, this, env)),
+            _ => Ok(()),
+        }
+    }
+}
 
 impl DependenciesFeature for DDecision {
     fn get_dependencies_using_context(
@@ -172,6 +214,10 @@ impl Scope for SWithInvariant {
         Ok(None)
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         _env: &mut Environment,
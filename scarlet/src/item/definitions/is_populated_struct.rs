@@ -6,6 +6,7 @@ use crate::{
         dependencies::{Dcc, DepResult, DependenciesFeature, OnlyCalledByDcc},
         equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
         invariants::{Icc, InvariantsFeature, InvariantsResult, OnlyCalledByIcc},
+        structure::Structure,
         util::{is_bool, placeholder},
         Item, ItemDefinition, ItemPtr,
     },
@@ -41,6 +42,13 @@ impl ItemDefinition for DIsPopulatedStruct {
     fn clone_into_box(&self) -> Box<dyn ItemDefinition> {
         Box::new(self.clone())
     }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(
+            "",
+            vec![self.base.ptr_clone(), self.is_bool.ptr_clone()],
+        ))
+    }
 }
 
 impl CheckFeature for DIsPopulatedStruct {}
@@ -0,0 +1,218 @@
+use num::{BigInt, BigUint, One, Zero};
+
+use crate::{
+    environment::Environment,
+    impl_any_eq_from_regular_eq,
+    item::{
+        check::CheckFeature,
+        definitions::{
+            placeholder::DPlaceholder, substitution::Substitutions, variable::DVariable,
+        },
+        dependencies::DependenciesFeature,
+        equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
+        invariants::InvariantsFeature,
+        resolvable::UnresolvedItemError,
+        structure::Structure,
+        util::unchecked_substitution,
+        Item, ItemDefinition, ItemPtr,
+    },
+    scope::SRoot,
+    util::PtrExtension,
+};
+
+/// A natural number literal like `12`. Besides other literals, it can be
+/// compared with the encoding of natural numbers given by the nat_zero and
+/// nat_succ language items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DNatural {
+    value: BigUint,
+    zero: ItemPtr,
+    succ: ItemPtr,
+}
+
+impl DNatural {
+    pub fn new(env: &Environment, value: BigUint) -> Self {
+        Self {
+            value,
+            zero: env.get_language_item("nat_zero").unwrap().ptr_clone(),
+            succ: env.get_language_item("nat_succ").unwrap().ptr_clone(),
+        }
+    }
+
+    pub fn value(&self) -> &BigUint {
+        &self.value
+    }
+
+    /// Writes the number as either nat_zero or nat_succ applied to the number
+    /// before it. Returns None if those language items are not defined.
+    fn unfold(&self) -> Option<ItemPtr> {
+        let zero = defined_language_item(&self.zero)?;
+        let succ = defined_language_item(&self.succ)?;
+        if self.value.is_zero() {
+            return Some(zero);
+        }
+        let predecessor = Self {
+            value: &self.value - 1u32,
+            ..self.clone()
+        };
+        let target = succ
+            .get_dependencies()
+            .as_variables()
+            .next()?
+            .var
+            .ptr_clone();
+        let mut subs = Substitutions::new();
+        subs.insert_no_replace(target, Item::new(predecessor, SRoot));
+        Some(unchecked_substitution(succ, &subs))
+    }
+}
+
+fn defined_language_item(item: &ItemPtr) -> Option<ItemPtr> {
+    let item = item.dereference();
+    if item.downcast_definition::<DPlaceholder>().is_some() {
+        None
+    } else {
+        Some(item)
+    }
+}
+
+impl_any_eq_from_regular_eq!(DNatural);
+
+impl ItemDefinition for DNatural {
+    fn clone_into_box(&self) -> Box<dyn ItemDefinition> {
+        Box::new(self.clone())
+    }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(
+            self.value.to_string(),
+            vec![self.zero.ptr_clone(), self.succ.ptr_clone()],
+        ))
+    }
+}
+
+impl CheckFeature for DNatural {}
+impl DependenciesFeature for DNatural {}
+impl InvariantsFeature for DNatural {}
+
+impl EqualityFeature for DNatural {
+    fn get_equality_using_context(&self, ctx: &mut Ecc, _: OnlyCalledByEcc) -> EqualResult {
+        if let Some(other) = ctx.other().downcast_resolved_definition::<Self>()? {
+            return Ok(if other.value == self.value {
+                Equal::yes()
+            } else {
+                Equal::No
+            });
+        }
+        if ctx
+            .other()
+            .downcast_resolved_definition::<DInteger>()?
+            .is_some()
+        {
+            // Integers have no encoding, so nothing says how they relate to
+            // naturals.
+            return Ok(Equal::Unknown);
+        }
+        if ctx
+            .other()
+            .downcast_resolved_definition::<DVariable>()?
+            .is_some()
+        {
+            // Let the variable decide, it will want to be replaced with the
+            // literal itself rather than its encoding.
+            return Ok(Equal::Unknown);
+        }
+        match self.unfold() {
+            Some(unfolded) => ctx.with_primary(unfolded).get_equality_left(),
+            None => Ok(Equal::Unknown),
+        }
+    }
+}
+
+/// An integer literal with an explicit sign, like `-3` or `+3`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DInteger(BigInt);
+
+impl DInteger {
+    pub fn new(value: BigInt) -> Self {
+        Self(value)
+    }
+
+    pub fn value(&self) -> &BigInt {
+        &self.0
+    }
+}
+
+impl_any_eq_from_regular_eq!(DInteger);
+
+impl ItemDefinition for DInteger {
+    fn clone_into_box(&self) -> Box<dyn ItemDefinition> {
+        Box::new(self.clone())
+    }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(self.0.to_string(), vec![]))
+    }
+}
+
+impl CheckFeature for DInteger {}
+impl DependenciesFeature for DInteger {}
+impl InvariantsFeature for DInteger {}
+
+impl EqualityFeature for DInteger {
+    fn get_equality_using_context(&self, ctx: &mut Ecc, _: OnlyCalledByEcc) -> EqualResult {
+        let equal = if let Some(other) = ctx.other().downcast_resolved_definition::<Self>()? {
+            if other.0 == self.0 {
+                Equal::yes()
+            } else {
+                Equal::No
+            }
+        } else {
+            Equal::Unknown
+        };
+        Ok(equal)
+    }
+}
+
+/// Works out nat_succ and nat_add when they are applied to literals, so that
+/// `add(1 2)` is the same as `3`. Returns Ok(None) for any other substitution.
+pub fn fold_arithmetic(
+    env: &Environment,
+    base: &ItemPtr,
+    subs: &Substitutions,
+) -> Result<Option<DNatural>, UnresolvedItemError> {
+    let base = base.dereference();
+    let is_language_item = |name: &str| {
+        env.get_language_item(name)
+            .and_then(defined_language_item)
+            .map(|item| item.is_same_instance_as(&base))
+            .unwrap_or(false)
+    };
+    let (arity, start) = if is_language_item("nat_succ") {
+        (1, BigUint::one())
+    } else if is_language_item("nat_add") {
+        (2, BigUint::zero())
+    } else {
+        return Ok(None);
+    };
+    let deps = base.get_dependencies();
+    let targets = deps.as_variables().take(arity).collect::<Vec<_>>();
+    if targets.len() != arity {
+        return Ok(None);
+    }
+    let mut sum = start;
+    for target in targets {
+        let value = match subs
+            .iter()
+            .find(|(var, _)| var.is_same_instance_as(&target.var))
+        {
+            Some((_, value)) => value.dereference_resolved()?,
+            None => return Ok(None),
+        };
+        match value.downcast_resolved_definition::<DNatural>()? {
+            Some(natural) => sum += natural.value(),
+            None => return Ok(None),
+        };
+    }
+    Ok(Some(DNatural::new(env, sum)))
+}
@@ -0,0 +1,35 @@
+use crate::{
+    impl_any_eq_from_regular_eq,
+    item::{
+        check::CheckFeature,
+        dependencies::DependenciesFeature,
+        equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
+        invariants::InvariantsFeature,
+        ItemDefinition,
+    },
+};
+
+/// Stands in for code that could not be parsed. Nothing is known about what
+/// the code was meant to be, so it has no kind and is never known to be equal
+/// or unequal to anything. That way the syntax error is the only mistake
+/// reported about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DStandIn;
+
+impl_any_eq_from_regular_eq!(DStandIn);
+
+impl ItemDefinition for DStandIn {
+    fn clone_into_box(&self) -> Box<dyn ItemDefinition> {
+        Box::new(self.clone())
+    }
+}
+
+impl CheckFeature for DStandIn {}
+impl DependenciesFeature for DStandIn {}
+impl InvariantsFeature for DStandIn {}
+
+impl EqualityFeature for DStandIn {
+    fn get_equality_using_context(&self, _ctx: &mut Ecc, _: OnlyCalledByEcc) -> EqualResult {
+        Ok(Equal::Unknown)
+    }
+}
@@ -3,13 +3,16 @@ use std::fmt::{self, Debug, Formatter};
 use itertools::Itertools;
 
 use crate::{
+    diagnostic::Diagnostic,
     environment::Environment,
     impl_any_eq_from_regular_eq,
     item::{
-        check::CheckFeature,
+        check::{CheckFeature, CheckResult, Kind},
         dependencies::{Dcc, DepResult, DependenciesFeature, OnlyCalledByDcc},
         equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
         invariants::{Icc, InvariantSetPtr, InvariantsFeature, InvariantsResult, OnlyCalledByIcc},
+        resolvable::is_visible,
+        structure::Structure,
         ContainmentType, ItemDefinition, ItemPtr,
     },
     scope::{LookupIdentResult, ReverseLookupIdentResult, Scope},
@@ -53,6 +56,13 @@ impl ItemDefinition for DPopulatedStruct {
             (ContainmentType::Computational, self.rest.ptr_clone()),
         ]
     }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(
+            self.label.clone(),
+            vec![self.value.ptr_clone(), self.rest.ptr_clone()],
+        ))
+    }
 }
 
 impl CheckFeature for DPopulatedStruct {}
@@ -79,6 +89,8 @@ impl EqualityFeature for DPopulatedStruct {
                 return Ok(Equal::No);
             }
             Some([other.value.ptr_clone(), other.rest.ptr_clone()])
+        } else if Kind::Struct.is_different_from_item(ctx.other()) {
+            return Ok(Equal::No);
         } else {
             None
         };
@@ -159,9 +171,67 @@ impl ItemDefinition for DAtomicStructMember {
             vec![(ContainmentType::Computational, self.0.ptr_clone())]
         }
     }
+
+    fn structure(&self) -> Option<Structure> {
+        Some(Structure::new(
+            format!("{:?}", self.1),
+            vec![self.0.ptr_clone()],
+        ))
+    }
 }
 
-impl CheckFeature for DAtomicStructMember {}
+impl CheckFeature for DAtomicStructMember {
+    fn check_self(&self, this: &ItemPtr, env: &mut Environment) -> CheckResult {
+        let member = match self.1 {
+            AtomicStructMember::Label => ".LABEL",
+            AtomicStructMember::Value => ".VALUE",
+            AtomicStructMember::Rest => ".BODY",
+        };
+        let base = self.0.dereference();
+        if base.is_same_instance_as(&env.get_void().dereference()) {
+            return Err(Diagnostic::new()
+                .with_text_error(format!(
+                    "This takes {} of void, which is the empty struct and has no members:",
+                    member
+                ))
+                .with_item_error(this, this, env));
+        }
+        if let (AtomicStructMember::Value, Some(structt)) =
+            (self.1, base.downcast_definition::<DPopulatedStruct>())
+        {
+            if !is_visible(&base, &structt.value, &*this.clone_scope()) {
+                let name = if structt.label.len() > 0 {
+                    structt.label.clone()
+                } else {
+                    format!("This member")
+                };
+                let value = structt.value.ptr_clone();
+                drop(structt);
+                return Err(Diagnostic::new()
+                    .with_text_error(format!(
+                        "{} is private, so it can only be used inside the struct it is a \
+                         member of:",
+                        name
+                    ))
+                    .with_item_error(this, this, env)
+                    .with_text_info(format!("It is defined here:"))
+                    .with_item_info(&value, this, env));
+            }
+        }
+        match Kind::of(&base) {
+            Some(kind @ Kind::Unique) | Some(kind @ Kind::Integer) => Err(Diagnostic::new()
+                .with_text_error(format!(
+                    "This takes {} of {}, which is not a struct:",
+                    member,
+                    kind.describe()
+                ))
+                .with_item_error(this, this, env)
+                .with_text_info(format!("It is defined here:"))
+                .with_item_info(&base, this, env)),
+            _ => Ok(()),
+        }
+    }
+}
 impl EqualityFeature for DAtomicStructMember {
     fn get_equality_using_context(&self, ctx: &mut Ecc, _: OnlyCalledByEcc) -> EqualResult {
         if let Some(structt) = self
@@ -255,6 +325,18 @@ impl Scope for SField {
         }
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        if let Some(structt) = self.0.downcast_definition::<DPopulatedStruct>() {
+            if structt.label.len() > 0 {
+                vec![structt.label.clone()]
+            } else {
+                vec![]
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         _env: &mut Environment,
@@ -301,6 +383,17 @@ fn lookup_ident_in(ident: &str, inn: &DPopulatedStruct) -> LookupIdentResult {
     })
 }
 
+fn idents_in(inn: &DPopulatedStruct) -> Vec<String> {
+    let mut result = Vec::new();
+    if inn.label.len() > 0 {
+        result.push(inn.label.clone());
+    }
+    if let Some(rest) = inn.rest.downcast_definition::<DPopulatedStruct>() {
+        result.append(&mut idents_in(&rest));
+    }
+    result
+}
+
 fn reverse_lookup_ident_in(
     env: &mut Environment,
     value: ItemPtr,
@@ -338,6 +431,14 @@ impl Scope for SFieldAndRest {
         }
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        if let Some(structt) = self.0.downcast_definition::<DPopulatedStruct>() {
+            idents_in(&structt)
+        } else {
+            unreachable!()
+        }
+    }
+
     fn local_reverse_lookup_ident<'a, 'x>(
         &self,
         env: &'a mut Environment,
@@ -1,9 +1,11 @@
 use std::{collections::HashSet, fmt::Debug};
 
 use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
     impl_any_eq_from_regular_eq,
     item::{
-        check::CheckFeature,
+        check::{CheckFeature, CheckResult},
         definitions::variable::{DVariable, VariablePtr},
         dependencies::{
             Dcc, DepResult, Dependencies, DependenciesFeature, DependencyCalculationContext,
@@ -14,6 +16,7 @@ use crate::{
             Icc, InvariantSet, InvariantSetPtr, InvariantsFeature, InvariantsResult,
             OnlyCalledByIcc,
         },
+        structure::Structure,
         util::unchecked_substitution,
         ContainmentType, ItemDefinition, ItemPtr,
     },
@@ -63,6 +66,12 @@ impl DSubstitution {
         }
     }
 
+    /// The invariants the substitution was resolved with. These are empty
+    /// for unchecked substitutions.
+    pub fn invs(&self) -> &InvariantSetPtr {
+        &self.invs
+    }
+
     pub fn substitutions(&self) -> &Substitutions {
         &self.subs
     }
@@ -152,9 +161,44 @@ impl ItemDefinition for DSubstitution {
             )
             .collect()
     }
+
+    /// The invariants are left out, since they are worked out from the rest.
+    fn structure(&self) -> Option<Structure> {
+        let mut items = vec![self.base.ptr_clone()];
+        for (target, value) in &self.subs {
+            items.push(target.borrow().item().ptr_clone());
+            items.push(value.ptr_clone());
+        }
+        Some(Structure::new("", items))
+    }
 }
 
-impl CheckFeature for DSubstitution {}
+impl CheckFeature for DSubstitution {
+    fn check_self(&self, this: &ItemPtr, env: &mut Environment) -> CheckResult {
+        let base_deps = self.base.get_dependencies();
+        if base_deps.error().is_some() {
+            return Ok(());
+        }
+        for (target, _) in &self.subs {
+            if base_deps
+                .as_variables()
+                .any(|dep| dep.var.is_same_instance_as(target))
+            {
+                continue;
+            }
+            let target = env.show_var(target.ptr_clone(), this.ptr_clone());
+            return Err(Diagnostic::new()
+                .with_text_error(format!(
+                    "This substitutes {}, which the base does not depend on:",
+                    target
+                ))
+                .with_item_error(this, this, env)
+                .with_text_info(format!("The base is:"))
+                .with_item_info(&self.base, this, env));
+        }
+        Ok(())
+    }
+}
 
 impl DependenciesFeature for DSubstitution {
     fn get_dependencies_using_context(
@@ -3,7 +3,7 @@ use std::rc::Rc;
 use crate::{
     impl_any_eq_from_regular_eq,
     item::{
-        check::CheckFeature,
+        check::{CheckFeature, Kind},
         dependencies::DependenciesFeature,
         equality::{Ecc, Equal, EqualResult, EqualityFeature, OnlyCalledByEcc},
         invariants::InvariantsFeature,
@@ -45,6 +45,8 @@ impl EqualityFeature for DUnique {
             } else {
                 Equal::No
             }
+        } else if Kind::Unique.is_different_from_item(ctx.other()) {
+            Equal::No
         } else {
             Equal::Unknown
         };
@@ -392,6 +392,10 @@ impl Scope for SVariableInvariants {
         })
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        vec!["SELF".to_owned()]
+    }
+
     fn local_reverse_lookup_ident<'a, 'x>(
         &self,
         _env: &'a mut Environment,
@@ -1,5 +1,6 @@
 pub mod from;
 mod identifier;
+mod import;
 mod named_member;
 mod placeholder;
 mod substitution;
@@ -8,9 +9,12 @@ mod variable;
 use std::{any::Any, convert::Infallible, fmt::Debug, ops::FromResidual};
 
 pub use identifier::RIdentifier;
+pub use import::RImport;
 use itertools::Itertools;
+pub(crate) use named_member::is_visible;
 pub use named_member::RNamedMember;
 pub use placeholder::RPlaceholder;
+pub(crate) use substitution::checked_substitution;
 pub use substitution::RSubstitution;
 pub use variable::RVariable;
 
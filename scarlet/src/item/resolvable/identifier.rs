@@ -25,11 +25,10 @@ impl Resolvable for RIdentifier {
         _limit: u32,
     ) -> ResolveResult {
         let identified = scope.lookup_ident(&self.0)?.ok_or_else(|| {
-            {
-                Diagnostic::new()
-                    .with_text_error(format!("Cannot find what \"{}\" refers to", self.0))
-            }
-            .with_source_code_block_error(self.1)
+            Diagnostic::new()
+                .with_text_error(format!("Cannot find what \"{}\" refers to", self.0))
+                .with_source_code_block_error(self.1)
+                .with_candidates_info(&self.0, scope.idents())
         })?;
         ResolveResult::Ok(DOther::new(identified).clone_into_box())
     }
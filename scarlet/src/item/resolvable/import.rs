@@ -0,0 +1,124 @@
+use super::{
+    named_member::{check_visible, member_names},
+    BoxedResolvable, Resolvable, ResolveResult, UnresolvedItemError,
+};
+use crate::{
+    diagnostic::{Diagnostic, Position},
+    environment::Environment,
+    impl_any_eq_from_regular_eq,
+    item::{
+        definitions::{other::DOther, structt::DPopulatedStruct},
+        ContainmentType, ItemDefinition, ItemPtr,
+    },
+    scope::Scope,
+};
+
+/// `IMPORT std.logic AS logic`. The first part of the path is looked for in
+/// the root the import is written in, then in the roots of other libraries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RImport {
+    pub path: Vec<String>,
+    pub position: Position,
+}
+
+impl_any_eq_from_regular_eq!(RImport);
+
+/// Returns the value of the member with the given label, or Ok(None) if the
+/// item is a struct without one. Returns Err(false) if the item is not a
+/// struct at all.
+fn find_member(inn: ItemPtr, name: &str) -> Result<Result<ItemPtr, bool>, UnresolvedItemError> {
+    if let Some(cstruct) = inn
+        .dereference_resolved()?
+        .downcast_resolved_definition::<DPopulatedStruct>()?
+    {
+        if cstruct.get_label() == name {
+            Ok(Ok(cstruct.get_value().ptr_clone()))
+        } else {
+            let rest = cstruct.get_rest().ptr_clone();
+            drop(cstruct);
+            Ok(find_member(rest, name)?.map_err(|_| true))
+        }
+    } else {
+        Ok(Err(false))
+    }
+}
+
+impl Resolvable for RImport {
+    fn dyn_clone(&self) -> BoxedResolvable {
+        Box::new(self.clone())
+    }
+
+    fn resolve(
+        &self,
+        env: &mut Environment,
+        _this: ItemPtr,
+        scope: Box<dyn Scope>,
+        _limit: u32,
+    ) -> ResolveResult {
+        let mut root = scope.parent().expect("imports are always inside a struct");
+        loop {
+            let parent = root.borrow().scope.parent();
+            match parent {
+                Some(parent) => root = parent,
+                None => break,
+            }
+        }
+
+        let first = &self.path[0];
+        let mut target = match find_member(root.ptr_clone(), first)? {
+            Ok(member) => member,
+            Err(_) => match env.get_module(first) {
+                Some(module) => module.ptr_clone(),
+                None => {
+                    let mut candidates = member_names(root)?;
+                    candidates.append(&mut env.module_names());
+                    return ResolveResult::Err(
+                        Diagnostic::new()
+                            .with_text_error(format!("Cannot find a module named \"{}\":", first))
+                            .with_source_code_block_error(self.position)
+                            .with_candidates_info(first, candidates)
+                            .into(),
+                    );
+                }
+            },
+        };
+        for (index, name) in self.path.iter().enumerate().skip(1) {
+            let parent_path = self.path[..index].join(".");
+            target = match find_member(target.ptr_clone(), name)? {
+                Ok(member) => {
+                    let structt = target.dereference_resolved()?;
+                    check_visible(&structt, &member, name, &*scope, self.position)?;
+                    member
+                }
+                Err(true) => {
+                    return ResolveResult::Err(
+                        Diagnostic::new()
+                            .with_text_error(format!(
+                                "\"{}\" has no member named \"{}\":",
+                                parent_path, name
+                            ))
+                            .with_source_code_block_error(self.position)
+                            .with_candidates_info(name, member_names(target)?)
+                            .into(),
+                    )
+                }
+                Err(false) => {
+                    return ResolveResult::Err(
+                        Diagnostic::new()
+                            .with_text_error(format!(
+                                "\"{}\" is not a struct, so nothing can be imported from it:",
+                                parent_path
+                            ))
+                            .with_source_code_block_error(self.position)
+                            .into(),
+                    )
+                }
+            };
+        }
+        ResolveResult::Ok(DOther::new(target).clone_into_box())
+    }
+
+    fn contents(&self) -> Vec<(ContainmentType, &ItemPtr)> {
+        vec![]
+    }
+}
@@ -45,6 +45,75 @@ fn find_member(
     }
 }
 
+pub(super) fn member_names(inn: ItemPtr) -> Result<Vec<String>, UnresolvedItemError> {
+    if let Some(cstruct) = inn
+        .dereference_resolved()?
+        .downcast_resolved_definition::<DPopulatedStruct>()?
+    {
+        let mut result = member_names(cstruct.get_rest().ptr_clone())?;
+        if cstruct.get_label().len() > 0 {
+            result.insert(0, cstruct.get_label().to_owned());
+        }
+        Ok(result)
+    } else {
+        Ok(vec![])
+    }
+}
+
+fn member_value(inn: ItemPtr, depth: u32) -> Result<ItemPtr, UnresolvedItemError> {
+    let inn = inn.dereference_resolved()?;
+    let cstruct = inn
+        .downcast_resolved_definition::<DPopulatedStruct>()?
+        .expect("find_member already checked that this is a struct");
+    if depth == 0 {
+        Ok(cstruct.get_value().ptr_clone())
+    } else {
+        let rest = cstruct.get_rest().ptr_clone();
+        drop(cstruct);
+        member_value(rest, depth - 1)
+    }
+}
+
+/// Private members can only be reached from inside the struct they are a
+/// member of.
+pub(crate) fn is_visible(structt: &ItemPtr, member: &ItemPtr, scope: &dyn Scope) -> bool {
+    if !member.borrow().private {
+        return true;
+    }
+    let mut parent = scope.parent();
+    while let Some(item) = parent {
+        if item.is_same_instance_as(structt) {
+            return true;
+        }
+        parent = item.borrow().scope.parent();
+    }
+    false
+}
+
+pub(super) fn check_visible(
+    structt: &ItemPtr,
+    member: &ItemPtr,
+    member_name: &str,
+    scope: &dyn Scope,
+    position: Position,
+) -> Result<(), Diagnostic> {
+    if is_visible(structt, member, scope) {
+        return Ok(());
+    }
+    let mut diagnostic = Diagnostic::new()
+        .with_text_error(format!(
+            "{} is private, so it can only be used inside the struct it is a member of:",
+            member_name
+        ))
+        .with_source_code_block_error(position);
+    if let Some(defined) = member.borrow().position {
+        diagnostic = diagnostic
+            .with_text_info(format!("It is defined here:"))
+            .with_source_code_block_info(defined);
+    }
+    Err(diagnostic)
+}
+
 impl Resolvable for RNamedMember {
     fn dyn_clone(&self) -> BoxedResolvable {
         Box::new(self.clone())
@@ -61,6 +130,7 @@ impl Resolvable for RNamedMember {
         let access_depth = if let Some(ad) = access_depth {
             ad
         } else {
+            let candidates = member_names(self.base.ptr_clone())?;
             return ResolveResult::Err(
                 Diagnostic::new()
                     .with_text_error(format!(
@@ -68,6 +138,7 @@ impl Resolvable for RNamedMember {
                         self.member_name
                     ))
                     .with_source_code_block_error(self.position)
+                    .with_candidates_info(&self.member_name, candidates)
                     .with_text_info(format!("The base is defined as follows:"))
                     .with_item_info(
                         self.base.dereference_once().as_ref().unwrap_or(&self.base),
@@ -77,6 +148,14 @@ impl Resolvable for RNamedMember {
                     .into(),
             );
         };
+        let member = member_value(self.base.ptr_clone(), access_depth)?;
+        check_visible(
+            &self.base.dereference_resolved()?,
+            &member,
+            &self.member_name,
+            &*scope,
+            self.position,
+        )?;
         let mut base = self.base.ptr_clone();
         for _ in 0..access_depth {
             base = Item::new_boxed(
@@ -7,13 +7,14 @@ use crate::{
     impl_any_eq_from_regular_eq,
     item::{
         definitions::{
+            number::fold_arithmetic,
             substitution::{DSubstitution, Substitutions},
             variable::{DVariable, Variable},
         },
         dependencies::{Dcc, Dependencies},
         invariants::{InvariantSet, InvariantsResult},
         util::unchecked_substitution,
-        ContainmentType, ItemDefinition, ItemPtr,
+        ContainmentType, Item, ItemDefinition, ItemPtr,
     },
     scope::Scope,
     shared::OrderedMap,
@@ -82,9 +83,12 @@ impl Resolvable for RSubstitution {
         self.resolve_named_subs(base_scope, env, &mut subs, &mut remaining_deps)?;
         self.resolve_anonymous_subs(total_dep_count, remaining_deps, env, &mut subs)?;
         resolve_dep_subs(&mut subs)?;
+        if let Some(folded) = fold_arithmetic(env, &base, &subs)? {
+            return ResolveResult::Ok(folded.clone_into_box());
+        }
 
         let justifications = make_justification_statements(&subs, limit)?;
-        let invs = create_invariants(env, this, base, &subs, justifications)?;
+        let invs = create_invariants(this, base, &subs, justifications)?;
         let csub = DSubstitution::new(self.base.ptr_clone(), subs, invs);
         ResolveResult::Ok(csub.clone_into_box())
     }
@@ -113,7 +117,6 @@ impl Resolvable for RSubstitution {
 }
 
 fn create_invariants(
-    _env: &mut Environment,
     this: ItemPtr,
     base: ItemPtr,
     subs: &Substitutions,
@@ -129,6 +132,23 @@ fn create_invariants(
     Ok(InvariantSet::new(this, invs, justifications, hashset![]))
 }
 
+/// Substitutes into the base the way a substitution written in the source
+/// code would, so the result requires the values to meet the invariants of
+/// the variables they replace. This is how auto theorems are applied.
+pub(crate) fn checked_substitution(
+    base: ItemPtr,
+    subs: Substitutions,
+    scope: Box<dyn Scope>,
+) -> Result<ItemPtr, ResolveError> {
+    let justifications = make_justification_statements(&subs, 0)?;
+    let this = Item::placeholder_with_scope(scope);
+    let invs = create_invariants(this.ptr_clone(), base.ptr_clone(), &subs, justifications)?;
+    // Nothing can have looked at the placeholder yet, so there is nothing for
+    // redefine to forget.
+    this.borrow_mut().definition = DSubstitution::new(base, subs, invs).clone_into_box();
+    Ok(this)
+}
+
 /// Finds invariants that confirm the substitutions we're performing are legal.
 /// For example, an_int[an_int IS something] would need an invariant of the form
 /// `(an_int FROM I32)[an_int IS something]`.
@@ -216,7 +236,18 @@ impl RSubstitution {
         remaining_deps: &mut Dependencies,
     ) -> Result<(), ResolveError> {
         for (position, name, value) in &self.named_subs {
-            let target = base_scope.lookup_ident(&name)?.unwrap();
+            let target = if let Some(target) = base_scope.lookup_ident(&name)? {
+                target
+            } else {
+                return Err(Diagnostic::new()
+                    .with_text_error(format!(
+                        "Cannot find a variable named \"{}\" to substitute:",
+                        name
+                    ))
+                    .with_source_code_block_error(*position)
+                    .with_candidates_info(name, base_scope.idents())
+                    .into());
+            };
             if let Some(var) = target
                 .dereference()
                 .downcast_resolved_definition::<DVariable>()?
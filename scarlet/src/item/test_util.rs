@@ -1,6 +1,11 @@
 #![cfg(test)]
 
-use std::sync::Mutex;
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 use lazy_static::lazy_static;
 
@@ -30,6 +35,53 @@ lazy_static! {
     static ref VARIABLE_COUNTER: Mutex<u32> = Mutex::new(0);
 }
 
+/// The language items a root needs before the whole pipeline will accept it.
+pub(crate) const LANGUAGE_ITEMS: &str = "void IS UNIQUE AS_LANGUAGE_ITEM(void)
+true IS UNIQUE AS_LANGUAGE_ITEM(true)
+false IS UNIQUE AS_LANGUAGE_ITEM(false)
+";
+
+/// A single axiom, and a statement that can only be justified by using it.
+pub(crate) const AXIOM_CODE: &str = r"
+x IS VAR()
+statement IS x AS_LANGUAGE_ITEM(trivial_t_statement)
+trivial_t IS AXIOM(trivial_t)
+
+a IS UNIQUE
+trivial_t(a)
+
+y IS VAR(SELF)
+uses_axiom IS y(a)
+";
+
+/// An empty folder for a test to write files into, which is removed again
+/// when this is dropped. Each test passes its own name so that tests running
+/// at the same time don't share a folder.
+pub(crate) struct TempFolder(PathBuf);
+
+impl TempFolder {
+    pub(crate) fn new(test: &str) -> Self {
+        let folder = std::env::temp_dir().join(format!("scarlet-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        Self(folder)
+    }
+}
+
+impl Deref for TempFolder {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
 pub(super) fn env() -> Environment {
     Environment::new()
 }
@@ -49,14 +101,15 @@ pub(super) fn with_env_from_code(code: &str, callback: impl FnOnce(Environment,
         def.set_name(lang_item_name.to_owned());
         env.define_language_item(lang_item_name, def);
     }
-    resolve_all(&mut env, root.ptr_clone()).unwrap();
+    resolve_all(&mut env, &[root.ptr_clone()]).unwrap();
 
     callback(env, root)
 }
 
 fn env_from_code<'x>(code: &'x FileNode, pc: &'x ParseContext) -> (Environment, ItemPtr) {
     let mut file_counter = 0;
-    let parsed = parse_tree(code, &pc, &mut file_counter).unwrap();
+    let (parsed, diagnostics) = parse_tree(code, &pc, &mut file_counter);
+    assert!(diagnostics.is_empty(), "{:#?}", diagnostics);
 
     let mut env = env();
     let root = parsed.as_item(&pc, &mut env, SRoot).unwrap();
@@ -0,0 +1,377 @@
+//! A language server that talks JSON-RPC over stdin and stdout. It checks the
+//! whole source tree whenever a file is opened or saved, so it only ever sees
+//! what is on disk.
+
+mod protocol;
+mod tests;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use self::protocol::{
+    error_response, notification, path_to_uri, position_to_offset, range, read_message, response,
+    uri_to_path, write_message,
+};
+use crate::{
+    diagnostic::{Diagnostic, Element, Level, Position},
+    file_tree,
+    item::{definitions::other::DOther, ItemPtr},
+    pipeline::{self, Analysis},
+};
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+pub struct Server {
+    root: Option<PathBuf>,
+    analysis: Option<Analysis>,
+    /// Documents we have published diagnostics for, which need to be cleared
+    /// if they stop having any.
+    published: HashSet<String>,
+    /// The document that diagnostics without a location get attached to.
+    last_document: Option<String>,
+    initialized: bool,
+    shutdown_requested: bool,
+}
+
+/// Runs the server on stdin and stdout, returning the exit code the process
+/// should use.
+pub fn run(root: Option<String>) -> i32 {
+    let mut server = Server::new(root.map(PathBuf::from));
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match server.serve(stdin.lock(), stdout.lock()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Language server stopped: {}", err);
+            1
+        }
+    }
+}
+
+impl Server {
+    pub fn new(root: Option<PathBuf>) -> Self {
+        Self {
+            root: root.map(|root| absolute(&root)),
+            analysis: None,
+            published: HashSet::new(),
+            last_document: None,
+            initialized: false,
+            shutdown_requested: false,
+        }
+    }
+
+    /// Handles messages until the client asks us to exit, returning the exit
+    /// code the protocol asks for.
+    pub fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<i32> {
+        while let Some(message) = read_message(&mut input)? {
+            let method = message["method"].as_str().unwrap_or("").to_owned();
+            if method == "exit" {
+                break;
+            }
+            let params = &message["params"];
+            let result = match &method[..] {
+                _ if self.shutdown_requested => {
+                    if !message["id"].is_null() {
+                        let error = error_response(
+                            &message["id"],
+                            INVALID_REQUEST,
+                            "The server has been shut down",
+                        );
+                        write_message(&mut output, &error)?;
+                    }
+                    None
+                }
+                "initialize" => Some(self.initialize(params)),
+                "shutdown" => {
+                    self.shutdown_requested = true;
+                    Some(Value::Null)
+                }
+                _ if !self.initialized => {
+                    if !message["id"].is_null() {
+                        let error = error_response(
+                            &message["id"],
+                            SERVER_NOT_INITIALIZED,
+                            "The server has not been initialized yet",
+                        );
+                        write_message(&mut output, &error)?;
+                    }
+                    None
+                }
+                "initialized" => None,
+                "textDocument/didOpen" | "textDocument/didSave" => {
+                    self.last_document = params["textDocument"]["uri"].as_str().map(Into::into);
+                    self.check(&mut output)?;
+                    None
+                }
+                "textDocument/definition" => Some(self.definition(params)),
+                "textDocument/hover" => Some(self.hover(params)),
+                _ => {
+                    if !message["id"].is_null() {
+                        let error = error_response(
+                            &message["id"],
+                            METHOD_NOT_FOUND,
+                            &format!("Unsupported method {}", method),
+                        );
+                        write_message(&mut output, &error)?;
+                    }
+                    None
+                }
+            };
+            if let (Some(result), false) = (result, message["id"].is_null()) {
+                write_message(&mut output, &response(&message["id"], result))?;
+            }
+            if method == "initialize" && self.root.is_none() {
+                let params = json!({
+                    "type": 2,
+                    "message": "There is no root to check, open a folder or set the root \
+                        initialization option.",
+                });
+                write_message(&mut output, &notification("window/showMessage", params))?;
+            }
+        }
+        Ok(if self.shutdown_requested { 0 } else { 1 })
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        self.initialized = true;
+        if self.root.is_none() {
+            let workspace = params["rootUri"]
+                .as_str()
+                .and_then(uri_to_path)
+                .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+            let option = params["initializationOptions"]["root"].as_str();
+            self.root = match (workspace, option) {
+                (Some(workspace), Some(option)) => Some(workspace.join(option)),
+                (None, Some(option)) => Some(absolute(Path::new(option))),
+                (workspace, None) => workspace,
+            };
+        }
+        json!({
+            "capabilities": {
+                "textDocumentSync": {
+                    "openClose": true,
+                    "change": 0,
+                    "save": { "includeText": false },
+                },
+                "definitionProvider": true,
+                "hoverProvider": true,
+            },
+            "serverInfo": { "name": "scarlet" },
+        })
+    }
+
+    /// Runs the whole pipeline and publishes the diagnostics it produces.
+    fn check(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let root = if let Some(root) = &self.root {
+            root.clone()
+        } else {
+            return Ok(());
+        };
+        let analysis = panic::catch_unwind(AssertUnwindSafe(|| pipeline::analyze(&root)));
+        let mut by_document: HashMap<String, Vec<Value>> = HashMap::new();
+        match analysis {
+            Ok(analysis) => {
+                for diagnostic in &analysis.diagnostics {
+                    let (document, diagnostic) = self.convert_diagnostic(&analysis, diagnostic);
+                    if let Some(document) = document {
+                        by_document.entry(document).or_default().push(diagnostic);
+                    }
+                }
+                self.analysis = Some(analysis);
+            }
+            Err(panic) => {
+                let reason = panic
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| panic.downcast_ref::<&str>().map(|text| text.to_string()))
+                    .unwrap_or_default();
+                if let Some(document) = self.last_document.clone() {
+                    by_document.entry(document).or_default().push(json!({
+                        "range": range("", 0, 0),
+                        "severity": 1,
+                        "source": "scarlet",
+                        "message": format!("The checker crashed: {}", reason),
+                    }));
+                }
+            }
+        }
+        let mut documents: HashSet<String> = by_document.keys().cloned().collect();
+        documents.extend(self.published.drain());
+        for document in documents {
+            let diagnostics = by_document.remove(&document).unwrap_or_default();
+            if diagnostics.len() > 0 {
+                self.published.insert(document.clone());
+            }
+            let params = json!({ "uri": document, "diagnostics": diagnostics });
+            write_message(
+                output,
+                &notification("textDocument/publishDiagnostics", params),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The first source code block in a diagnostic decides where it is shown,
+    /// the rest become related information.
+    fn convert_diagnostic(
+        &self,
+        analysis: &Analysis,
+        diagnostic: &Diagnostic,
+    ) -> (Option<String>, Value) {
+        let mut location = None;
+        let mut message = Vec::new();
+        let mut related = Vec::new();
+        let mut last_text = diagnostic.message();
+        for (_, element) in diagnostic.elements() {
+            match element {
+                Element::Text(text) => {
+                    message.push(text.clone());
+                    last_text = text;
+                }
+                Element::GeneratedCodeBlock(code) => message.push(code.clone()),
+                Element::SourceCodeBlock(position) => {
+                    let here = self.location(analysis, *position);
+                    if location.is_none() {
+                        location = Some(here);
+                    } else {
+                        related.push(json!({ "location": here, "message": last_text }));
+                    }
+                }
+            }
+        }
+        let severity = match diagnostic.level() {
+            Level::Error => 1,
+            Level::Warning => 2,
+            Level::Info => 3,
+        };
+        let (document, range) = match location {
+            Some(location) => (
+                location["uri"].as_str().map(Into::into),
+                location["range"].clone(),
+            ),
+            None => (self.last_document.clone(), range("", 0, 0)),
+        };
+        let result = json!({
+            "range": range,
+            "severity": severity,
+            "source": "scarlet",
+            "message": message.join("\n"),
+            "relatedInformation": related,
+        });
+        (document, result)
+    }
+
+    fn location(&self, analysis: &Analysis, position: Position) -> Value {
+        let (path, content) = analysis.file_tree.get_file(position.file_index());
+        let path = file_tree::source_path(&analysis.base, &path);
+        let range = position.range();
+        json!({
+            "uri": path_to_uri(&absolute(&path)),
+            "range": protocol::range(content, range.start, range.end),
+        })
+    }
+
+    /// Finds the smallest item whose source code contains the given position,
+    /// along with the identifier the position is inside of.
+    fn item_at(&self, params: &Value) -> Option<(ItemPtr, String)> {
+        let analysis = self.analysis.as_ref()?;
+        let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+        let path = canonical(&path);
+        let file_index =
+            analysis.file_tree.paths().iter().position(|file| {
+                canonical(&file_tree::source_path(&analysis.base, file)) == path
+            })? + 1;
+        let (_, content) = analysis.file_tree.get_file(file_index);
+        let offset = position_to_offset(content, &params["position"])?;
+
+        let mut best: Option<(ItemPtr, usize)> = None;
+        for root in analysis.env.module_roots() {
+            root.for_self_and_deep_contents(&mut |item| {
+                if let Some(position) = item.borrow().position {
+                    let range = position.range();
+                    let size = range.end - range.start;
+                    if position.file_index() == file_index
+                        && range.start <= offset
+                        && offset < range.end
+                        && best.as_ref().map(|(_, best)| size < *best).unwrap_or(true)
+                    {
+                        best = Some((item.ptr_clone(), size));
+                    }
+                }
+            });
+        }
+        let is_ident = |char: char| char.is_alphanumeric() || char == '_';
+        let start = content[..offset]
+            .rfind(|char| !is_ident(char))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let end = content[offset..]
+            .find(|char| !is_ident(char))
+            .map(|index| offset + index)
+            .unwrap_or(content.len());
+        best.map(|(item, _)| (item, content[start..end].to_owned()))
+    }
+
+    fn definition(&mut self, params: &Value) -> Value {
+        let (item, ident) = if let Some(found) = self.item_at(params) {
+            found
+        } else {
+            return Value::Null;
+        };
+        let is_identifier = item.downcast_definition::<DOther>().is_some();
+        let target = if is_identifier && ident.len() > 0 {
+            item.lookup_ident(&ident).ok().flatten()
+        } else {
+            // Member accesses and the like, follow them until we find
+            // something that was written somewhere else.
+            let mut target = item.dereference_once();
+            while let Some(candidate) = target.clone() {
+                if candidate.borrow().position.is_some()
+                    && candidate.borrow().position != item.borrow().position
+                {
+                    break;
+                }
+                target = candidate.dereference_once();
+            }
+            target
+        };
+        let position = target.and_then(|target| target.borrow().position);
+        match (position, &self.analysis) {
+            (Some(position), Some(analysis)) => self.location(analysis, position),
+            _ => Value::Null,
+        }
+    }
+
+    fn hover(&mut self, params: &Value) -> Value {
+        let (item, _) = if let Some(found) = self.item_at(params) {
+            found
+        } else {
+            return Value::Null;
+        };
+        let analysis = self.analysis.as_mut().unwrap();
+        let shown = analysis.env.show(item.ptr_clone(), item.ptr_clone());
+        json!({
+            "contents": { "kind": "markdown", "value": format!("```scarlet\n{}\n```", shown) },
+        })
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir().unwrap().join(path)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| absolute(path))
+}
@@ -0,0 +1,130 @@
+//! The parts of the Language Server Protocol that don't depend on Scarlet:
+//! message framing and converting between the ways positions are described.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+/// Reads one message, returning None once the input has ended.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.len() == 0 {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Message has no Content-Length")
+    })?;
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = serde_json::to_string(message).unwrap();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+pub fn response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::new();
+    let mut index = 0;
+    while index < encoded.len() {
+        if encoded[index] == b'%' && index + 2 < encoded.len() {
+            let hex = std::str::from_utf8(&encoded[index + 1..index + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(encoded[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Converts a byte offset into a zero-based line and a character offset
+/// measured in UTF-16 code units, which is what the protocol uses.
+pub fn offset_to_position(content: &str, offset: usize) -> Value {
+    let mut line = 0;
+    let mut character = 0;
+    for char in content[..offset.min(content.len())].chars() {
+        if char == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += char.len_utf16();
+        }
+    }
+    json!({ "line": line, "character": character })
+}
+
+pub fn position_to_offset(content: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let mut line_start = 0;
+    for _ in 0..line {
+        line_start += content[line_start..].find('\n')? + 1;
+    }
+    let mut units = 0;
+    for (index, char) in content[line_start..].char_indices() {
+        if units >= character || char == '\n' {
+            return Some(line_start + index);
+        }
+        units += char.len_utf16();
+    }
+    Some(content.len())
+}
+
+pub fn range(content: &str, start: usize, end: usize) -> Value {
+    json!({
+        "start": offset_to_position(content, start),
+        "end": offset_to_position(content, end),
+    })
+}
@@ -0,0 +1,182 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::{
+    protocol::{path_to_uri, read_message, write_message},
+    Server,
+};
+
+fn full() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("full")
+}
+
+fn uri(file: &str) -> String {
+    path_to_uri(&full().join(file))
+}
+
+/// Feeds the given messages to a fresh server and returns everything it sent
+/// back, along with its exit code.
+fn run_script(messages: Vec<Value>) -> (Vec<Value>, i32) {
+    let mut input = Vec::new();
+    for message in &messages {
+        write_message(&mut input, message).unwrap();
+    }
+    let mut output = Vec::new();
+    let code = Server::new(None).serve(&input[..], &mut output).unwrap();
+    let mut replies = Vec::new();
+    let mut output = &output[..];
+    while let Some(reply) = read_message(&mut output).unwrap() {
+        replies.push(reply);
+    }
+    (replies, code)
+}
+
+fn initialize() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": { "rootUri": path_to_uri(&full()), "capabilities": {} },
+    })
+}
+
+fn open(file: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri(file), "languageId": "scarlet", "version": 0, "text": "" },
+        },
+    })
+}
+
+fn request(id: u64, method: &str, file: &str, line: u64, character: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": uri(file) },
+            "position": { "line": line, "character": character },
+        },
+    })
+}
+
+fn shutdown_and_exit() -> Vec<Value> {
+    vec![
+        json!({ "jsonrpc": "2.0", "id": 99, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]
+}
+
+fn reply_to(replies: &[Value], id: u64) -> &Value {
+    replies
+        .iter()
+        .find(|reply| reply["id"] == json!(id))
+        .expect("no reply to request")
+}
+
+#[test]
+fn exit_code_depends_on_shutdown() {
+    let (_, code) = run_script(vec![initialize(), json!({ "method": "exit" })]);
+    assert_eq!(code, 1);
+    let mut script = vec![initialize()];
+    script.append(&mut shutdown_and_exit());
+    let (replies, code) = run_script(script);
+    assert_eq!(code, 0);
+    assert_eq!(
+        reply_to(&replies, 0)["result"]["capabilities"]["hoverProvider"],
+        true
+    );
+    assert_eq!(reply_to(&replies, 99)["result"], Value::Null);
+}
+
+#[test]
+fn requests_after_shutdown_are_invalid() {
+    let script = vec![
+        initialize(),
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" }),
+        open("misc.sr"),
+        request(2, "textDocument/hover", "misc.sr", 0, 0),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ];
+    let (replies, code) = run_script(script);
+    assert_eq!(code, 0);
+    assert_eq!(reply_to(&replies, 1)["result"], Value::Null);
+    assert_eq!(reply_to(&replies, 2)["error"]["code"], -32600);
+    assert_eq!(reply_to(&replies, 3)["error"]["code"], -32600);
+    assert!(!replies
+        .iter()
+        .any(|reply| reply["method"] == "textDocument/publishDiagnostics"));
+}
+
+#[test]
+fn works_without_a_root() {
+    let mut script = vec![
+        request(1, "textDocument/hover", "misc.sr", 0, 0),
+        json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} }),
+        open("misc.sr"),
+        request(2, "textDocument/hover", "misc.sr", 0, 0),
+    ];
+    script.append(&mut shutdown_and_exit());
+    let (replies, code) = run_script(script);
+    assert_eq!(code, 0);
+    assert_eq!(reply_to(&replies, 1)["error"]["code"], -32002);
+    assert_eq!(
+        reply_to(&replies, 0)["result"]["capabilities"]["hoverProvider"],
+        true
+    );
+    assert!(replies
+        .iter()
+        .any(|reply| reply["method"] == "window/showMessage"));
+    assert_eq!(reply_to(&replies, 2)["result"], Value::Null);
+    assert_eq!(reply_to(&replies, 99)["result"], Value::Null);
+}
+
+#[test]
+fn unknown_request_is_an_error() {
+    let mut script = vec![
+        initialize(),
+        request(1, "textDocument/rename", "misc.sr", 0, 0),
+    ];
+    script.append(&mut shutdown_and_exit());
+    let (replies, _) = run_script(script);
+    assert_eq!(reply_to(&replies, 1)["error"]["code"], -32601);
+}
+
+#[test]
+fn definition_and_hover() {
+    let mut script = vec![
+        initialize(),
+        open("misc.sr"),
+        // logic IS std.logic
+        //          ^
+        request(1, "textDocument/definition", "misc.sr", 0, 10),
+        request(2, "textDocument/hover", "misc.sr", 0, 10),
+    ];
+    script.append(&mut shutdown_and_exit());
+    let (replies, _) = run_script(script);
+
+    let published = replies
+        .iter()
+        .filter(|reply| reply["method"] == "textDocument/publishDiagnostics")
+        .collect::<Vec<_>>();
+    for notification in published {
+        assert_eq!(notification["params"]["diagnostics"], json!([]));
+    }
+
+    let definition = &reply_to(&replies, 1)["result"];
+    assert_eq!(definition["uri"], uri("std.sr"));
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 0, "character": 0 })
+    );
+
+    let hover = &reply_to(&replies, 2)["result"];
+    let hover = hover["contents"]["value"].as_str().unwrap();
+    assert!(hover.starts_with("```scarlet\n"));
+}
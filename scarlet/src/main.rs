@@ -12,68 +12,206 @@
 #![feature(core_intrinsics)]
 
 use crate::{
-    environment::Environment, item::resolve::resolve_all, parser::ParseContext, scope::SRoot,
+    check::Mode,
+    output::{ColorChoice, MessageFormat, Output, Verbosity},
 };
 
+mod axioms;
+mod cache;
+mod certify;
+mod check;
 pub mod diagnostic;
 mod environment;
+mod explain;
 mod file_tree;
+mod fmt;
+mod golden;
+mod graph;
 mod item;
+mod lsp;
+mod modules;
+mod output;
 pub mod parser;
+mod pipeline;
+mod repl;
 pub mod scope;
 mod shared;
 mod util;
+mod watch;
 
-fn entry() {
-    let path = std::env::args().skip(1).next().unwrap_or(String::from("."));
-    println!("Reading source from {}", path);
+const USAGE: &str = "\
+Usage: scarlet [OPTIONS] [COMMAND] [ARGS]
 
-    let file_tree = file_tree::read_root(&path).unwrap();
+Commands:
+    check [PATH]             Check a root and report any problems
+    show [PATH]              Check a root and print every item marked SHOWN
+    fmt [PATH]               Rewrite every file in a root in the canonical layout
+    axioms [PATH]            List the axioms each justified statement relies on
+    explain PATH NAME        Explain how items with the given name are justified
+    repl [PATH]              Evaluate expressions typed in against a root
+    graph [PATH]             Write dependency and justification graphs as DOT files
+    certify [PATH]           Print a certificate of how a root was justified
+    verify PATH CERTIFICATE  Check a certificate without searching for justifications
+    test [FOLDER]            Run the roots in a folder against their annotations
+    lsp [ROOT]               Start a language server on stdin and stdout
 
-    let parse_context = ParseContext::new();
-    let mut file_counter = 0;
-    let root = parser::parse_tree(&file_tree, &parse_context, &mut file_counter);
-    let root = match root {
-        Ok(root) => root,
-        Err(diagnostics) => {
-            for diagnostic in diagnostics {
-                println!("{}", diagnostic.format_colorful(&file_tree));
+Running scarlet with a path and no command is the same as scarlet show.
+
+Options for check and show:
+    --cache                  Reuse what earlier runs worked out about unchanged files,
+                             storing it in a .scarlet-cache folder next to the root
+    --watch                  Check the root again whenever it changes
+
+Options for fmt:
+    --check                  Only report which files would be rewritten
+
+Options for graph:
+    --out=DIR                Write the graphs into DIR instead of the current folder
+
+Options:
+    -q, --quiet              Only print diagnostics and results
+    -v, --verbose            Also print how far through checking the code got
+    --color=WHEN             Color output: auto, always or never
+    --message-format=FORMAT  Print diagnostics as human or json
+    -h, --help               Print this message";
+
+const COMMANDS: &[&str] = &[
+    "check", "show", "fmt", "axioms", "explain", "repl", "graph", "certify", "verify", "test",
+    "lsp",
+];
+
+/// The exit code for arguments that don't make sense.
+pub const USAGE_ERROR: i32 = 2;
+
+/// The arguments given to a single command.
+pub struct CommandArgs {
+    /// The options the command accepts that were given, like --check or
+    /// --out=DIR.
+    pub options: Vec<String>,
+    pub positional: Vec<String>,
+}
+
+impl CommandArgs {
+    /// Splits the arguments into the options the command accepts and at most
+    /// max_positional other arguments. Options which take a value are given
+    /// with a trailing =, like "--out=". Anything else is reported, and the
+    /// exit code to use is returned instead.
+    pub fn parse(
+        args: impl Iterator<Item = String>,
+        options: &[&str],
+        max_positional: usize,
+    ) -> Result<Self, i32> {
+        let mut result = Self {
+            options: Vec::new(),
+            positional: Vec::new(),
+        };
+        for arg in args {
+            let accepted = options.iter().any(|option| {
+                if option.ends_with('=') {
+                    arg.starts_with(option)
+                } else {
+                    &arg == option
+                }
+            });
+            if accepted {
+                result.options.push(arg);
+            } else if arg.starts_with("--") {
+                eprintln!("Unknown option \"{}\"", arg);
+                return Err(USAGE_ERROR);
+            } else if result.positional.len() < max_positional {
+                result.positional.push(arg);
+            } else {
+                eprintln!("Unexpected argument \"{}\"", arg);
+                return Err(USAGE_ERROR);
             }
-            return;
-        }
-    };
-    println!("Parsed");
-
-    let mut env = Environment::new();
-    let root = root.as_item(&parse_context, &mut env, SRoot);
-    let root = match root {
-        Ok(root) => root,
-        Err(diagnostic) => {
-            println!("{}", diagnostic.format_colorful(&file_tree));
-            return;
         }
-    };
-    if let Err(diagnostics) = resolve_all(&mut env, root.ptr_clone()) {
-        for diagnostic in diagnostics {
-            println!("{}", diagnostic.format_colorful(&file_tree));
+        Ok(result)
+    }
+
+    pub fn has(&self, option: &str) -> bool {
+        self.options.iter().any(|given| given == option)
+    }
+
+    /// The value of the last time an option like "--out=" was given.
+    pub fn value(&self, option: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find_map(|given| given.strip_prefix(option))
+    }
+
+    /// The positional argument at the index, which is the current folder if
+    /// it was left out.
+    pub fn path(&self, index: usize) -> String {
+        self.positional
+            .get(index)
+            .cloned()
+            .unwrap_or(String::from("."))
+    }
+}
+
+/// Removes the options which apply to every command from the arguments,
+/// applying the color option straight away.
+fn take_global_options(args: Vec<String>) -> Result<(Output, Vec<String>), String> {
+    let mut verbosity = Verbosity::Normal;
+    let mut message_format = MessageFormat::Human;
+    let mut color = ColorChoice::Auto;
+    let mut rest = Vec::new();
+    for arg in &args {
+        match &arg[..] {
+            "-q" | "--quiet" => verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => verbosity = Verbosity::Verbose,
+            _ => {
+                if let Some(when) = arg.strip_prefix("--color=") {
+                    color = ColorChoice::parse(when)?;
+                } else if let Some(format) = arg.strip_prefix("--message-format=") {
+                    message_format = MessageFormat::parse(format)?;
+                } else {
+                    rest.push(arg.clone());
+                }
+            }
         }
-        return;
     }
-    println!("Resolved");
-    root.check_all();
-    println!("Checked!");
-    if let Err(diagnostics) = env.justify_all(&root) {
-        for diagnostic in diagnostics {
-            println!("{}", diagnostic.format_colorful(&file_tree));
+    output::set_color_override(color.for_stream(&std::io::stdout()));
+    let mut output = Output::new(verbosity, message_format);
+    output.color = color;
+    Ok((output, rest))
+}
+
+fn run(args: Vec<String>) -> i32 {
+    let (output, args) = match take_global_options(args) {
+        Ok(result) => result,
+        Err(message) => {
+            eprintln!("{}", message);
+            return USAGE_ERROR;
         }
-        return;
+    };
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek() {
+        Some(command) if COMMANDS.contains(&&command[..]) => args.next().unwrap(),
+        _ => String::from("show"),
+    };
+    match &command[..] {
+        "check" => check::run(args, Mode::Check, output),
+        "show" => check::run(args, Mode::Show, output),
+        "fmt" => fmt::run(args, output),
+        "axioms" => axioms::run(args, output),
+        "explain" => explain::run(args, output),
+        "repl" => repl::run(args, output),
+        "graph" => graph::run(args, output),
+        "certify" => certify::run(args, output),
+        "verify" => certify::run_verify(args, output),
+        "test" => golden::run(args, output),
+        "lsp" => lsp::run(args.next()),
+        _ => unreachable!(),
     }
-    println!("Justified!");
-    env.show_all_requested(&root);
 }
 
 fn main() {
-    for _ in 0..1 {
-        entry();
-    }
+    let args = std::env::args().skip(1).collect();
+    std::process::exit(run(args));
 }
@@ -0,0 +1,252 @@
+//! Finds the other roots that a root imports from, and makes sure modules
+//! don't import each other in a cycle. This all happens on the parse trees,
+//! before any items exist.
+
+mod tests;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use typed_arena::Arena;
+
+use crate::{
+    diagnostic::{Diagnostic, Position},
+    file_tree::{self, FileNode},
+    parser::{self, Node, NodeChild, ParseContext},
+};
+
+struct Import {
+    path: Vec<String>,
+    position: Position,
+}
+
+/// Every root needed to check the one that was asked for.
+pub struct Sources<'a> {
+    /// The folder all the roots are in.
+    pub base: PathBuf,
+    /// Has a child for each root, starting with the one that was asked for.
+    /// Libraries follow in the order they were first imported.
+    pub files: FileNode,
+    /// The parse tree of each root, in the same order as the files. Imports
+    /// can only be found by parsing, so these are kept for the rest of the
+    /// pipeline instead of parsing everything twice.
+    pub parsed: Vec<(String, Node<'a>)>,
+    pub syntax_errors: Vec<Diagnostic>,
+    imports: Vec<Import>,
+}
+
+/// Reads the root at the given path along with any roots next to it that it
+/// imports from, directly or indirectly. The roots are kept in the arena so
+/// that their parse trees can borrow from them. Returns None if there is no
+/// source code at the path.
+pub fn load<'a>(
+    path: &Path,
+    ctx: &ParseContext,
+    arena: &'a Arena<FileNode>,
+) -> Option<Sources<'a>> {
+    let main = file_tree::read_root(path)?;
+    let (base, name) = split_root_path(path);
+    let mut roots: Vec<(String, &'a FileNode)> = vec![(name, arena.alloc(main))];
+    let mut parsed = Vec::new();
+    let mut syntax_errors = Vec::new();
+    let mut imports = Vec::new();
+    // The tree that will be built from these roots starts with an empty file,
+    // so this makes file indexes line up with the ones used later.
+    let mut file_counter = 1;
+    let mut index = 0;
+    while index < roots.len() {
+        let (name, tree) = roots[index].clone();
+        let mut found = Vec::new();
+        let (node, mut errors) = parser::parse_tree(tree, ctx, &mut file_counter);
+        find_imports(&node, &mut found);
+        parsed.push((name, node));
+        syntax_errors.append(&mut errors);
+        for import in &found {
+            let first = &import.path[0];
+            let is_member = tree.children.iter().any(|(name, _)| name == first);
+            let is_loaded = roots.iter().any(|(name, _)| name == first);
+            if !is_member && !is_loaded {
+                if let Some(library) = file_tree::read_root(base.join(first)) {
+                    roots.push((first.clone(), arena.alloc(library)));
+                }
+            }
+        }
+        imports.append(&mut found);
+        index += 1;
+    }
+    let children = roots
+        .into_iter()
+        .map(|(name, tree)| (name, tree.clone()))
+        .collect();
+    Some(Sources {
+        base,
+        files: FileNode {
+            self_content: String::new(),
+            children,
+        },
+        parsed,
+        syntax_errors,
+        imports,
+    })
+}
+
+/// Splits a path into the folder it is in and the name of the root it
+/// refers to.
+fn split_root_path(path: &Path) -> (PathBuf, String) {
+    let path = if path.file_name().is_some() {
+        path.to_owned()
+    } else {
+        // Things like "." and "..".
+        path.canonicalize().unwrap_or_else(|_| path.to_owned())
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".sr").unwrap_or(&name).to_owned();
+    let base = path.parent().map(Path::to_owned).unwrap_or_default();
+    (base, name)
+}
+
+fn find_imports(node: &Node, into: &mut Vec<Import>) {
+    if node.phrase == "import" && !node.is_malformed() {
+        // Paths that aren't made of identifiers get reported when the import
+        // is turned into an item.
+        if let Ok(path) = node.children[1].as_node().as_path() {
+            into.push(Import {
+                path: path.into_iter().map(String::from).collect(),
+                position: node.position,
+            });
+        }
+    }
+    for child in &node.children {
+        if let NodeChild::Node(child) = child {
+            find_imports(child, into);
+        }
+    }
+}
+
+impl<'a> Sources<'a> {
+    /// Returns a diagnostic for each group of modules that import each other
+    /// in a cycle. Each file counts as its own module.
+    pub fn import_cycles(&self) -> Vec<Diagnostic> {
+        let paths = self.files.paths();
+        let mut edges = vec![Vec::new(); paths.len() + 1];
+        for import in &self.imports {
+            let from = import.position.file_index();
+            match target_file(&paths, from, &import.path) {
+                // Importing something from the same file is fine.
+                Some(to) if to != from => edges[from].push((to, import.position)),
+                _ => (),
+            }
+        }
+        let mut search = CycleSearch {
+            edges,
+            visited: vec![false; paths.len() + 1],
+            files: Vec::new(),
+            imports: Vec::new(),
+            seen: HashSet::new(),
+            cycles: Vec::new(),
+        };
+        for file in 1..=paths.len() {
+            if !search.visited[file] {
+                search.visit(file);
+            }
+        }
+        search.cycles
+    }
+
+    /// For each root, the indexes of the other roots it imports from directly.
+    pub fn root_dependencies(&self) -> Vec<Vec<usize>> {
+        let paths = self.files.paths();
+        let root_of = |file: usize| {
+            let name = paths[file - 1].split('/').nth(1)?;
+            self.files
+                .children
+                .iter()
+                .position(|(root, _)| root == name)
+        };
+        let mut dependencies = vec![Vec::new(); self.files.children.len()];
+        for import in &self.imports {
+            let from = import.position.file_index();
+            let to = target_file(&paths, from, &import.path);
+            if let (Some(from), Some(to)) = (root_of(from), to.and_then(root_of)) {
+                if from != to && !dependencies[from].contains(&to) {
+                    dependencies[from].push(to);
+                }
+            }
+        }
+        dependencies
+    }
+}
+
+/// Works out which file an import refers to, or a file containing it if it
+/// refers to something inside a file. This mirrors how imports are resolved,
+/// except that only members which are files are known about.
+fn target_file(paths: &[String], from: usize, path: &[String]) -> Option<usize> {
+    let root = paths[from - 1].split('/').nth(1)?;
+    let in_root = format!("/{}/{}", root, path[0]);
+    let mut current = if paths.contains(&in_root) {
+        in_root
+    } else {
+        format!("/{}", path[0])
+    };
+    for name in &path[1..] {
+        let next = format!("{}/{}", current, name);
+        if !paths.contains(&next) {
+            break;
+        }
+        current = next;
+    }
+    paths
+        .iter()
+        .position(|path| path == &current)
+        .map(|index| index + 1)
+}
+
+struct CycleSearch {
+    /// For each file index, the file each of its imports refers to.
+    edges: Vec<Vec<(usize, Position)>>,
+    visited: Vec<bool>,
+    /// The files currently being visited, and the imports that led to each of
+    /// them after the first.
+    files: Vec<usize>,
+    imports: Vec<Position>,
+    /// The files in each cycle found so far, so that each is reported once.
+    seen: HashSet<Vec<usize>>,
+    cycles: Vec<Diagnostic>,
+}
+
+impl CycleSearch {
+    fn visit(&mut self, file: usize) {
+        self.visited[file] = true;
+        self.files.push(file);
+        for (to, position) in self.edges[file].clone() {
+            if let Some(start) = self.files.iter().position(|&open| open == to) {
+                let mut members = self.files[start..].to_vec();
+                members.sort();
+                if self.seen.insert(members) {
+                    let mut imports = self.imports[start..].to_vec();
+                    imports.push(position);
+                    self.report(imports);
+                }
+            } else if !self.visited[to] {
+                self.imports.push(position);
+                self.visit(to);
+                self.imports.pop();
+            }
+        }
+        self.files.pop();
+    }
+
+    fn report(&mut self, imports: Vec<Position>) {
+        let mut diagnostic = Diagnostic::new()
+            .with_text_error(format!("These modules import each other in a cycle:"));
+        for position in imports {
+            diagnostic = diagnostic.with_source_code_block_error(position);
+        }
+        self.cycles.push(diagnostic);
+    }
+}
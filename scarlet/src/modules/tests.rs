@@ -0,0 +1,220 @@
+#![cfg(test)]
+
+use std::{fs, path::PathBuf};
+
+use crate::pipeline::{self, Analysis, Stage};
+
+const VOID: &str = "void IS UNIQUE AS_LANGUAGE_ITEM(void)\n";
+
+/// Writes each root into its own file in a fresh folder and analyzes the
+/// first one.
+fn analyze_roots(test: &str, roots: &[(&str, &str)]) -> Analysis {
+    let folder =
+        std::env::temp_dir().join(format!("scarlet-modules-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    for (name, code) in roots {
+        fs::write(folder.join(format!("{}.sr", name)), code).unwrap();
+    }
+    let analysis = pipeline::analyze(&folder.join(roots[0].0));
+    fs::remove_dir_all(&folder).unwrap();
+    analysis
+}
+
+fn messages(analysis: &Analysis) -> Vec<&str> {
+    analysis
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.message())
+        .collect()
+}
+
+#[test]
+fn imports_from_another_root() {
+    let analysis = analyze_roots(
+        "another_root",
+        &[
+            (
+                "app",
+                &format!("{}IMPORT lib.thing AS thing\n y IS thing", VOID),
+            ),
+            ("lib", "thing IS UNIQUE"),
+        ],
+    );
+    assert_eq!(messages(&analysis), Vec::<&str>::new());
+    assert_eq!(analysis.stage, Stage::Justified);
+    assert_eq!(analysis.file_tree.paths(), vec!["", "/app", "/lib"]);
+    assert_eq!(
+        analysis.base,
+        PathBuf::from(std::env::temp_dir()).join(format!(
+            "scarlet-modules-{}-another_root",
+            std::process::id()
+        ))
+    );
+}
+
+#[test]
+fn imports_from_own_root() {
+    let analysis = analyze_roots(
+        "own_root",
+        &[(
+            "app",
+            &format!(
+                "{}thing IS UNIQUE\n inner IS {{ IMPORT app.thing AS t\n x IS t }}\n y IS inner.x",
+                VOID
+            ),
+        )],
+    );
+    assert_eq!(messages(&analysis), Vec::<&str>::new());
+    assert_eq!(analysis.stage, Stage::Justified);
+}
+
+#[test]
+fn imported_names_are_not_members() {
+    let analysis = analyze_roots(
+        "not_members",
+        &[(
+            "app",
+            &format!(
+                "{}thing IS UNIQUE\n inner IS {{ IMPORT thing AS t\n x IS t }}\n y IS inner.t",
+                VOID
+            ),
+        )],
+    );
+    assert_eq!(
+        messages(&analysis),
+        vec!["Failed to find a member named t:"]
+    );
+}
+
+#[test]
+fn missing_modules_are_reported() {
+    let analysis = analyze_roots(
+        "missing",
+        &[
+            (
+                "app",
+                &format!(
+                    "{}IMPORT nothing AS a\n IMPORT lib.other AS b\n y IS a",
+                    VOID
+                ),
+            ),
+            ("lib", "thing IS UNIQUE"),
+        ],
+    );
+    let mut messages = messages(&analysis);
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec![
+            "\"lib\" has no member named \"other\":",
+            "Cannot find a module named \"nothing\":",
+        ]
+    );
+    assert_eq!(analysis.stage, Stage::Parsed);
+}
+
+#[test]
+fn import_cycles_are_reported() {
+    let analysis = analyze_roots(
+        "cycle",
+        &[
+            (
+                "app",
+                &format!("{}IMPORT lib.thing AS thing\n y IS UNIQUE", VOID),
+            ),
+            ("lib", "IMPORT app.y AS y\n thing IS UNIQUE"),
+        ],
+    );
+    assert_eq!(
+        messages(&analysis),
+        vec!["These modules import each other in a cycle:"]
+    );
+    assert_eq!(analysis.diagnostics[0].elements().len(), 3);
+    assert_eq!(analysis.stage, Stage::Parsed);
+}
+
+#[test]
+fn private_members_can_be_used_inside() {
+    let analysis = analyze_roots(
+        "private_inside",
+        &[(
+            "app",
+            &format!(
+                "{}inner IS {{ helper IS UNIQUE PRIVATE\n x IS inner.helper }}\n y IS inner.x",
+                VOID
+            ),
+        )],
+    );
+    assert_eq!(messages(&analysis), Vec::<&str>::new());
+    assert_eq!(analysis.stage, Stage::Justified);
+}
+
+#[test]
+fn private_members_are_hidden_outside() {
+    let analysis = analyze_roots(
+        "private_outside",
+        &[
+            (
+                "app",
+                &format!(
+                    "{}IMPORT lib.helper AS helper\n inner IS {{ hidden IS UNIQUE PRIVATE }}\n y IS inner.hidden",
+                    VOID
+                ),
+            ),
+            ("lib", "helper IS UNIQUE PRIVATE"),
+        ],
+    );
+    assert_eq!(
+        messages(&analysis),
+        vec![
+            "hidden is private, so it can only be used inside the struct it is a member of:",
+            "helper is private, so it can only be used inside the struct it is a member of:",
+        ]
+    );
+}
+
+#[test]
+fn private_members_are_hidden_from_structural_access() {
+    let analysis = analyze_roots(
+        "private_structural",
+        &[(
+            "app",
+            &format!(
+                "{}inner IS {{ shown IS UNIQUE\n hidden IS UNIQUE PRIVATE }}\n x IS inner.VALUE\n y IS inner.BODY.VALUE",
+                VOID
+            ),
+        )],
+    );
+    assert_eq!(
+        messages(&analysis),
+        vec!["hidden is private, so it can only be used inside the struct it is a member of:"]
+    );
+}
+
+#[test]
+fn justification_errors_are_reported_for_every_root() {
+    let broken = "a IS UNIQUE\n b IS UNIQUE\n y IS VAR(SELF = a)\n broken IS y(b)";
+    let analysis = analyze_roots(
+        "justify_every_root",
+        &[
+            (
+                "app",
+                &format!(
+                    "{}true IS UNIQUE AS_LANGUAGE_ITEM(true)\n\
+                     false IS UNIQUE AS_LANGUAGE_ITEM(false)\n\
+                     IMPORT lib.a AS lib_a\n{}",
+                    VOID, broken
+                ),
+            ),
+            ("lib", broken),
+        ],
+    );
+    assert_eq!(
+        messages(&analysis),
+        vec![
+            "Failed to find any justification for the following statements:",
+            "Failed to find any justification for the following statements:",
+        ]
+    );
+}
@@ -0,0 +1,211 @@
+//! How commands print what they found. Every command which reports
+//! diagnostics does it through an Output, so the options which apply to every
+//! command work the same way for all of them.
+
+use std::{
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use colored::control;
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{Diagnostic, Span},
+    file_tree::FileNode,
+};
+
+/// How much to print besides diagnostics and results.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Verbosity {
+    /// Nothing.
+    Quiet,
+    /// A summary of what was done.
+    Normal,
+    /// The summary and how far through the pipeline the code made it.
+    Verbose,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("Unknown message format \"{}\"", text)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color option \"{}\"", text)),
+        }
+    }
+
+    /// Whether text written to the stream should be colored, or None to leave
+    /// it up to the environment.
+    pub fn for_stream(self, stream: &impl IsTerminal) -> Option<bool> {
+        match self {
+            ColorChoice::Auto if stream.is_terminal() => None,
+            ColorChoice::Auto => Some(false),
+            ColorChoice::Always => Some(true),
+            ColorChoice::Never => Some(false),
+        }
+    }
+}
+
+/// Colors text from now on if given Some(true), never if given Some(false),
+/// and otherwise leaves it up to the environment.
+pub fn set_color_override(colored: Option<bool>) {
+    match colored {
+        Some(colored) => control::set_override(colored),
+        None => control::unset_override(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Output {
+    pub verbosity: Verbosity,
+    pub message_format: MessageFormat,
+    /// Text written to stdout is colored according to this from the start,
+    /// other streams only while they are being written to.
+    pub color: ColorChoice,
+}
+
+impl Output {
+    pub fn new(verbosity: Verbosity, message_format: MessageFormat) -> Self {
+        Self {
+            verbosity,
+            message_format,
+            color: ColorChoice::Auto,
+        }
+    }
+
+    /// Prints a message which is only useful to humans, if they asked for
+    /// messages at least this verbose.
+    pub fn progress(&self, verbosity: Verbosity, message: &str) {
+        if self.message_format == MessageFormat::Human && self.verbosity >= verbosity {
+            println!("{}", message);
+        }
+    }
+
+    fn format(&self, diagnostic: &Diagnostic, file_tree: &FileNode, base: &Path) -> String {
+        match self.message_format {
+            MessageFormat::Human => diagnostic.format_colorful(file_tree),
+            MessageFormat::Json => diagnostic.format_json(file_tree, base),
+        }
+    }
+
+    pub fn report(&self, diagnostic: &Diagnostic, file_tree: &FileNode, base: &Path) {
+        println!("{}", self.format(diagnostic, file_tree, base));
+    }
+
+    /// Like report, for commands whose results are written to stdout.
+    pub fn report_to_stderr(&self, diagnostic: &Diagnostic, file_tree: &FileNode, base: &Path) {
+        set_color_override(self.color.for_stream(&io::stderr()));
+        let text = self.format(diagnostic, file_tree, base);
+        set_color_override(self.color.for_stream(&io::stdout()));
+        eprintln!("{}", text);
+    }
+
+    /// Reports a file which `fmt --check` found was not in the canonical
+    /// layout. This is the result of the check, so it is printed no matter
+    /// how quiet the output is.
+    pub fn report_unformatted(&self, file: &Path) {
+        #[derive(Serialize)]
+        struct JsonUnformatted<'a> {
+            #[serde(rename = "$message_type")]
+            message_type: &'static str,
+            file: &'a str,
+        }
+
+        match self.message_format {
+            MessageFormat::Human => println!("Would reformat {}", file.display()),
+            MessageFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&JsonUnformatted {
+                    message_type: "unformatted",
+                    file: &file.to_string_lossy(),
+                })
+                .unwrap()
+            ),
+        }
+    }
+
+    /// Reports how a root run by the `test` subcommand compared with its
+    /// annotations. Roots which passed are only mentioned if the output isn't
+    /// quiet.
+    pub fn report_test(&self, root: &Path, failures: &[String]) {
+        #[derive(Serialize)]
+        struct JsonTest<'a> {
+            #[serde(rename = "$message_type")]
+            message_type: &'static str,
+            root: &'a str,
+            passed: bool,
+            failures: &'a [String],
+        }
+
+        match self.message_format {
+            MessageFormat::Human if failures.is_empty() => {
+                self.progress(Verbosity::Normal, &format!("{} ... ok", root.display()))
+            }
+            MessageFormat::Human => {
+                println!("{} ... FAILED", root.display());
+                for failure in failures {
+                    println!("    {}", failure);
+                }
+            }
+            MessageFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&JsonTest {
+                    message_type: "test",
+                    root: &root.to_string_lossy(),
+                    passed: failures.is_empty(),
+                    failures,
+                })
+                .unwrap()
+            ),
+        }
+    }
+
+    pub fn report_shown(&self, text: &str, span: Option<Span>) {
+        #[derive(Serialize)]
+        struct JsonShown<'a> {
+            #[serde(rename = "$message_type")]
+            message_type: &'static str,
+            text: &'a str,
+            span: Option<Span>,
+        }
+
+        match self.message_format {
+            MessageFormat::Human => println!("{}", text),
+            MessageFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&JsonShown {
+                    message_type: "shown",
+                    text,
+                    span,
+                })
+                .unwrap()
+            ),
+        }
+    }
+}
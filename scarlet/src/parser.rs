@@ -1,11 +1,17 @@
+mod diagnostics;
+mod format;
+mod lexer;
 mod matchh;
 mod node;
+mod notation;
 mod parse;
 mod phrase;
 mod scarlet_phrases;
 mod stack;
+mod tests;
 mod util;
-mod diagnostics;
 
+pub use format::format_file;
 pub use node::{Node, NodeChild};
-pub use parse::{parse_tree, ParseContext};
+pub use notation::{desugar_expression, parse_with_notations, NotationPiece, NotationSyntax};
+pub use parse::{parse, parse_tree, ParseContext};
@@ -23,3 +23,12 @@ pub fn unrecognized_input(position: Position) -> Diagnostic {
         .with_text_error("Not sure what to do with the following input:".to_owned())
         .with_source_code_block_error(position)
 }
+
+pub fn nothing_before(phrase: &str, position: Position) -> Diagnostic {
+    Diagnostic::new()
+        .with_text_error(format!(
+            "Expected something before this \"{}\" phrase:",
+            phrase
+        ))
+        .with_source_code_block_error(position)
+}
@@ -0,0 +1,335 @@
+//! Prints parse trees back out as source code in a single canonical layout,
+//! keeping all the comments from the original source.
+
+use super::{notation, util, Node, NodeChild, ParseContext};
+use crate::diagnostic::Diagnostic;
+
+const INDENT: &str = "   ";
+const MAX_WIDTH: usize = 80;
+
+struct Comment<'a> {
+    start: usize,
+    end: usize,
+    text: &'a str,
+    taken: bool,
+}
+
+/// Finds comments the same way the parser skips over them.
+fn find_comments(source: &str) -> Vec<Comment<'_>> {
+    let mut comments = Vec::new();
+    let mut index = 0;
+    while index < source.len() {
+        let rest = &source[index..];
+        if rest.starts_with("#=") {
+            let mut depth = 0;
+            let mut end = index;
+            while end < source.len() {
+                if source[end..].starts_with("#=") {
+                    depth += 1;
+                    end += 2;
+                } else if source[end..].starts_with("=#") {
+                    depth -= 1;
+                    end += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    end += source[end..].chars().next().unwrap().len_utf8();
+                }
+            }
+            comments.push(Comment {
+                start: index,
+                end,
+                text: &source[index..end],
+                taken: false,
+            });
+            index = end;
+        } else if rest.starts_with('#') {
+            let end = rest.find('\n').map(|x| index + x).unwrap_or(source.len());
+            comments.push(Comment {
+                start: index,
+                end,
+                text: source[index..end].trim_end(),
+                taken: false,
+            });
+            index = end;
+        } else {
+            index += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    comments
+}
+
+/// Something on its own line in a list of items.
+struct Piece {
+    text: String,
+    /// A comment that was on the same line as the end of the item.
+    trailing: Option<String>,
+    blank_line_before: bool,
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment<'a>>,
+}
+
+impl<'a> Formatter<'a> {
+    /// The part of the source code a node came from, ignoring nodes the
+    /// parser made up itself.
+    fn span(&self, node: &Node) -> Option<(usize, usize)> {
+        let mut result = None;
+        let mut include = |(start, end): (usize, usize)| {
+            result = Some(match result {
+                Some((old_start, old_end)) => (start.min(old_start), end.max(old_end)),
+                None => (start, end),
+            })
+        };
+        if node.position.file_index() != 0 {
+            let range = node.position.range();
+            include((range.start, range.end));
+        }
+        for child in &node.children {
+            if let NodeChild::Node(child) = child {
+                if let Some(span) = self.span(child) {
+                    include(span);
+                }
+            }
+        }
+        result
+    }
+
+    fn has_blank_line(&self, from: usize, to: usize) -> bool {
+        from < to && self.source[from..to].matches('\n').count() >= 2
+    }
+
+    fn take_comments(&mut self, from: usize, to: usize) -> Vec<(usize, usize, &'a str)> {
+        let mut result = Vec::new();
+        for comment in &mut self.comments {
+            if !comment.taken && comment.start >= from && comment.start < to {
+                comment.taken = true;
+                result.push((comment.start, comment.end, comment.text));
+            }
+        }
+        result
+    }
+
+    fn has_comments(&self, from: usize, to: usize) -> bool {
+        self.comments
+            .iter()
+            .any(|comment| !comment.taken && comment.start >= from && comment.start < to)
+    }
+
+    /// Formats the items of a list along with the comments between them.
+    /// Comments inside an item that don't belong to anything more specific
+    /// are moved to before the item.
+    fn pieces(&mut self, items: &[&Node], from: usize, to: usize, indent: usize) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let mut cursor = from;
+        for (index, item) in items.iter().enumerate() {
+            let (start, end) = self.span(item).unwrap_or((cursor, cursor));
+            let next_start = items
+                .get(index + 1)
+                .and_then(|next| self.span(next))
+                .map(|(next_start, _)| next_start)
+                .unwrap_or(to);
+            for (comment_start, comment_end, text) in self.take_comments(cursor, start) {
+                pieces.push(Piece {
+                    text: text.to_owned(),
+                    trailing: None,
+                    blank_line_before: pieces.len() > 0
+                        && self.has_blank_line(cursor, comment_start),
+                });
+                cursor = comment_end;
+            }
+            let blank_line_before = pieces.len() > 0 && self.has_blank_line(cursor, start);
+            let text = self.format_node(item, indent);
+            for (_, _, text) in self.take_comments(start, end) {
+                pieces.push(Piece {
+                    text: text.to_owned(),
+                    trailing: None,
+                    blank_line_before: false,
+                });
+            }
+            let line_end = self.source[end..]
+                .find('\n')
+                .map(|x| end + x)
+                .unwrap_or(self.source.len());
+            let mut trailing = self.take_comments(end, line_end.min(next_start));
+            cursor = end;
+            if let Some((_, comment_end, _)) = trailing.last() {
+                cursor = *comment_end;
+            }
+            pieces.push(Piece {
+                text,
+                trailing: trailing.pop().map(|(_, _, text)| text.to_owned()),
+                blank_line_before,
+            });
+        }
+        for (comment_start, comment_end, text) in self.take_comments(cursor, to) {
+            pieces.push(Piece {
+                text: text.to_owned(),
+                trailing: None,
+                blank_line_before: pieces.len() > 0 && self.has_blank_line(cursor, comment_start),
+            });
+            cursor = comment_end;
+        }
+        pieces
+    }
+
+    /// Puts each piece on its own line. The first line is not indented.
+    fn block(&self, pieces: &[Piece], indent: usize) -> String {
+        let mut result = String::new();
+        for (index, piece) in pieces.iter().enumerate() {
+            if index > 0 {
+                result.push('\n');
+                if piece.blank_line_before {
+                    result.push('\n');
+                }
+                result.push_str(&INDENT.repeat(indent));
+            }
+            result.push_str(&piece.text);
+            if let Some(trailing) = &piece.trailing {
+                result.push(' ');
+                result.push_str(trailing);
+            }
+        }
+        result
+    }
+
+    fn bracket(
+        &mut self,
+        node: &Node,
+        contents: &NodeChild,
+        open: &str,
+        close: &str,
+        indent: usize,
+    ) -> String {
+        let (from, to) = self.span(node).unwrap_or((0, 0));
+        let has_comments = self.has_comments(from, to);
+        let items = util::collect_comma_list(contents);
+        let pieces = self.pieces(&items, from, to, indent + 1);
+        if pieces.len() == 0 {
+            return format!("{}{}", open, close);
+        }
+        let texts = pieces
+            .iter()
+            .map(|piece| &piece.text[..])
+            .collect::<Vec<_>>();
+        let separator = if texts.iter().any(|text| text.contains(' ')) {
+            "  "
+        } else {
+            " "
+        };
+        let inline = texts.join(separator);
+        if open == "(" && !has_comments && !inline.contains('\n') {
+            let width = INDENT.len() * indent + inline.chars().count() + 2;
+            if width <= MAX_WIDTH {
+                return format!("{}{}{}", open, inline, close);
+            }
+        }
+        format!(
+            "{}\n{}{}\n{}{}",
+            open,
+            INDENT.repeat(indent + 1),
+            self.block(&pieces, indent + 1),
+            INDENT.repeat(indent),
+            close
+        )
+    }
+
+    fn format_node(&mut self, node: &Node, indent: usize) -> String {
+        let mut tokens = Vec::new();
+        let mut index = 0;
+        while index < node.children.len() {
+            let bracket = match (&node.children[index], node.children.get(index + 2)) {
+                (NodeChild::Text(open), Some(NodeChild::Text(close)))
+                    if (*open == "(" && *close == ")") || (*open == "{" && *close == "}") =>
+                {
+                    Some((*open, *close))
+                }
+                _ => None,
+            };
+            if let Some((open, close)) = bracket {
+                let contents = &node.children[index + 1];
+                tokens.push((true, self.bracket(node, contents, open, close, indent)));
+                index += 3;
+                continue;
+            }
+            match &node.children[index] {
+                NodeChild::Node(child) if child.phrase == "multiple items" => {
+                    let child = NodeChild::Node(child.clone());
+                    for item in util::collect_comma_list(&child) {
+                        tokens.push((false, self.format_node(item, indent)));
+                    }
+                }
+                NodeChild::Node(child) => tokens.push((false, self.format_node(child, indent))),
+                NodeChild::Text(",") => (),
+                NodeChild::Text(text) => tokens.push((false, text.to_string())),
+                NodeChild::Missing => (),
+            }
+            index += 1;
+        }
+
+        let joined = self.join(&tokens, indent, false);
+        let too_wide = INDENT.len() * indent + joined.chars().count() > MAX_WIDTH;
+        let infix = matches!(node.children.last(), Some(NodeChild::Node(..)));
+        if !joined.contains('\n') && too_wide && infix && tokens.len() > 1 {
+            self.join(&tokens, indent, true)
+        } else {
+            joined
+        }
+    }
+
+    /// Joins tokens with spaces, or line breaks where something spans multiple
+    /// lines. The boolean on each token says whether it is an opening bracket.
+    fn join(&self, tokens: &[(bool, String)], indent: usize, break_last: bool) -> String {
+        let mut result = String::new();
+        for (index, (is_bracket, token)) in tokens.iter().enumerate() {
+            let newline = format!("\n{}", INDENT.repeat(indent));
+            if index == 0 {
+            } else if token.starts_with('.') {
+                if result.contains('\n') {
+                    result.push_str(&newline);
+                }
+            } else if result.ends_with('.') || (*is_bracket && token.starts_with('(')) {
+            } else if token.contains('\n') || (break_last && index == tokens.len() - 1) {
+                result.push_str(&newline);
+            } else {
+                result.push(' ');
+            }
+            result.push_str(token);
+        }
+        result
+    }
+}
+
+/// Formats the contents of a single file, returning its syntax errors instead
+/// if it has any. Notations declared in the file are kept as they are.
+pub fn format_file(
+    source: &str,
+    ctx: &ParseContext,
+    file_index: usize,
+) -> Result<String, Vec<Diagnostic>> {
+    let (root, diagnostics, _) = notation::parse_with_notations(source, ctx, file_index);
+    if diagnostics.len() > 0 {
+        return Err(diagnostics);
+    }
+    let mut formatter = Formatter {
+        source,
+        comments: find_comments(source),
+    };
+    let items = match &root {
+        Some(root) => util::collect_comma_list(&NodeChild::Node(root.clone()))
+            .into_iter()
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    let items = items.iter().collect::<Vec<_>>();
+    let pieces = formatter.pieces(&items, 0, source.len(), 0);
+    let mut result = formatter.block(&pieces, 0);
+    if result.len() > 0 {
+        result.push('\n');
+    }
+    Ok(result)
+}
@@ -0,0 +1,196 @@
+//! Splits source code into tokens before phrases are matched against it.
+//! Whitespace and comments are skipped here, so the parser only ever sees
+//! text that could belong to a phrase.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{
+    matchh,
+    phrase::{PhraseComponent, PhraseTable},
+};
+
+lazy_static! {
+    static ref R_WHITESPACE: Regex = Regex::new(r"^(?:[ \r\n\t]+|#[^\n]*)").unwrap();
+}
+
+/// Compiles a regex so that it only matches at the start of the input.
+pub fn anchored(regex: &str) -> Regex {
+    Regex::new(&format!("^(?:{})", regex)).unwrap()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    /// Where the token starts in the input, in bytes.
+    pub start: usize,
+    /// False for a run of characters that no phrase could start with.
+    pub recognized: bool,
+    /// How much of the input each token regex matches at the start of the
+    /// token, so that phrases never have to run them again.
+    lengths: Vec<Option<usize>>,
+}
+
+impl<'a> Token<'a> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// The text at the start of the token matched by the token regex with the
+    /// given index.
+    pub fn matched_by(&self, regex_index: usize) -> Option<&'a str> {
+        self.lengths[regex_index].map(|length| &self.text[..length])
+    }
+}
+
+/// Every regex that can match text in a phrase, with duplicates removed.
+pub struct TokenRegexes {
+    regexes: Vec<Regex>,
+    /// For each phrase in the table, the index of the regex of each of its
+    /// components that is text.
+    components: Vec<Vec<Option<usize>>>,
+}
+
+impl TokenRegexes {
+    pub fn new(phrases: &PhraseTable) -> Self {
+        let mut regexes: Vec<Regex> = Vec::new();
+        let mut components = Vec::new();
+        for phrase in phrases.values() {
+            let mut indices = Vec::new();
+            for component in &phrase.components {
+                let regex = match component {
+                    PhraseComponent::Text(regex) => regex,
+                    PhraseComponent::Node(..) => {
+                        indices.push(None);
+                        continue;
+                    }
+                };
+                let index = regexes
+                    .iter()
+                    .position(|other| other.as_str() == regex.as_str())
+                    .unwrap_or_else(|| {
+                        regexes.push(regex.clone());
+                        regexes.len() - 1
+                    });
+                indices.push(Some(index));
+            }
+            components.push(indices);
+        }
+        Self {
+            regexes,
+            components,
+        }
+    }
+
+    /// The index of the regex of a component of the phrase with the given
+    /// index in the table, if the component is text.
+    pub fn index_of(&self, phrase_index: usize, component_index: usize) -> Option<usize> {
+        self.components[phrase_index][component_index]
+    }
+}
+
+/// Produces tokens one at a time, each as long as the longest text that any
+/// phrase could match there.
+#[derive(Clone)]
+pub struct Lexer<'a, 'r> {
+    input: &'a str,
+    position: usize,
+    comment_depth: i32,
+    token_regexes: &'r TokenRegexes,
+}
+
+impl<'a, 'r> Lexer<'a, 'r> {
+    pub fn new(input: &'a str, token_regexes: &'r TokenRegexes) -> Self {
+        Self {
+            input,
+            position: 0,
+            comment_depth: 0,
+            token_regexes,
+        }
+    }
+
+    /// Continues lexing from the given position instead, used when only part
+    /// of a token was used.
+    pub fn restart_at(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// How much of the input each token regex matches at the position.
+    fn lengths_at(&self, position: usize) -> Vec<Option<usize>> {
+        let rest = &self.input[position..];
+        self.token_regexes
+            .regexes
+            .iter()
+            .map(|regex| {
+                matchh::anchored_find(regex, rest)
+                    .map(|text| text.len())
+                    .filter(|&length| length > 0)
+            })
+            .collect()
+    }
+
+    fn whitespace_at(&self, position: usize) -> Option<&'a str> {
+        matchh::anchored_find(&R_WHITESPACE, &self.input[position..])
+    }
+
+    /// Skips over whitespace and comments. Block comments are written
+    /// between #= and =#, and can be nested.
+    fn skip_ignored(&mut self) {
+        while self.position < self.input.len() {
+            let rest = &self.input[self.position..];
+            if rest.starts_with("#=") {
+                self.comment_depth += 1;
+                self.position += 2;
+            } else if rest.starts_with("=#") {
+                self.comment_depth -= 1;
+                self.position += 2;
+            } else if self.comment_depth > 0 {
+                self.position += rest.chars().next().unwrap().len_utf8();
+            } else if let Some(whitespace) = self.whitespace_at(self.position) {
+                self.position += whitespace.len();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<'a, 'r> Iterator for Lexer<'a, 'r> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.skip_ignored();
+        if self.position >= self.input.len() {
+            return None;
+        }
+        let start = self.position;
+        let lengths = self.lengths_at(start);
+        if let Some(&length) = lengths.iter().flatten().max() {
+            self.position += length;
+            return Some(Token {
+                text: &self.input[start..self.position],
+                start,
+                recognized: true,
+                lengths,
+            });
+        }
+        // Report a run of unrecognized characters as a single token.
+        let mut end = start;
+        loop {
+            end += self.input[end..].chars().next().unwrap().len_utf8();
+            if end >= self.input.len()
+                || self.lengths_at(end).iter().any(Option::is_some)
+                || self.whitespace_at(end).is_some()
+            {
+                break;
+            }
+        }
+        self.position = end;
+        Some(Token {
+            text: &self.input[start..end],
+            start,
+            recognized: false,
+            lengths,
+        })
+    }
+}
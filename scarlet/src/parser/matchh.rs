@@ -1,18 +1,28 @@
+#[cfg(test)]
+use std::cell::Cell;
+
 use regex::Regex;
 
-use super::phrase::{Phrase, PhraseTable, Precedence};
+use super::{
+    lexer::{Token, TokenRegexes},
+    phrase::{Phrase, PhraseTable, Precedence},
+};
 use crate::parser::stack::Stack;
 
+#[cfg(test)]
+thread_local! {
+    /// How many times anchored_find has run a regex on this thread, so tests
+    /// can check how much work parsing does.
+    pub static REGEX_RUNS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Finds the text at the start of the input that matches the regex, which
+/// must have been made with lexer::anchored so that the rest of the input is
+/// never searched.
 pub fn anchored_find<'a>(regex: &Regex, input: &'a str) -> Option<&'a str> {
-    if let Some(matchh) = regex.find(input) {
-        if matchh.start() == 0 {
-            Some(matchh.as_str())
-        } else {
-            None
-        }
-    } else {
-        None
-    }
+    #[cfg(test)]
+    REGEX_RUNS.with(|runs| runs.set(runs.get() + 1));
+    regex.find(input).map(|matchh| matchh.as_str())
 }
 
 #[derive(Debug)]
@@ -29,14 +39,20 @@ pub struct MatchSuccess<'a> {
     pub continuation_of: Option<usize>,
 }
 
+/// Checks whether the phrase, which has the given index in the phrase table,
+/// can continue with text at the start of the token. The lexer already ran
+/// every regex there, so this only looks up what it found.
 pub fn matches<'a>(
-    match_against: &'a str,
+    token: &Token<'a>,
+    token_regexes: &TokenRegexes,
+    phrase_index: usize,
     how_many_components_already_matched: usize,
     continuation_of: Option<usize>,
     phrase: &Phrase,
 ) -> Option<MatchSuccess<'a>> {
-    if let (pop_node, Some(regex)) = phrase.upcoming(how_many_components_already_matched) {
-        if let Some(matchh) = anchored_find(regex, match_against) {
+    if let (pop_node, Some(component)) = phrase.upcoming(how_many_components_already_matched) {
+        let regex_index = token_regexes.index_of(phrase_index, component).unwrap();
+        if let Some(matchh) = token.matched_by(regex_index) {
             let action = match pop_node {
                 Some(node) => StackAction::PopNode(node.prec),
                 None => StackAction::DontPopNode,
@@ -55,16 +71,28 @@ pub fn matches<'a>(
     }
 }
 
+/// Finds the longest text any phrase could match at the start of the token,
+/// given what is on the stack. The match is usually the whole token, but it
+/// can be shorter when the phrases that fit here only want part of it.
 pub fn longest_match<'a>(
-    match_against: &'a str,
+    token: &Token<'a>,
     stack: &Stack<'a>,
     phrases: &PhraseTable,
+    token_regexes: &TokenRegexes,
 ) -> Option<MatchSuccess<'a>> {
     let mut longest_match = None;
     for (node_index, node) in stack.0.iter().enumerate().rev() {
-        let phrase = &phrases[node.phrase];
-        if let Some(matchh) = matches(match_against, node.children.len(), Some(node_index), phrase)
-        {
+        let (phrase_index, _, phrase) = phrases.get_full(node.phrase).unwrap();
+        let already_matched = node.children.len();
+        let continuation_of = Some(node_index);
+        if let Some(matchh) = matches(
+            token,
+            token_regexes,
+            phrase_index,
+            already_matched,
+            continuation_of,
+            phrase,
+        ) {
             if longest_match
                 .as_ref()
                 .map(|x: &MatchSuccess| matchh.text.len() > x.text.len())
@@ -74,8 +102,8 @@ pub fn longest_match<'a>(
             }
         }
     }
-    for (_, phrase) in phrases {
-        if let Some(matchh) = matches(match_against, 0, None, phrase) {
+    for (phrase_index, phrase) in phrases.values().enumerate() {
+        if let Some(matchh) = matches(token, token_regexes, phrase_index, 0, None, phrase) {
             if longest_match
                 .as_ref()
                 .map(|x: &MatchSuccess| matchh.text.len() > x.text.len())
@@ -4,7 +4,7 @@ use super::{phrase::PhraseTable, ParseContext};
 use crate::{
     diagnostic::{Diagnostic, Position},
     environment::Environment,
-    item::ItemPtr,
+    item::{definitions::stand_in::DStandIn, Item, ItemPtr},
     scope::Scope,
     shared::indented,
 };
@@ -39,7 +39,7 @@ impl<'a> NodeChild<'a> {
         env: &mut Environment,
         scope: impl Scope + 'static,
     ) -> Result<ItemPtr, Diagnostic> {
-        self.as_node().as_item(pc, env, scope)
+        self.as_construct_dyn_scope(pc, env, Box::new(scope))
     }
 
     pub(crate) fn as_construct_dyn_scope(
@@ -48,7 +48,10 @@ impl<'a> NodeChild<'a> {
         env: &mut Environment,
         scope: Box<dyn Scope>,
     ) -> Result<ItemPtr, Diagnostic> {
-        self.as_node().as_item_dyn_scope(pc, env, scope)
+        match self {
+            NodeChild::Missing => Ok(stand_in(scope)),
+            _ => self.as_node().as_item_dyn_scope(pc, env, scope),
+        }
     }
 
     pub fn vomit(&self, pc: &ParseContext) -> String {
@@ -70,6 +73,12 @@ impl<'a> Debug for NodeChild<'a> {
     }
 }
 
+/// Stands in for code that could not be parsed. The syntax error has already
+/// been reported, so this just has to be something later stages can work with.
+fn stand_in(scope: Box<dyn Scope>) -> ItemPtr {
+    Item::new_boxed(Box::new(DStandIn), scope)
+}
+
 #[derive(Clone, PartialEq, Eq, Default, Hash)]
 pub struct Node<'x> {
     pub phrase: &'static str,
@@ -115,6 +124,25 @@ impl<'x> Node<'x> {
         pt.get(self.phrase).unwrap().components.len() == self.children.len()
     }
 
+    /// Fills in the rest of the phrase with missing children so that parsing
+    /// can continue after a syntax error.
+    pub fn pad_with_missing(&mut self, pt: &PhraseTable) {
+        let len = pt.get(self.phrase).unwrap().components.len();
+        self.children.resize(len, NodeChild::Missing);
+    }
+
+    /// Returns true if the parser had to make up part of this phrase while
+    /// recovering from a syntax error. Missing children are only legitimate
+    /// between two pieces of text, like in `{}`.
+    pub fn is_malformed(&self) -> bool {
+        self.children.iter().enumerate().any(|(index, child)| {
+            child == &NodeChild::Missing
+                && !(index > 0
+                    && matches!(self.children[index - 1], NodeChild::Text(..))
+                    && matches!(self.children.get(index + 1), Some(NodeChild::Text(..))))
+        })
+    }
+
     pub fn as_item(
         &self,
         pc: &ParseContext,
@@ -130,6 +158,9 @@ impl<'x> Node<'x> {
         env: &mut Environment,
         scope: Box<dyn Scope>,
     ) -> Result<ItemPtr, Diagnostic> {
+        if self.is_malformed() {
+            return Ok(stand_in(scope));
+        }
         let item = pc
             .phrases_sorted_by_priority
             .get(self.phrase)
@@ -137,7 +168,11 @@ impl<'x> Node<'x> {
             .create_and_uncreate
             .expect(&format!("{} is not a construct", self.phrase))
             .0(pc, env, scope, self)?;
-        item.set_position(self.position);
+        // Nodes generated by the parser itself, like the ones wrapping each
+        // file, don't correspond to any source code.
+        if self.position != Position::placeholder() {
+            item.set_position(self.position);
+        }
         Ok(item)
     }
 
@@ -156,4 +191,16 @@ impl<'x> Node<'x> {
                 .with_source_code_block_error(self.position))
         }
     }
+
+    /// Reads a path like `std.logic`, which is written as identifiers joined
+    /// by member accesses.
+    pub fn as_path(&self) -> Result<Vec<&'x str>, Diagnostic> {
+        if self.phrase == "member access" && !self.is_malformed() {
+            let mut path = self.children[0].as_node().as_path()?;
+            path.push(self.children[2].as_node().as_ident()?);
+            Ok(path)
+        } else {
+            Ok(vec![self.as_ident()?])
+        }
+    }
 }
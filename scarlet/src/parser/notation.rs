@@ -0,0 +1,503 @@
+//! Syntax declared in source code, like
+//!
+//! ```text
+//! NOTATION("{a} AND {b}" 140 100 and(a b))
+//! ```
+//!
+//! The pattern is made of operands in braces and regexes for the text between
+//! them, separated by spaces. It is followed by the precedence and priority of
+//! the new phrase, then by what it means written using existing syntax.
+//! Notations declared in a file can be used anywhere in that file and in the
+//! files in the folder next to it. What a notation means is created once,
+//! where it was declared, and each use is replaced while parsing with a
+//! substitution of its operands into that. Names in it always mean what they
+//! mean where the notation was declared, and the rest of the language never
+//! sees the notation itself.
+
+use std::{collections::HashSet, sync::Mutex};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{
+    lexer,
+    parse::parse,
+    phrase::{NodePhraseComponent, Phrase, PhraseComponent, Precedence, Priority},
+    util, Node, NodeChild, ParseContext,
+};
+use crate::diagnostic::{Diagnostic, Position};
+
+lazy_static! {
+    static ref R_OPERAND: Regex = Regex::new(r"^\{([a-zA-Z0-9_]+)\}$").unwrap();
+    /// Phrase names have to live forever, so the name of each pattern is
+    /// only made once and reused whenever the pattern is declared again, like
+    /// when a file is parsed again after it changes. Only used to find those
+    /// names, never to decide what a phrase is.
+    static ref NAMES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+/// Notation phrases are named after their quoted pattern, which no built in
+/// phrase is.
+const NAME_PREFIX: &str = "notation \"";
+
+fn name_of(quoted: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap();
+    let name = format!("notation {}", quoted);
+    if let Some(name) = names.get(&name[..]) {
+        name
+    } else {
+        let name = Box::leak(name.into_boxed_str());
+        names.insert(name);
+        name
+    }
+}
+
+/// Whether the phrase is a notation declared in any file, which only depends
+/// on its name so that notations declared elsewhere don't change it.
+fn is_notation_phrase(phrase: &str) -> bool {
+    phrase.starts_with(NAME_PREFIX)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationPiece {
+    Operand(String),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotationSyntax {
+    pub name: &'static str,
+    pub pieces: Vec<NotationPiece>,
+    pub precedence: Precedence,
+    pub priority: Priority,
+}
+
+impl NotationSyntax {
+    pub fn operands(&self) -> Vec<&str> {
+        self.pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                NotationPiece::Operand(name) => Some(&name[..]),
+                NotationPiece::Text(..) => None,
+            })
+            .collect()
+    }
+
+    /// The precedence of the operand at the given index in the pieces.
+    /// Operands between two pieces of text can be anything, like the
+    /// contents of brackets.
+    pub fn operand_precedence(&self, index: usize) -> Precedence {
+        if index > 0 && index + 1 < self.pieces.len() {
+            255
+        } else {
+            self.precedence.saturating_sub(1)
+        }
+    }
+
+    /// Text which the regex of a piece matches exactly, used when showing the
+    /// notation. Returns None if there isn't an obvious choice.
+    pub fn shown_text(regex: &str) -> Option<String> {
+        let text = regex.replace(r"\b", "").replace('\\', "");
+        let matches = lexer::anchored(&format!("(?:{})$", regex)).is_match(&text);
+        if matches {
+            Some(text)
+        } else {
+            None
+        }
+    }
+
+    pub fn phrase(&self) -> Phrase {
+        let components = self
+            .pieces
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| match piece {
+                NotationPiece::Operand(..) => PhraseComponent::Node(NodePhraseComponent {
+                    prec: self.operand_precedence(index),
+                    additional_allowed_phraes: vec![],
+                }),
+                NotationPiece::Text(regex) => regex[..].into(),
+            })
+            .collect();
+        Phrase {
+            name: self.name,
+            components,
+            create_and_uncreate: None,
+            vomit,
+            precedence: self.precedence,
+            priority: self.priority,
+            vomit_priority: self.priority,
+        }
+    }
+}
+
+fn vomit(pc: &ParseContext, src: &Node) -> String {
+    src.children
+        .iter()
+        .map(|child| child.vomit(pc))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Clone, Debug)]
+pub struct Notation<'x> {
+    pub syntax: NotationSyntax,
+    /// The names of the operands, in the same order as in the pattern.
+    pub operands: Vec<&'x str>,
+    /// What the notation means, with the operands written as identifiers.
+    pub template: Node<'x>,
+}
+
+fn declaration_error(node: &Node, text: String) -> Diagnostic {
+    Diagnostic::new()
+        .with_text_error(text)
+        .with_source_code_block_error(node.position)
+}
+
+fn read_pattern(node: &Node) -> Result<Vec<NotationPiece>, Diagnostic> {
+    let quoted = node.children[0].as_text();
+    let pattern = &quoted[1..quoted.len() - 1];
+    let mut pieces = Vec::new();
+    for piece in pattern.split_whitespace() {
+        if let Some(captures) = R_OPERAND.captures(piece) {
+            let name = captures[1].to_owned();
+            if pieces.contains(&NotationPiece::Operand(name.clone())) {
+                return Err(declaration_error(
+                    node,
+                    format!("The operand {{{}}} appears more than once:", name),
+                ));
+            }
+            if let Some(NotationPiece::Operand(..)) = pieces.last() {
+                return Err(declaration_error(
+                    node,
+                    format!("Operands must be separated by some text:"),
+                ));
+            }
+            pieces.push(NotationPiece::Operand(name));
+        } else {
+            let regex = Regex::new(&format!("^(?:{})", piece)).map_err(|err| {
+                declaration_error(node, format!("\"{}\" is not a valid regex: {}", piece, err))
+            })?;
+            if regex.is_match("") {
+                return Err(declaration_error(
+                    node,
+                    format!("\"{}\" can match empty text:", piece),
+                ));
+            }
+            pieces.push(NotationPiece::Text(piece.to_owned()));
+        }
+    }
+    if !pieces
+        .iter()
+        .any(|piece| matches!(piece, NotationPiece::Text(..)))
+    {
+        return Err(declaration_error(
+            node,
+            format!("A notation needs at least one piece of text:"),
+        ));
+    }
+    Ok(pieces)
+}
+
+fn read_number(node: &Node, what: &str) -> Result<u8, Diagnostic> {
+    let number = if node.phrase == "natural" {
+        node.children[0].as_text().parse().ok()
+    } else {
+        None
+    };
+    number.ok_or_else(|| {
+        declaration_error(
+            node,
+            format!("Expected the {} to be a number from 0 to 255:", what),
+        )
+    })
+}
+
+/// Checks whether the identifier is used anywhere in the node, other than as
+/// the name of a member.
+fn mentions(node: &Node, ident: &str) -> bool {
+    if node.phrase == "identifier" {
+        return node.children == [NodeChild::Text(ident)];
+    }
+    node.children
+        .iter()
+        .enumerate()
+        .any(|(index, child)| match child {
+            NodeChild::Node(child) => {
+                !(node.phrase == "member access" && index == 2) && mentions(child, ident)
+            }
+            _ => false,
+        })
+}
+
+/// Checks whether the node uses any notation not in the list.
+fn uses_other_notation(node: &Node, notations: &[Notation]) -> bool {
+    let is_other = is_notation_phrase(node.phrase)
+        && !notations
+            .iter()
+            .any(|notation| notation.syntax.name == node.phrase);
+    is_other
+        || node.children.iter().any(|child| match child {
+            NodeChild::Node(child) => uses_other_notation(child, notations),
+            _ => false,
+        })
+}
+
+/// Reads the notation declared by a NOTATION phrase.
+pub fn declared_by<'x>(node: &Node<'x>) -> Result<Notation<'x>, Diagnostic> {
+    assert_eq!(node.phrase, "notation");
+    let args = util::collect_comma_list(&node.children[2]);
+    if args.len() != 4 || args[0].phrase != "notation pattern" {
+        return Err(declaration_error(
+            node,
+            format!(
+                "Expected a pattern in quotes, a precedence, a priority and what the \
+                 notation means:"
+            ),
+        ));
+    }
+    let pieces = read_pattern(args[0])?;
+    let precedence = read_number(args[1], "precedence")?;
+    let priority = read_number(args[2], "priority")?;
+    let template = args[3].clone();
+    if template.phrase == "identifier"
+        && pieces.contains(&NotationPiece::Operand(template.as_ident()?.to_owned()))
+    {
+        return Err(declaration_error(
+            args[3],
+            format!("A notation has to mean something other than one of its operands:"),
+        ));
+    }
+    for piece in &pieces {
+        if let NotationPiece::Operand(name) = piece {
+            if !mentions(&template, name) {
+                return Err(declaration_error(
+                    args[3],
+                    format!("This does not use the operand {{{}}}:", name),
+                ));
+            }
+        }
+    }
+    let quoted = args[0].children[0].as_text();
+    let operands = quoted[1..quoted.len() - 1]
+        .split_whitespace()
+        .filter_map(|piece| R_OPERAND.captures(piece))
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+    let name = name_of(quoted);
+    Ok(Notation {
+        syntax: NotationSyntax {
+            name,
+            pieces,
+            precedence,
+            priority,
+        },
+        operands,
+        template,
+    })
+}
+
+/// Every notation declared at the top level of a file.
+fn declarations<'a, 'x>(node: &'a Node<'x>) -> Vec<&'a Node<'x>> {
+    if node.phrase == "multiple items" {
+        node.children
+            .iter()
+            .flat_map(|child| match child {
+                NodeChild::Node(child) => declarations(child),
+                _ => Vec::new(),
+            })
+            .collect()
+    } else if node.phrase == "notation" && !node.is_malformed() {
+        vec![node]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Parses a file, first looking for notations declared at the top level of it
+/// so that they can be used anywhere in the file. If there are any, also
+/// returns the parse context extended with them.
+pub fn parse_with_notations<'x>(
+    input: &'x str,
+    ctx: &ParseContext,
+    file_index: usize,
+) -> (Option<Node<'x>>, Vec<Diagnostic>, Option<ParseContext>) {
+    let mut extended: Option<ParseContext> = None;
+    let mut declared: Vec<NotationSyntax> = Vec::new();
+    loop {
+        let (root, diagnostics) = parse(input, extended.as_ref().unwrap_or(ctx), file_index);
+        // A declaration might only make sense once the notations declared
+        // before it are known, so keep going until nothing new turns up.
+        let mut found_new = false;
+        for node in root.as_ref().map(declarations).unwrap_or_default() {
+            if let Ok(notation) = declared_by(node) {
+                let known = ctx.notations().contains(&notation.syntax)
+                    || declared.contains(&notation.syntax);
+                if !known {
+                    declared.push(notation.syntax);
+                    found_new = true;
+                }
+            }
+        }
+        if !found_new {
+            return (root, diagnostics, extended);
+        }
+        let mut notations = ctx.notations().to_vec();
+        notations.extend(declared.iter().cloned());
+        extended = Some(ParseContext::with_notations(notations));
+    }
+}
+
+/// Replaces every use of a notation with what it means. Notations declared at
+/// the top level of the file are added to the list, and any mistakes in them
+/// are reported.
+pub fn desugar<'x>(
+    root: &Node<'x>,
+    notations: &mut Vec<Notation<'x>>,
+) -> (Node<'x>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let mut declared = Vec::new();
+    for node in declarations(root) {
+        match declared_by(node) {
+            Ok(notation) => declared.push((node, notation)),
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+    for (node, notation) in declared {
+        // Templates can only use notations declared before them, which
+        // keeps them from being defined in terms of themselves. Uses of ones
+        // which break this stand in for nothing.
+        if uses_other_notation(&notation.template, notations) {
+            diagnostics.push(declaration_error(
+                node,
+                format!("What a notation means can only use notations declared before it:"),
+            ));
+        } else {
+            notations.push(notation);
+        }
+    }
+    let root = expand(root, notations, true, &mut diagnostics);
+    (root, diagnostics)
+}
+
+/// Replaces every use of a notation in code that was parsed on its own, like a
+/// line typed into the REPL. Only the syntax of the notations is needed, since
+/// there can't be any declarations to check in such code.
+pub fn desugar_expression<'x>(
+    node: &Node<'x>,
+    syntaxes: &'x [NotationSyntax],
+) -> (Node<'x>, Vec<Diagnostic>) {
+    let notations = syntaxes
+        .iter()
+        .map(|syntax| Notation {
+            syntax: syntax.clone(),
+            operands: syntax.operands(),
+            template: stand_in(Position::placeholder()),
+        })
+        .collect::<Vec<_>>();
+    let mut diagnostics = Vec::new();
+    let node = expand(node, &notations, false, &mut diagnostics);
+    (node, diagnostics)
+}
+
+/// Uses of a notation that couldn't be declared stand in for nothing, like
+/// code with a syntax error in it. The mistake has already been reported.
+fn stand_in<'x>(position: Position) -> Node<'x> {
+    Node {
+        phrase: "identifier",
+        children: vec![NodeChild::Missing],
+        position,
+    }
+}
+
+/// Replaces uses of the notations in the node. Declarations are only looked
+/// for at the top level of a file, so any others are reported.
+fn expand<'x>(
+    node: &Node<'x>,
+    notations: &[Notation<'x>],
+    top_level: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Node<'x> {
+    if node.phrase == "notation" && !top_level {
+        diagnostics.push(declaration_error(
+            node,
+            format!("NOTATION can only be used directly inside a file:"),
+        ));
+        return stand_in(node.position);
+    }
+    let children_top_level = top_level && node.phrase == "multiple items";
+    let mut children = node
+        .children
+        .iter()
+        .map(|child| match child {
+            NodeChild::Node(child) => {
+                NodeChild::Node(expand(child, notations, children_top_level, diagnostics))
+            }
+            other => other.clone(),
+        })
+        .collect::<Vec<_>>();
+    let notation = notations
+        .iter()
+        .rev()
+        .find(|notation| notation.syntax.name == node.phrase);
+    let notation = match notation {
+        // Malformed uses are left as they are, since they stand in for
+        // nothing anyway.
+        Some(notation) if !node.is_malformed() => notation,
+        None if is_notation_phrase(node.phrase) => return stand_in(node.position),
+        _ => {
+            return Node {
+                children,
+                ..node.clone()
+            }
+        }
+    };
+    let operands = children
+        .drain(..)
+        .filter_map(|child| match child {
+            NodeChild::Node(operand) => Some(operand),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    instantiate(notation, operands, node.position)
+}
+
+/// Makes a substitution of the operands into what the notation means, like
+/// `notation "{a} AND {b}"(a IS x  b IS y)`. Where the notation was
+/// declared, what it means is given the name of the notation, which can't be
+/// written in source code.
+fn instantiate<'x>(
+    notation: &Notation<'x>,
+    operands: Vec<Node<'x>>,
+    position: Position,
+) -> Node<'x> {
+    let identifier = |name: &'x str| Node {
+        phrase: "identifier",
+        children: vec![NodeChild::Text(name)],
+        position,
+    };
+    let subs = notation
+        .operands
+        .iter()
+        .zip(operands)
+        .map(|(name, operand)| Node {
+            phrase: "is",
+            children: vec![
+                NodeChild::Node(identifier(name)),
+                NodeChild::Text("IS"),
+                NodeChild::Node(operand),
+            ],
+            position,
+        })
+        .collect();
+    Node {
+        phrase: "substitution",
+        children: vec![
+            NodeChild::Node(identifier(notation.syntax.name)),
+            NodeChild::Text("("),
+            util::create_comma_list(subs),
+            NodeChild::Text(")"),
+        ],
+        position,
+    }
+}
@@ -1,27 +1,42 @@
+use lazy_static::lazy_static;
 use regex::Regex;
 
 use super::{
-    diagnostics::incomplete_phrase_error,
+    diagnostics::{incomplete_phrase_error, nothing_before, unrecognized_input},
+    lexer::{self, Lexer, Token, TokenRegexes},
     matchh::{MatchSuccess, StackAction},
     node::{Node, NodeChild},
+    notation::{self, Notation, NotationSyntax},
     phrase::PhraseTable,
     util,
 };
 use crate::{
     diagnostic::{Diagnostic, Position},
     file_tree::FileNode,
-    parser::{diagnostics::unrecognized_input, matchh, scarlet_phrases, stack::Stack},
+    parser::{matchh, scarlet_phrases, stack::Stack},
 };
 
+lazy_static! {
+    static ref R_DEFINITION: Regex = lexer::anchored(r"[a-zA-Z0-9_]+[ \r\n\t]+IS\b|IMPORT\b");
+}
+
 pub struct ParseContext {
     pub(crate) phrases_sorted_by_priority: PhraseTable,
     pub(crate) phrases_sorted_by_vomit_priority: PhraseTable,
+    pub(crate) token_regexes: TokenRegexes,
+    notations: Vec<NotationSyntax>,
 }
 
 impl ParseContext {
     pub fn new() -> Self {
+        Self::with_notations(Vec::new())
+    }
+
+    /// Like new, but can also parse notations declared in source code.
+    pub fn with_notations(notations: Vec<NotationSyntax>) -> Self {
         let mut phrases_sorted_by_priority = PhraseTable::new();
         let mut source = scarlet_phrases::phrases();
+        source.extend(notations.iter().map(NotationSyntax::phrase));
         source.sort_by_key(|p| p.priority);
         for phrase in source {
             phrases_sorted_by_priority.insert(phrase.name.to_owned(), phrase);
@@ -29,16 +44,25 @@ impl ParseContext {
 
         let mut phrases_sorted_by_vomit_priority = PhraseTable::new();
         let mut source = scarlet_phrases::phrases();
+        source.extend(notations.iter().map(NotationSyntax::phrase));
         source.sort_by_key(|p| p.vomit_priority);
         for phrase in source {
             phrases_sorted_by_vomit_priority.insert(phrase.name.to_owned(), phrase);
         }
 
+        let token_regexes = TokenRegexes::new(&phrases_sorted_by_priority);
+
         Self {
             phrases_sorted_by_priority,
             phrases_sorted_by_vomit_priority,
+            token_regexes,
+            notations,
         }
     }
+
+    pub fn notations(&self) -> &[NotationSyntax] {
+        &self.notations
+    }
 }
 
 fn push_match<'a>(
@@ -46,23 +70,40 @@ fn push_match<'a>(
     matchh: MatchSuccess<'a>,
     to: &mut Stack<'a>,
     position: Position,
-) -> Result<(), Diagnostic> {
+    diagnostics: &mut Vec<Diagnostic>,
+) {
     let mut append = Vec::new();
     if let StackAction::PopNode(prec) = matchh.action {
-        to.collapse_to_precedence(pt, prec)?;
-        if Some(to.0.len() - 1) == matchh.continuation_of {
-            append.push(NodeChild::Missing);
-        } else {
-            let top = to.0.pop().unwrap();
-            if !top.is_complete(pt) {
-                return Err(incomplete_phrase_error(&top)
+        loop {
+            to.collapse_to_precedence(pt, prec, diagnostics);
+            if to.0.len() == 0 {
+                diagnostics.push(nothing_before(matchh.phrase, position));
+                append.push(NodeChild::Missing);
+                break;
+            } else if Some(to.0.len() - 1) == matchh.continuation_of {
+                append.push(NodeChild::Missing);
+                break;
+            }
+            let top = to.0.last_mut().unwrap();
+            if top.is_complete(pt) {
+                append.push(NodeChild::Node(to.0.pop().unwrap()));
+                break;
+            }
+            diagnostics.push(
+                incomplete_phrase_error(top)
                     .with_text_info(format!(
                         "It is interrupted by a \"{}\" phrase:",
                         matchh.phrase
                     ))
-                    .with_source_code_block_info(position));
+                    .with_source_code_block_info(position),
+            );
+            if matchh.continuation_of.is_none() && top.is_waiting_for_node(pt) {
+                // Something like "a IS IS b", the second IS is missing the
+                // node it would usually take from the top of the stack.
+                append.push(NodeChild::Missing);
+                break;
             }
-            append.push(NodeChild::Node(top));
+            top.pad_with_missing(pt);
         }
     } else {
         if to
@@ -77,7 +118,7 @@ fn push_match<'a>(
                 text: ",",
                 continuation_of: None,
             };
-            push_match(pt, matchh, to, Default::default())?;
+            push_match(pt, matchh, to, Default::default(), diagnostics);
         }
     }
     append.push(NodeChild::Text(matchh.text));
@@ -98,94 +139,176 @@ fn push_match<'a>(
             position,
         });
     }
-    Ok(())
 }
 
-fn parse<'a>(
+/// Where parsing can pick back up after a syntax error: the start of a line
+/// defining or importing something, like "name IS ..." or "IMPORT ...".
+fn is_definition_start(input: &str, position: usize) -> bool {
+    let line_start = input[..position].rfind('\n').map(|x| x + 1).unwrap_or(0);
+    input[line_start..position].trim().len() == 0
+        && matchh::anchored_find(&R_DEFINITION, &input[position..]).is_some()
+}
+
+/// Finds the longest match for the text starting at the token, if any phrase
+/// can use it there.
+fn match_token<'a>(
+    token: &Token<'a>,
+    stack: &Stack<'a>,
+    phrases: &PhraseTable,
+    token_regexes: &TokenRegexes,
+) -> Option<MatchSuccess<'a>> {
+    if token.recognized {
+        matchh::longest_match(token, stack, phrases, token_regexes)
+    } else {
+        None
+    }
+}
+
+/// Parses as much of the input as possible. After a syntax error, input is
+/// skipped until the start of the next definition or the bracket closing the
+/// one the error was in, so that one mistake produces one error.
+pub fn parse<'a>(
     input: &'a str,
-    ctx: &'a ParseContext,
+    ctx: &ParseContext,
     file_index: usize,
-) -> Result<Option<Node<'a>>, Diagnostic> {
-    let r_whitespace = Regex::new(r"[ \r\n\t]+|#[^\n]*").unwrap();
-
+) -> (Option<Node<'a>>, Vec<Diagnostic>) {
     let ParseContext {
         phrases_sorted_by_priority: phrases,
+        token_regexes,
         ..
     } = ctx;
 
     let mut stack = Stack(Vec::new());
+    let mut diagnostics = Vec::new();
+    // Diagnostics caused by recovering from an earlier error end up here.
+    let mut ignored = Vec::new();
+    // How many brackets deep we are in the input being skipped, or None if
+    // we are not recovering from an error.
+    let mut skip_depth: Option<usize> = None;
 
-    let mut input_position = 0;
-    let mut comment_depth = 0;
-    while input_position < input.len() {
-        let match_against = &input[input_position..];
-        if match_against.len() < 2 {
-        } else if &match_against[0..2] == "#=" {
-            comment_depth += 1;
-            input_position += 2;
-            continue;
-        } else if &match_against[0..2] == "=#" {
-            comment_depth -= 1;
-            input_position += 2;
-            continue;
-        } else if comment_depth > 0 {
-            input_position += 1;
-            continue;
+    let mut lexer = Lexer::new(input, token_regexes);
+    while let Some(token) = lexer.next() {
+        if skip_depth.is_some() && is_definition_start(input, token.start) {
+            stack.close_to_bracket(phrases);
+            skip_depth = None;
         }
-        let longest_match = matchh::longest_match(match_against, &stack, phrases);
-        if let Some(matchh) = longest_match {
-            input_position += matchh.text.len();
-            let start_char = input.len() - match_against.len();
+        if let Some(matchh) = match_token(&token, &stack, phrases, token_regexes) {
+            if matchh.text.len() < token.text.len() {
+                lexer.restart_at(token.start + matchh.text.len());
+            }
             let file_position = Position::new(
                 file_index as usize,
-                start_char..start_char + matchh.text.len(),
+                token.start..token.start + matchh.text.len(),
             );
-            push_match(phrases, matchh, &mut stack, file_position)?;
-        } else if let Some(matchh) = matchh::anchored_find(&r_whitespace, match_against) {
-            input_position += matchh.len();
+            if let Some(depth) = skip_depth {
+                // Whether this is the last piece of text in a bracket-like
+                // phrase we were in the middle of.
+                let closes = matchh.continuation_of.map(|index| {
+                    let node = &stack.0[index];
+                    let components = &phrases[node.phrase].components;
+                    let mut text_index = node.children.len();
+                    if components[text_index].is_node() {
+                        text_index += 1;
+                    }
+                    text_index + 1 == components.len()
+                });
+                if depth == 0 && closes == Some(true) {
+                    skip_depth = None;
+                    push_match(phrases, matchh, &mut stack, file_position, &mut ignored);
+                } else {
+                    let opened = matchh.text.matches(&['(', '[', '{'][..]).count();
+                    let closed = matchh.text.matches(&[')', ']', '}'][..]).count();
+                    skip_depth = Some((depth + opened).saturating_sub(closed));
+                }
+            } else {
+                push_match(phrases, matchh, &mut stack, file_position, &mut diagnostics);
+            }
         } else {
-            let start_char = input.len() - match_against.len();
-            let file_position = Position::new(file_index as usize, start_char..start_char + 1);
-            return Err(unrecognized_input(file_position));
+            // Report a run of tokens that can't be used here as a single
+            // error.
+            let mut end = token.end();
+            loop {
+                let mut ahead = lexer.clone();
+                match ahead.next() {
+                    Some(next)
+                        if next.start == end
+                            && match_token(&next, &stack, phrases, token_regexes).is_none() =>
+                    {
+                        end = next.end();
+                        lexer = ahead;
+                    }
+                    _ => break,
+                }
+            }
+            if skip_depth.is_none() {
+                let file_position = Position::new(file_index as usize, token.start..end);
+                diagnostics.push(unrecognized_input(file_position));
+                skip_depth = Some(0);
+            }
         }
     }
 
+    let end_diagnostics = if skip_depth.is_some() {
+        &mut ignored
+    } else {
+        &mut diagnostics
+    };
     while stack.0.len() > 1 {
-        stack.collapse(phrases)?;
+        stack.collapse(phrases, end_diagnostics);
+    }
+    let mut root = stack.0.pop();
+    if let Some(root) = &mut root {
+        if !root.is_complete(phrases) {
+            end_diagnostics.push(incomplete_phrase_error(root));
+            root.pad_with_missing(phrases);
+        }
     }
 
-    Ok(stack.0.pop())
+    (root, diagnostics)
 }
 
+/// Parses a tree of files, returning every syntax error along with as much of
+/// the tree as could be parsed.
 pub fn parse_tree<'x>(
     tree: &'x FileNode,
-    ctx: &'x ParseContext,
+    ctx: &ParseContext,
+    file_counter: &mut usize,
+) -> (Node<'x>, Vec<Diagnostic>) {
+    parse_tree_with_notations(tree, ctx, &mut Vec::new(), file_counter)
+}
+
+/// Notations declared in a file are added to the context and the list of
+/// notations used for the files in the folder next to it.
+fn parse_tree_with_notations<'x>(
+    tree: &'x FileNode,
+    ctx: &ParseContext,
+    notations: &mut Vec<Notation<'x>>,
     file_counter: &mut usize,
-) -> Result<Node<'x>, Vec<Diagnostic>> {
+) -> (Node<'x>, Vec<Diagnostic>) {
     *file_counter += 1;
+    let file_index = *file_counter;
     let mut children = Vec::new();
     let mut diagnostics = Vec::new();
+    let inherited = notations.len();
+    let mut extended_ctx = None;
     if tree.self_content.trim().len() > 0 {
-        match parse(&tree.self_content, ctx, *file_counter) {
-            Ok(Some(content)) => {
-                for child in util::collect_comma_list(&NodeChild::Node(content)) {
-                    children.push(child.clone());
-                }
-            }
-            Err(diagnostic) => {
-                diagnostics.push(diagnostic);
+        let (content, mut content_diagnostics, file_ctx) =
+            notation::parse_with_notations(&tree.self_content, ctx, file_index);
+        diagnostics.append(&mut content_diagnostics);
+        if let Some(content) = content {
+            let (content, mut notation_diagnostics) = notation::desugar(&content, notations);
+            diagnostics.append(&mut notation_diagnostics);
+            for child in util::collect_comma_list(&NodeChild::Node(content)) {
+                children.push(child.clone());
             }
-            Ok(None) => (),
         }
+        extended_ctx = file_ctx;
     }
+    let ctx = extended_ctx.as_ref().unwrap_or(ctx);
     for (name, child) in &tree.children {
-        let child = match parse_tree(child, ctx, file_counter) {
-            Ok(child) => child,
-            Err(mut other_diagnostics) => {
-                diagnostics.append(&mut other_diagnostics);
-                continue;
-            }
-        };
+        let (child, mut child_diagnostics) =
+            parse_tree_with_notations(child, ctx, notations, file_counter);
+        diagnostics.append(&mut child_diagnostics);
         children.push(Node {
             phrase: "is",
             children: vec![
@@ -200,17 +323,18 @@ pub fn parse_tree<'x>(
             ..Default::default()
         })
     }
-    if diagnostics.len() > 0 {
-        Err(diagnostics)
-    } else {
-        Ok(Node {
-            phrase: "struct",
-            children: vec![
-                NodeChild::Text("{"),
-                util::create_comma_list(children),
-                NodeChild::Text("}"),
-            ],
-            ..Default::default()
-        })
-    }
+    let root = Node {
+        phrase: "struct",
+        children: vec![
+            NodeChild::Text("{"),
+            util::create_comma_list(children),
+            NodeChild::Text("}"),
+        ],
+        // Points at the start of the file so that things like
+        // go-to-definition have somewhere to go.
+        position: Position::new(file_index, 0..0),
+    };
+    // Notations from this file can't be used in the files next to it.
+    notations.truncate(inherited);
+    (root, diagnostics)
 }
@@ -1,7 +1,7 @@
 use indexmap::IndexMap;
 use regex::Regex;
 
-use super::{Node, ParseContext};
+use super::{lexer, Node, ParseContext};
 use crate::{
     environment::{vomit::VomitContext, Environment},
     item::{resolvable::UnresolvedItemError, ItemPtr},
@@ -35,7 +35,7 @@ impl From<Precedence> for PhraseComponent {
 
 impl From<&str> for PhraseComponent {
     fn from(regex: &str) -> Self {
-        Self::Text(Regex::new(regex).unwrap())
+        Self::Text(lexer::anchored(regex))
     }
 }
 
@@ -74,14 +74,17 @@ pub struct Phrase {
 }
 
 impl Phrase {
+    /// The next component that is text, by its index, along with the node
+    /// before it if there is one.
     pub fn upcoming(
         &self,
         starting_at_component: usize,
-    ) -> (Option<&NodePhraseComponent>, Option<&Regex>) {
+    ) -> (Option<&NodePhraseComponent>, Option<usize>) {
         let mut preceding_node_precedence = None;
-        for component in &self.components[starting_at_component..] {
-            if let PhraseComponent::Text(regex) = component {
-                return (preceding_node_precedence, Some(regex));
+        for (index, component) in self.components.iter().enumerate().skip(starting_at_component)
+        {
+            if let PhraseComponent::Text(..) = component {
+                return (preceding_node_precedence, Some(index));
             } else if let PhraseComponent::Node(node) = component {
                 preceding_node_precedence = Some(node)
             }
@@ -1,16 +1,24 @@
 mod as_auto_theorem;
 mod as_language_item;
 mod axiom;
+mod by;
 mod decision;
 mod equal;
 mod from;
 mod identifier;
+mod import;
+mod integer;
 mod is;
 mod is_populated_struct;
 mod label_access;
 mod member_access;
 mod multiple_items;
+mod natural;
+mod normalized;
+mod notation;
+mod notation_pattern;
 mod populated_struct;
+mod private;
 mod rest_access;
 mod shown;
 mod structt;
@@ -57,13 +65,21 @@ pub fn phrases() -> Vec<Phrase> {
         rest_access::phrase(),
         is_populated_struct::phrase(),
         shown::phrase(),
+        normalized::phrase(),
+        private::phrase(),
         as_language_item::phrase(),
         as_auto_theorem::phrase(),
+        by::phrase(),
         member_access::phrase(),
         substitution::phrase(),
         structt::phrase(),
         is::phrase(),
+        import::phrase(),
+        notation::phrase(),
+        notation_pattern::phrase(),
         multiple_items::phrase(),
+        natural::phrase(),
+        integer::phrase(),
         identifier::phrase(),
     ]
 }
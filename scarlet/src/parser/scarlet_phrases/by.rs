@@ -0,0 +1,56 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::ItemPtr,
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        util, Node, NodeChild, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+fn create(
+    pc: &ParseContext,
+    env: &mut Environment,
+    scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    assert_eq!(node.children.len(), 5);
+    assert_eq!(node.children[1], NodeChild::Text("BY"));
+    assert_eq!(node.children[2], NodeChild::Text("("));
+    assert_eq!(node.children[4], NodeChild::Text(")"));
+    let base = node.children[0].as_construct_dyn_scope(pc, env, scope.dyn_clone())?;
+    for hint in util::collect_comma_list(&node.children[3]) {
+        let hint = hint.as_item_dyn_scope(pc, env, scope.dyn_clone())?;
+        env.add_hint(hint.ptr_clone());
+        base.borrow_mut().hints.push(hint);
+    }
+    Ok(base)
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    _ctx: &mut VomitContext<'a, '_>,
+    _uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    Ok(None)
+}
+
+fn vomit(pc: &ParseContext, src: &Node) -> String {
+    format!(
+        "{} BY({})",
+        src.children[0].vomit(pc),
+        src.children[3].vomit(pc)
+    )
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "by",
+        128, 128,
+        Some((create, uncreate)),
+        vomit,
+        200 => 200, r"\bBY\b", r"\(", 255, r"\)"
+    )
+}
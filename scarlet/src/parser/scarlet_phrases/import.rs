@@ -0,0 +1,52 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::ItemPtr,
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        Node, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+/// Imports are picked out of the fields of a struct when it is created, so
+/// this only runs for ones written anywhere else.
+fn create(
+    _pc: &ParseContext,
+    _env: &mut Environment,
+    _scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    Err(Diagnostic::new()
+        .with_text_error(format!(
+            "IMPORT can only be used directly inside a struct or file:"
+        ))
+        .with_source_code_block_error(node.position))
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    _ctx: &mut VomitContext<'a, '_>,
+    _uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    Ok(None)
+}
+
+fn vomit(pc: &ParseContext, src: &Node) -> String {
+    format!(
+        "IMPORT {} AS {}",
+        src.children[1].vomit(pc),
+        src.children[3].vomit(pc)
+    )
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "import",
+        128, 128,
+        Some((create, uncreate)),
+        vomit,
+        250 => r"\bIMPORT\b", 250, r"\bAS\b", 250
+    )
+}
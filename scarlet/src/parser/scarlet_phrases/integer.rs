@@ -0,0 +1,61 @@
+use num::BigInt;
+
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::{definitions::number::DInteger, Item, ItemDefinition, ItemPtr},
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        Node, NodeChild, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+fn create(
+    _pc: &ParseContext,
+    _env: &mut Environment,
+    scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    assert_eq!(node.children.len(), 1);
+    let text = node.children[0].as_text();
+    let value: BigInt = text.trim_start_matches('+').parse().unwrap();
+    Ok(Item::new_boxed(
+        DInteger::new(value).clone_into_box(),
+        scope,
+    ))
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    ctx: &mut VomitContext<'a, '_>,
+    uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    let uncreate = uncreate.dereference();
+    let result = if let Some(integer) = uncreate.downcast_definition::<DInteger>() {
+        let text = ctx.code_arena.alloc(format!("{:+}", integer.value()));
+        Some(Node {
+            phrase: "integer",
+            children: vec![NodeChild::Text(text)],
+            ..Default::default()
+        })
+    } else {
+        None
+    };
+    Ok(result)
+}
+
+fn vomit(_pc: &ParseContext, src: &Node) -> String {
+    src.children[0].as_text().to_owned()
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "integer",
+        128, 0,
+        Some((create, uncreate)),
+        vomit,
+        0 => r"[+-][0-9]+\b"
+    )
+}
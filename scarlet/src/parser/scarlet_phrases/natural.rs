@@ -0,0 +1,58 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::{definitions::number::DNatural, Item, ItemDefinition, ItemPtr},
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        Node, NodeChild, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+fn create(
+    _pc: &ParseContext,
+    env: &mut Environment,
+    scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    assert_eq!(node.children.len(), 1);
+    let value = node.children[0].as_text().parse().unwrap();
+    Ok(Item::new_boxed(
+        DNatural::new(env, value).clone_into_box(),
+        scope,
+    ))
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    ctx: &mut VomitContext<'a, '_>,
+    uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    let uncreate = uncreate.dereference();
+    let result = if let Some(natural) = uncreate.downcast_definition::<DNatural>() {
+        let text = ctx.code_arena.alloc(natural.value().to_string());
+        Some(Node {
+            phrase: "natural",
+            children: vec![NodeChild::Text(text)],
+            ..Default::default()
+        })
+    } else {
+        None
+    };
+    Ok(result)
+}
+
+fn vomit(_pc: &ParseContext, src: &Node) -> String {
+    src.children[0].as_text().to_owned()
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "natural",
+        128, 0,
+        Some((create, uncreate)),
+        vomit,
+        0 => r"[0-9]+\b"
+    )
+}
@@ -0,0 +1,124 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{notation::Notation, vomit::VomitContext, Environment},
+    item::{
+        definitions::{
+            structt::{DPopulatedStruct, SFieldAndRest},
+            variable::{DVariable, VariableOrder},
+        },
+        ItemPtr,
+    },
+    parser::{
+        notation,
+        phrase::{Phrase, UncreateResult},
+        Node, ParseContext,
+    },
+    phrase,
+    scope::{SImports, SPlain, Scope},
+};
+
+/// Notations are picked out of the fields of a struct when it is created, and
+/// the parser reports ones which aren't at the top level of a file, so this
+/// only runs for code that was parsed on its own.
+fn create(
+    _pc: &ParseContext,
+    _env: &mut Environment,
+    _scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    Err(Diagnostic::new()
+        .with_text_error(format!("NOTATION can only be used directly inside a file:"))
+        .with_source_code_block_error(node.position))
+}
+
+/// Creates what each notation means, so that items can be shown using it and
+/// uses of it can substitute into it. The operands are variables which are
+/// only visible inside the template. Each template is visible inside the
+/// struct under the name of its notation, which is what the parser replaces
+/// uses of the notation with.
+pub(super) fn create_notations(
+    pc: &ParseContext,
+    env: &mut Environment,
+    declarations: Vec<&Node>,
+    parent: &ItemPtr,
+) -> Result<(), Diagnostic> {
+    let mut templates = Vec::new();
+    for node in declarations {
+        let declared = match notation::declared_by(node) {
+            Ok(declared) => declared,
+            // The mistake has already been reported by the parser.
+            Err(_) => continue,
+        };
+        let position = node.position;
+        let operands = declared
+            .syntax
+            .operands()
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let order = VariableOrder::new(
+                    128,
+                    position.file_index() as _,
+                    (position.range().start + index) as _,
+                );
+                let var =
+                    DVariable::new(vec![], vec![], order, Box::new(SPlain(parent.ptr_clone())));
+                (name.to_owned(), var)
+            })
+            .collect::<Vec<_>>();
+        // Other names in the template are looked up among the members of
+        // the struct the notation was declared in.
+        let inner: Box<dyn Scope> = if parent.downcast_definition::<DPopulatedStruct>().is_some() {
+            Box::new(SFieldAndRest(parent.ptr_clone()))
+        } else {
+            Box::new(SPlain(parent.ptr_clone()))
+        };
+        let scope = SImports {
+            imports: operands
+                .iter()
+                .map(|(name, var)| (name.clone(), var.ptr_clone()))
+                .collect(),
+            inner,
+        };
+        let template = declared.template.as_item(pc, env, scope)?;
+        templates.push((declared.syntax.name.to_owned(), template.ptr_clone()));
+        env.add_notation(Notation {
+            syntax: declared.syntax,
+            template,
+            operands: operands.into_iter().map(|(_, var)| var).collect(),
+            declared_in: parent.ptr_clone(),
+        });
+    }
+    if templates.len() > 0 {
+        // Nothing has been resolved yet, so nothing has looked names up in
+        // the old scope.
+        let inner = parent.clone_scope();
+        parent.borrow_mut().scope = Box::new(SImports {
+            imports: templates,
+            inner,
+        });
+    }
+    Ok(())
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    _ctx: &mut VomitContext<'a, '_>,
+    _uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    Ok(None)
+}
+
+fn vomit(pc: &ParseContext, src: &Node) -> String {
+    format!("NOTATION({})", src.children[2].vomit(pc))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "notation",
+        128, 128,
+        Some((create, uncreate)),
+        vomit,
+        4 => r"\bNOTATION\b", r"\(", 255, r"\)"
+    )
+}
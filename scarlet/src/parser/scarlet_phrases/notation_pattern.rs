@@ -0,0 +1,46 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::ItemPtr,
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        Node, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+fn create(
+    _pc: &ParseContext,
+    _env: &mut Environment,
+    _scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    Err(Diagnostic::new()
+        .with_text_error(format!(
+            "Text in quotes can only be used as the pattern of a NOTATION:"
+        ))
+        .with_source_code_block_error(node.position))
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    _ctx: &mut VomitContext<'a, '_>,
+    _uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    Ok(None)
+}
+
+fn vomit(_pc: &ParseContext, src: &Node) -> String {
+    src.children[0].as_text().to_owned()
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "notation pattern",
+        128, 128,
+        Some((create, uncreate)),
+        vomit,
+        0 => r#""[^"\n]*""#
+    )
+}
@@ -0,0 +1,46 @@
+use crate::{
+    diagnostic::Diagnostic,
+    environment::{vomit::VomitContext, Environment},
+    item::ItemPtr,
+    parser::{
+        phrase::{Phrase, UncreateResult},
+        Node, NodeChild, ParseContext,
+    },
+    phrase,
+    scope::Scope,
+};
+
+fn create(
+    pc: &ParseContext,
+    env: &mut Environment,
+    scope: Box<dyn Scope>,
+    node: &Node,
+) -> Result<ItemPtr, Diagnostic> {
+    assert_eq!(node.children.len(), 2);
+    assert_eq!(node.children[1], NodeChild::Text("PRIVATE"));
+    let base = node.children[0].as_construct_dyn_scope(pc, env, scope)?;
+    base.borrow_mut().private = true;
+    Ok(base)
+}
+
+fn uncreate<'a>(
+    _env: &mut Environment,
+    _ctx: &mut VomitContext<'a, '_>,
+    _uncreate: ItemPtr,
+) -> UncreateResult<'a> {
+    Ok(None)
+}
+
+fn vomit(pc: &ParseContext, src: &Node) -> String {
+    format!("{} PRIVATE", src.children[0].vomit(pc))
+}
+
+pub fn phrase() -> Phrase {
+    phrase!(
+        "private",
+        128, 128,
+        Some((create, uncreate)),
+        vomit,
+        4 => 4, r"\bPRIVATE\b"
+    )
+}
@@ -1,9 +1,11 @@
+use super::notation;
 use crate::{
     diagnostic::Diagnostic,
     environment::{vomit::VomitContext, Environment},
     item::{
         definitions::structt::{DPopulatedStruct, SField, SFieldAndRest},
-        ItemDefinition, ItemPtr,
+        resolvable::{DResolvable, RImport},
+        Item, ItemDefinition, ItemPtr,
     },
     parser::{
         phrase::{Phrase, UncreateResult},
@@ -11,7 +13,7 @@ use crate::{
         Node, NodeChild, ParseContext,
     },
     phrase,
-    scope::Scope,
+    scope::{SImports, SPlain, SRoot, Scope},
 };
 
 fn struct_from_fields(
@@ -46,21 +48,78 @@ fn create(
     assert_eq!(node.children.len(), 3);
     assert_eq!(node.children[0], NodeChild::Text("{"));
     assert_eq!(node.children[2], NodeChild::Text("}"));
-    let fields = util::collect_comma_list(&node.children[1]);
+    let (imports, fields): (Vec<_>, Vec<_>) = util::collect_comma_list(&node.children[1])
+        .into_iter()
+        .partition(|field| field.phrase == "import");
+    let (notations, fields): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .partition(|field| field.phrase == "notation");
+    let imports = create_imports(env, imports)?;
     let fields = fields
         .into_iter()
         .map(|field| {
-            Ok(if field.phrase == "is" {
-                (
-                    Some(field.children[0].as_node().as_ident()?),
-                    field.children[2].as_node(),
-                )
-            } else {
-                (None, field)
-            })
+            Ok(
+                if let ("is", NodeChild::Node(label)) = (field.phrase, &field.children[0]) {
+                    // If the value could not be parsed, the malformed field stands
+                    // in for it so that the label is still defined.
+                    let value = match &field.children[2] {
+                        NodeChild::Node(value) => value,
+                        _ => field,
+                    };
+                    (Some(label.as_ident()?), value)
+                } else {
+                    (None, field)
+                },
+            )
         })
         .collect::<Result<_, _>>()?;
-    struct_from_fields(pc, env, fields, scope)
+    if imports.is_empty() {
+        let this = struct_from_fields(pc, env, fields, scope)?;
+        notation::create_notations(pc, env, notations, &this)?;
+        return Ok(this);
+    }
+    let scope = SImports {
+        imports: imports
+            .iter()
+            .map(|(alias, import)| (alias.clone(), import.ptr_clone()))
+            .collect(),
+        inner: scope,
+    };
+    let this = struct_from_fields(pc, env, fields, Box::new(scope))?;
+    for (_, import) in imports {
+        // Imports are looked up starting from the struct they are in.
+        import.borrow_mut().scope = Box::new(SPlain(this.ptr_clone()));
+    }
+    notation::create_notations(pc, env, notations, &this)?;
+    Ok(this)
+}
+
+/// Creates an item for each import, paired with the name it is imported as.
+fn create_imports(
+    env: &mut Environment,
+    imports: Vec<&Node>,
+) -> Result<Vec<(String, ItemPtr)>, Diagnostic> {
+    let mut result = Vec::new();
+    for import in imports {
+        if import.is_malformed() {
+            // The syntax error has already been reported.
+            continue;
+        }
+        let path = import.children[1].as_node().as_path()?;
+        let alias = import.children[3].as_node().as_ident()?;
+        let item = Item::new_boxed(
+            DResolvable::new(RImport {
+                path: path.into_iter().map(String::from).collect(),
+                position: import.position,
+            })
+            .clone_into_box(),
+            Box::new(SRoot),
+        );
+        item.set_position(import.position);
+        env.add_import(item.ptr_clone());
+        result.push((alias.to_owned(), item));
+    }
+    Ok(result)
 }
 
 fn uncreate<'a>(
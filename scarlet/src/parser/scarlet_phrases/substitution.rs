@@ -33,8 +33,7 @@ fn create(
     let mut named_subs = Vec::new();
     let mut anonymous_subs = Vec::new();
     for sub in util::collect_comma_list(&node.children[2]) {
-        if sub.phrase == "is" {
-            let name = sub.children[0].as_node();
+        if let ("is", NodeChild::Node(name)) = (sub.phrase, &sub.children[0]) {
             named_subs.push((
                 name.position,
                 name.as_ident()?.to_owned(),
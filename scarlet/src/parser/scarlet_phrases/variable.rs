@@ -50,7 +50,11 @@ fn create(
             let con = arg.as_item(pc, env, SPlain(this.ptr_clone()))?;
             dependencies.push(con);
         } else if mode == 2 {
-            let text = arg.as_ident()?;
+            let text = if arg.phrase == "natural" {
+                arg.children[0].as_text()
+            } else {
+                arg.as_ident()?
+            };
             order.major_order = text
                 .parse()
                 .expect("TODO: Nice error, expected order to be a number between 0 and 255");
@@ -4,27 +4,41 @@ use super::{
     node::Node,
     phrase::{PhraseTable, Precedence},
 };
-use crate::{diagnostic::Diagnostic, parser::{node::NodeChild, diagnostics::incomplete_phrase_error}};
+use crate::{
+    diagnostic::Diagnostic,
+    parser::{diagnostics::incomplete_phrase_error, node::NodeChild},
+};
 
 #[derive(Debug)]
 pub struct Stack<'a>(pub Vec<Node<'a>>);
 
 impl<'a> Stack<'a> {
-    pub fn collapse(&mut self, pt: &PhraseTable) -> Result<(), Diagnostic> {
+    /// Moves the top node into the one below it. Incomplete phrases are
+    /// reported and padded with missing children so that parsing can
+    /// continue.
+    pub fn collapse(&mut self, pt: &PhraseTable, diagnostics: &mut Vec<Diagnostic>) {
         assert!(
             self.0.len() >= 2,
             "Internal error, tried to collapse with <2 items on the stack."
         );
-        let top = self.0.pop().unwrap();
-        if top.is_complete(pt) {
-            let next = self.0.len() - 1;
-            let next = &mut self.0[next];
-            assert!(next.is_waiting_for_node(pt));
-            next.children.push(NodeChild::Node(top));
-            Ok(())
-        } else {
-            Err(incomplete_phrase_error(&top))
+        let mut top = self.0.pop().unwrap();
+        if !top.is_complete(pt) {
+            diagnostics.push(incomplete_phrase_error(&top));
+            top.pad_with_missing(pt);
+        }
+        let next = self.0.len() - 1;
+        let next = &mut self.0[next];
+        if !next.is_waiting_for_node(pt) {
+            // The next phrase still needs some text before it can take a node.
+            diagnostics.push(incomplete_phrase_error(next));
+            while !next.is_complete(pt) && !next.is_waiting_for_node(pt) {
+                next.children.push(NodeChild::Missing);
+            }
+            if next.is_complete(pt) {
+                return;
+            }
         }
+        next.children.push(NodeChild::Node(top));
     }
 
     /// Collapses the stack until collapsing it more would result in the top
@@ -33,14 +47,39 @@ impl<'a> Stack<'a> {
         &mut self,
         pt: &PhraseTable,
         prec: Precedence,
-    ) -> Result<(), Diagnostic> {
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
         while self.0.len() >= 2
             && self.0[self.0.len() - 1].is_complete(pt)
             && pt[self.0[self.0.len() - 2].phrase].precedence <= prec
             && !self.0[self.0.len() - 2].will_wait_for_text(pt)
         {
-            self.collapse(pt)?;
+            self.collapse(pt, diagnostics);
         }
-        Ok(())
     }
+
+    /// Closes every phrase inside the innermost open bracket, padding
+    /// incomplete ones with missing children. This is used to get back on
+    /// track after a syntax error, so it doesn't report anything itself.
+    pub fn close_to_bracket(&mut self, pt: &PhraseTable) {
+        let mut ignored = Vec::new();
+        while let Some(top) = self.0.last_mut() {
+            if !top.is_complete(pt) {
+                if is_open_bracket(top, pt) {
+                    break;
+                }
+                top.pad_with_missing(pt);
+            } else if self.0.len() < 2 || is_open_bracket(&self.0[self.0.len() - 2], pt) {
+                break;
+            } else {
+                self.collapse(pt, &mut ignored);
+            }
+        }
+    }
+}
+
+/// Returns true if the node is waiting for its contents, which will be
+/// followed by some closing text.
+fn is_open_bracket(node: &Node, pt: &PhraseTable) -> bool {
+    !node.is_complete(pt) && node.is_waiting_for_node(pt) && node.will_wait_for_text(pt)
 }
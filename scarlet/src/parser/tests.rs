@@ -0,0 +1,244 @@
+#![cfg(test)]
+
+use super::{
+    format_file, lexer::Lexer, matchh::REGEX_RUNS, parse::parse, parse_tree, util, Node,
+    ParseContext,
+};
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    file_tree::{self, FileNode},
+    scope::SRoot,
+};
+
+fn with_parsed(code: &str, callback: impl FnOnce(&Node, Vec<Diagnostic>)) {
+    let file = FileNode {
+        self_content: code.to_owned(),
+        children: Vec::new(),
+    };
+    let pc = ParseContext::new();
+    let (root, diagnostics) = parse_tree(&file, &pc, &mut 0);
+    callback(&root, diagnostics)
+}
+
+fn labels<'a>(root: &Node<'a>) -> Vec<&'a str> {
+    util::collect_comma_list(&root.children[1])
+        .into_iter()
+        .filter(|field| field.phrase == "is")
+        .map(|field| field.children[0].as_node().as_ident().unwrap())
+        .collect()
+}
+
+#[test]
+fn valid_code_has_no_errors() {
+    with_parsed("a IS UNIQUE\nb IS { c IS a }", |root, diagnostics| {
+        assert!(diagnostics.is_empty());
+        assert_eq!(labels(root), vec!["a", "b"]);
+    });
+}
+
+#[test]
+fn reports_every_syntax_error() {
+    let code = "a IS $ junk\nb IS UNIQUE\nc IS { d IS }\ne IS AXIOM(";
+    with_parsed(code, |root, diagnostics| {
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(labels(root), vec!["a", "b", "c", "e"]);
+    });
+}
+
+#[test]
+fn skipped_brackets_do_not_resynchronize() {
+    with_parsed("a IS { b IS $ { c } }\nd IS UNIQUE", |root, diagnostics| {
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(labels(root), vec!["a", "d"]);
+    });
+}
+
+#[test]
+fn lexer_skips_whitespace_and_comments() {
+    let pc = ParseContext::new();
+    let code = "a IS # Comment\n #= Block #= nested =# =# b.VALUE $$ c";
+    let tokens = Lexer::new(code, &pc.token_regexes)
+        .map(|token| (token.text, token.recognized))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        ("a", true),
+        ("IS", true),
+        ("b", true),
+        (".VALUE", true),
+        ("$$", false),
+        ("c", true),
+    ];
+    assert_eq!(tokens, expected);
+}
+
+#[test]
+fn parsing_work_grows_linearly_with_the_input() {
+    let regex_runs = |definitions: usize| {
+        let code = (0..definitions)
+            .map(|index| format!("item_{} IS {{ a IS UNIQUE  b IS a.x(y IS a) }}\n", index))
+            .collect::<String>();
+        let pc = ParseContext::new();
+        REGEX_RUNS.with(|runs| runs.set(0));
+        let (root, diagnostics) = parse(&code, &pc, 0);
+        assert!(root.is_some() && diagnostics.is_empty());
+        REGEX_RUNS.with(|runs| runs.get())
+    };
+    let small = regex_runs(250);
+    let large = regex_runs(2_000);
+    // Every definition is lexed the same way, so eight times as many of them
+    // take eight times as many runs. Quadratic parsing would take 64 times as
+    // many.
+    assert_eq!(large, small * 8);
+}
+
+#[test]
+fn unclosed_block_comment_hides_the_rest_of_the_file() {
+    with_parsed("a IS UNIQUE\n#= b IS a(", |root, diagnostics| {
+        assert!(diagnostics.is_empty());
+        assert_eq!(labels(root), vec!["a"]);
+    });
+}
+
+#[test]
+fn non_ascii_input_is_reported() {
+    with_parsed("a IS é\nb IS UNIQUE #= é =#", |root, diagnostics| {
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(labels(root), vec!["a", "b"]);
+    });
+}
+
+const AND: &str = "NOTATION(\"{a} AND {b}\" 140 100 both(a b))\n";
+
+fn value<'a, 'n>(root: &'a Node<'n>, label: &str) -> &'a Node<'n> {
+    util::collect_comma_list(&root.children[1])
+        .into_iter()
+        .find(|field| {
+            field.phrase == "is" && field.children[0].as_node().as_ident().ok() == Some(label)
+        })
+        .map(|field| field.children[2].as_node())
+        .unwrap()
+}
+
+#[test]
+fn notations_are_replaced_with_what_they_mean() {
+    with_parsed(&format!("{}c IS x AND y", AND), |root, diagnostics| {
+        assert!(diagnostics.is_empty());
+        let c = value(root, "c");
+        assert_eq!(c.phrase, "substitution");
+        // What the notation means is referred to by the name of the
+        // notation, so the names in it don't depend on where it is used.
+        let base = c.children[0].as_node();
+        assert_eq!(base.as_ident().ok(), Some("notation \"{a} AND {b}\""));
+        // The whole thing came from where the notation was used.
+        assert_eq!(base.position, c.position);
+        let subs = util::collect_comma_list(&c.children[2])
+            .into_iter()
+            .map(|sub| {
+                let name = sub.children[0].as_node().as_ident().unwrap();
+                let value = sub.children[2].as_node().as_ident().unwrap();
+                (name, value)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(subs, vec![("a", "x"), ("b", "y")]);
+    });
+}
+
+#[test]
+fn parsing_a_notation_again_reuses_its_name() {
+    let name = || {
+        let mut name = None;
+        with_parsed(&format!("{}c IS x AND y", AND), |root, _| {
+            let base = value(root, "c").children[0].as_node();
+            name = base.as_ident().ok().map(str::as_ptr);
+        });
+        name.unwrap()
+    };
+    // The name lives forever, so it must not be made again each time a file
+    // is parsed.
+    assert_eq!(name(), name());
+}
+
+#[test]
+fn notations_can_only_be_used_below_where_they_are_declared() {
+    let file = |content: &str, children| FileNode {
+        self_content: content.to_owned(),
+        children,
+    };
+    let tree = file(
+        AND,
+        vec![
+            (
+                "first".to_owned(),
+                file(
+                    "NOTATION(\"{a} ~> {b}\" 140 100 b AND a)\nc IS x AND x ~> y",
+                    vec![],
+                ),
+            ),
+            ("second".to_owned(), file("d IS x ~> y", vec![])),
+        ],
+    );
+    let pc = ParseContext::new();
+    let (_, diagnostics) = parse_tree(&tree, &pc, &mut 0);
+    // Only the use in the second file is a mistake.
+    assert_eq!(diagnostics.len(), 1);
+}
+
+#[test]
+fn missing_phrases_become_stand_ins() {
+    with_parsed("a IS { b IS }\nc IS a.b", |root, diagnostics| {
+        assert_eq!(diagnostics.len(), 1);
+        let pc = ParseContext::new();
+        let mut env = Environment::new();
+        assert!(root.as_item(&pc, &mut env, SRoot).is_ok());
+    });
+}
+
+fn format(code: &str) -> String {
+    format_file(code, &ParseContext::new(), 1).unwrap()
+}
+
+#[test]
+fn formatting_keeps_comments() {
+    let code = "# Leading\na IS { b IS UNIQUE # Trailing\n\n\n#= Block =# c IS b }\n# End\n";
+    let expected =
+        "# Leading\na IS\n{\n   b IS UNIQUE # Trailing\n\n   #= Block =#\n   c IS b\n}\n# End\n";
+    assert_eq!(format(code), expected);
+}
+
+#[test]
+fn formatting_keeps_notations() {
+    let code = format!("{}c IS x   AND y", AND);
+    let expected = "NOTATION(\"{a} AND {b}\"  140  100  both(a b))\nc IS x AND y\n";
+    assert_eq!(format(&code), expected);
+}
+
+#[test]
+fn formatting_breaks_long_lists() {
+    let code =
+        "a IS VAR(DECISION(first_long_name second_long_name third_long_name fourth_long_name fifth_long_name))";
+    let expected = "a IS\nVAR(\n   DECISION(\n      first_long_name\n      second_long_name\n      third_long_name\n      fourth_long_name\n      fifth_long_name\n   )\n)\n";
+    assert_eq!(format(code), expected);
+}
+
+/// Formatting the standard library should not change what it means, and
+/// formatting it a second time should not change anything.
+#[test]
+fn formatting_full_is_idempotent() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("full");
+    let tree = file_tree::read_root(&root).unwrap();
+    let pc = ParseContext::new();
+    for (index, path) in tree.paths().into_iter().enumerate() {
+        let (_, original) = tree.get_file(index + 1);
+        let formatted = format_file(original, &pc, 1).unwrap();
+        assert_eq!(
+            format_file(&formatted, &pc, 1).unwrap(),
+            formatted,
+            "{}",
+            path
+        );
+        let (before, _) = parse(original, &pc, 1);
+        let (after, _) = parse(&formatted, &pc, 1);
+        assert_eq!(format!("{:?}", before), format!("{:?}", after), "{}", path);
+    }
+}
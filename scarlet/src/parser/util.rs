@@ -5,11 +5,13 @@ pub fn collect_comma_list<'a, 'n>(list: &'a NodeChild<'n>) -> Vec<&'a Node<'n>>
         if list.phrase == "multiple items" {
             assert_eq!(list.children.len(), 3);
             assert_eq!(list.children[1], NodeChild::Text(","));
-            [
-                collect_comma_list(&list.children[0]),
-                vec![list.children[2].as_node()],
-            ]
-            .concat()
+            let mut items = collect_comma_list(&list.children[0]);
+            // The last item is missing if the parser recovered from a
+            // trailing comma.
+            if let NodeChild::Node(last) = &list.children[2] {
+                items.push(last);
+            }
+            items
         } else {
             vec![list]
         }
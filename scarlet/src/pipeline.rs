@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+use typed_arena::Arena;
+
+use crate::{
+    cache::Cache,
+    diagnostic::Diagnostic,
+    environment::Environment,
+    file_tree::FileNode,
+    item::{
+        equality::clear_equality_cache, invariants::InvariantSetPtr, resolve::resolve_all,
+        structure::clear_structure_table, ItemPtr,
+    },
+    modules,
+    parser::ParseContext,
+    scope::SRoot,
+    util::fnv1a,
+};
+
+/// How far through the pipeline the source code made it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Stage {
+    Read,
+    Parsed,
+    Resolved,
+    Checked,
+    Justified,
+}
+
+/// Everything produced by running source code through the pipeline. The
+/// pipeline stops at the first stage which produces any diagnostics, except
+/// for syntax errors, after which the rest of the code is still resolved and
+/// checked.
+pub struct Analysis {
+    /// Has a child for each root that was loaded, starting with the one that
+    /// was asked for.
+    pub file_tree: FileNode,
+    /// The folder that paths in the file tree are relative to.
+    pub base: PathBuf,
+    pub env: Environment,
+    /// The root that was asked for, as opposed to libraries it imports.
+    pub root: Option<ItemPtr>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub stage: Stage,
+    /// Every invariant set in the roots as it was before justification, in
+    /// the order certificates refer to them.
+    pub invariant_sets: Vec<InvariantSetPtr>,
+}
+
+impl Analysis {
+    fn new(file_tree: FileNode, base: PathBuf) -> Self {
+        Self {
+            file_tree,
+            base,
+            env: Environment::new(),
+            root: None,
+            diagnostics: Vec::new(),
+            stage: Stage::Read,
+            invariant_sets: Vec::new(),
+        }
+    }
+
+    fn fail(mut self, mut diagnostics: Vec<Diagnostic>) -> Self {
+        self.diagnostics.append(&mut diagnostics);
+        self
+    }
+
+    /// Returns every item marked with SHOWN alongside how it should be shown.
+    /// Nothing is shown unless everything was justified.
+    pub fn shown(&mut self) -> Vec<(ItemPtr, String)> {
+        match (self.stage, &self.root) {
+            (Stage::Justified, Some(root)) => {
+                let root = root.ptr_clone();
+                self.env.show_all_requested(&root)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Identifies the source code that was loaded, so that anything worked
+    /// out from it can be matched back up with it later.
+    pub fn source_hash(&self) -> String {
+        let mut bytes = Vec::new();
+        for index in 1..=self.file_tree.paths().len() {
+            let (path, content) = self.file_tree.get_file(index);
+            bytes.extend_from_slice(path.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(content.as_bytes());
+            bytes.push(0);
+        }
+        format!("{:016x}", fnv1a(&bytes))
+    }
+}
+
+pub fn analyze(path: &Path) -> Analysis {
+    analyze_until(path, Stage::Justified)
+}
+
+/// Like analyze, but stops once the given stage has been reached.
+pub fn analyze_until(path: &Path, until: Stage) -> Analysis {
+    analyze_impl(path, until, false)
+}
+
+/// Like analyze, but reuses what earlier runs stored about files that haven't
+/// changed, and stores it again for the next run.
+pub fn analyze_cached(path: &Path) -> Analysis {
+    analyze_impl(path, Stage::Justified, true)
+}
+
+fn analyze_impl(path: &Path, until: Stage, use_cache: bool) -> Analysis {
+    // Results from earlier runs are about items which are about to be thrown
+    // away.
+    clear_equality_cache();
+    clear_structure_table();
+    let parse_context = ParseContext::new();
+    let arena = Arena::new();
+    let mut sources = if let Some(sources) = modules::load(path, &parse_context, &arena) {
+        sources
+    } else {
+        let empty = FileNode {
+            self_content: String::new(),
+            children: Vec::new(),
+        };
+        return Analysis::new(empty, PathBuf::new()).fail(vec![Diagnostic::new().with_text_error(
+            format!("Could not find any source code at {}", path.display()),
+        )]);
+    };
+    let file_tree = sources.files.clone();
+    let mut analysis = Analysis::new(file_tree.clone(), sources.base.clone());
+
+    let parsed_roots = std::mem::take(&mut sources.parsed);
+    let mut syntax_errors = std::mem::take(&mut sources.syntax_errors);
+    // Later stages still run on whatever could be parsed so that their errors
+    // get reported too, but the stage only advances if there were no errors.
+    let parsed_cleanly = syntax_errors.is_empty();
+    analysis.diagnostics.append(&mut syntax_errors);
+    let advance = |analysis: &mut Analysis, stage: Stage| {
+        if parsed_cleanly {
+            analysis.stage = stage;
+        }
+    };
+    advance(&mut analysis, Stage::Parsed);
+
+    let mut roots = Vec::new();
+    for (name, root) in parsed_roots {
+        let root = match root.as_item(&parse_context, &mut analysis.env, SRoot) {
+            Ok(root) => root,
+            Err(diagnostic) => return analysis.fail(vec![diagnostic]),
+        };
+        analysis.env.define_module(name.clone(), root.ptr_clone());
+        roots.push(root);
+    }
+    analysis.root = Some(roots[0].ptr_clone());
+    let cycles = sources.import_cycles();
+    if cycles.len() > 0 {
+        return analysis.fail(cycles);
+    }
+    let mut cache = if use_cache {
+        let cache = Cache::new(&analysis.base, &sources, &file_tree, &roots);
+        cache.reuse_links(&mut analysis.env);
+        Some(cache)
+    } else {
+        None
+    };
+    if let Err(diagnostics) = resolve_all(&mut analysis.env, &roots) {
+        return analysis.fail(diagnostics);
+    }
+    if let Some(cache) = &mut cache {
+        cache.reuse_invariants(&file_tree, &roots);
+    }
+    advance(&mut analysis, Stage::Resolved);
+    let mut check_errors = Vec::new();
+    for root in &roots {
+        if let Err(mut diagnostics) = root.check_all(&mut analysis.env) {
+            check_errors.append(&mut diagnostics);
+        }
+    }
+    if check_errors.len() > 0 {
+        return analysis.fail(check_errors);
+    }
+    advance(&mut analysis, Stage::Checked);
+    if !parsed_cleanly {
+        // Statements with missing parts can't be justified.
+        return analysis;
+    }
+    // Justification can change which items are reachable from the roots, so
+    // the sets certificates refer to are collected beforehand.
+    analysis.invariant_sets = analysis.env.invariant_set_table(&roots);
+    if until < Stage::Justified {
+        return analysis;
+    }
+    if let Some(cache) = &mut cache {
+        let reused = cache.reuse_steps(&analysis.env, &file_tree, &roots);
+        analysis.env.reuse_steps(&reused);
+    }
+
+    let mut hint_warnings = analysis.env.check_hints(&roots);
+    analysis.diagnostics.append(&mut hint_warnings);
+    let mut justify_errors = Vec::new();
+    for root in &roots {
+        if let Err(mut diagnostics) = analysis.env.justify_all(root) {
+            justify_errors.append(&mut diagnostics);
+        }
+    }
+    if justify_errors.len() > 0 {
+        return analysis.fail(justify_errors);
+    }
+    analysis.stage = Stage::Justified;
+
+    if let Some(cache) = &cache {
+        cache.store(&analysis.env);
+    }
+    analysis
+}
@@ -0,0 +1,215 @@
+//! The `repl` subcommand, which loads a root and then evaluates expressions
+//! typed in one line at a time, as if they were written inside the root.
+
+mod tests;
+
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostic::Diagnostic,
+    environment::Environment,
+    file_tree::FileNode,
+    item::{
+        definitions::structt::{DPopulatedStruct, SFieldAndRest},
+        normalize::{out_of_shown_steps, SHOWN_NORMALIZE_STEPS},
+        resolve::resolve_all,
+        ItemPtr,
+    },
+    output::{MessageFormat, Output, Verbosity},
+    parser::{self, NotationSyntax, ParseContext},
+    pipeline::{self, Stage},
+    scope::{SPlain, Scope},
+    util::PtrExtension,
+    CommandArgs,
+};
+
+/// The name of the file each line typed in is put in, so that diagnostics can
+/// point at it.
+const LINE_FILE: &str = "repl";
+
+struct Repl {
+    env: Environment,
+    root: ItemPtr,
+    /// The files that were loaded, followed by a file holding the line that
+    /// was typed in last.
+    file_tree: FileNode,
+    /// The index of the file holding the line.
+    line_file_index: usize,
+    /// The folder that paths in the file tree are relative to.
+    base: PathBuf,
+    /// Can parse the notations declared in the files that were loaded, so
+    /// that what is shown can be typed back in.
+    parse_context: ParseContext,
+    notations: Vec<NotationSyntax>,
+}
+
+/// Loads the root given in the arguments and evaluates lines from stdin until
+/// it runs out, returning the exit code the process should use.
+pub fn run(args: impl Iterator<Item = String>, output: Output) -> i32 {
+    let root = match CommandArgs::parse(args, &[], 1) {
+        Ok(args) => args.path(0),
+        Err(code) => return code,
+    };
+    let mut repl = match Repl::load(Path::new(&root), &output) {
+        Some(repl) => repl,
+        None => return 1,
+    };
+    output.progress(
+        Verbosity::Normal,
+        &format!(
+            "Loaded {}. Type an expression to evaluate it, or :quit to leave.",
+            root
+        ),
+    );
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if output.message_format == MessageFormat::Human {
+            print!("> ");
+            io::stdout().flush().unwrap();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        let line = line.trim();
+        if line == ":quit" || line == ":q" {
+            break;
+        } else if line.len() > 0 {
+            match repl.evaluate(line) {
+                Ok(shown) => output.report_shown(&shown, None),
+                Err(diagnostics) => {
+                    for diagnostic in &diagnostics {
+                        output.report(diagnostic, &repl.file_tree, &repl.base);
+                    }
+                }
+            }
+        }
+    }
+    0
+}
+
+impl Repl {
+    /// Prints any problems with the root and returns None if they mean
+    /// expressions can't be evaluated against it.
+    fn load(path: &Path, output: &Output) -> Option<Self> {
+        let mut analysis = pipeline::analyze(path);
+        for diagnostic in &analysis.diagnostics {
+            output.report(diagnostic, &analysis.file_tree, &analysis.base);
+        }
+        let root = match (analysis.stage, &analysis.root) {
+            (stage, Some(root)) if stage >= Stage::Checked => root.ptr_clone(),
+            _ => return None,
+        };
+        let notations = analysis.env.notation_syntaxes();
+        analysis.file_tree.children.push((
+            String::from(LINE_FILE),
+            FileNode {
+                self_content: String::new(),
+                children: Vec::new(),
+            },
+        ));
+        Some(Self {
+            parse_context: ParseContext::with_notations(notations.clone()),
+            notations,
+            env: analysis.env,
+            root,
+            line_file_index: analysis.file_tree.paths().len(),
+            file_tree: analysis.file_tree,
+            base: analysis.base,
+        })
+    }
+
+    /// Returns how the line should be shown, along with its dependencies and
+    /// invariants, or what went wrong. Lines marked NORMALIZED are normalized
+    /// first.
+    fn evaluate(&mut self, line: &str) -> Result<String, Vec<Diagnostic>> {
+        // Only the latest line needs to be in the tree, since its diagnostics
+        // are reported before the next one is typed.
+        let (_, line_file) = self.file_tree.children.last_mut().unwrap();
+        line_file.self_content = line.to_owned();
+        let (node, diagnostics) = parser::parse(line, &self.parse_context, self.line_file_index);
+        if diagnostics.len() > 0 {
+            return Err(diagnostics);
+        }
+        let node = match node {
+            Some(node) => node,
+            None => return Ok(String::new()),
+        };
+        let (node, diagnostics) = parser::desugar_expression(&node, &self.notations);
+        if diagnostics.len() > 0 {
+            return Err(diagnostics);
+        }
+        // Members of the root are visible as if the line was written among
+        // them.
+        let scope: Box<dyn Scope> = if self
+            .root
+            .downcast_definition::<DPopulatedStruct>()
+            .is_some()
+        {
+            Box::new(SFieldAndRest(self.root.ptr_clone()))
+        } else {
+            Box::new(SPlain(self.root.ptr_clone()))
+        };
+        let item = node
+            .as_item_dyn_scope(&self.parse_context, &mut self.env, scope)
+            .map_err(|diagnostic| vec![diagnostic])?;
+        resolve_all(&mut self.env, &[item.ptr_clone()])?;
+        if item.borrow().show_normalized {
+            let normalized = item.normalize(SHOWN_NORMALIZE_STEPS);
+            let mut result = self.describe(&normalized.item);
+            if normalized.out_of_steps {
+                result.push('\n');
+                result.push_str(&out_of_shown_steps());
+            }
+            return Ok(result);
+        }
+        Ok(self.describe(&item))
+    }
+
+    fn describe(&mut self, item: &ItemPtr) -> String {
+        let mut result = self.env.show(item.ptr_clone(), item.ptr_clone());
+        let dependencies = item.get_dependencies();
+        let dependency_vars = dependencies
+            .as_variables()
+            .map(|dep| dep.var.ptr_clone())
+            .collect::<Vec<_>>();
+        if dependency_vars.len() > 0 {
+            result.push_str("\nDepends on:");
+            for var in dependency_vars {
+                let shown = self.env.show_var(var, item.ptr_clone());
+                push_indented(&mut result, &shown);
+            }
+        } else if dependencies.error().is_some() {
+            result.push_str("\nDepends on something that is not resolved yet.");
+        } else {
+            result.push_str("\nDepends on nothing.");
+        }
+        match item.get_invariants() {
+            Ok(set) => {
+                let statements = set.borrow().statements().to_vec();
+                if statements.len() == 0 {
+                    result.push_str("\nHas no invariants.");
+                } else {
+                    result.push_str("\nHas these invariants:");
+                    for statement in statements {
+                        let shown = self.env.show(statement, item.ptr_clone());
+                        push_indented(&mut result, &shown);
+                    }
+                }
+            }
+            Err(_) => result.push_str("\nHas invariants which could not be found."),
+        }
+        result
+    }
+}
+
+fn push_indented(to: &mut String, text: &str) {
+    for line in text.lines() {
+        to.push_str("\n    ");
+        to.push_str(line);
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use super::Repl;
+use crate::output::{MessageFormat, Output, Verbosity};
+
+fn load(file: &str) -> Repl {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(file);
+    Repl::load(&root, &Output::new(Verbosity::Quiet, MessageFormat::Human)).unwrap()
+}
+
+fn repl() -> Repl {
+    load("shown.sr")
+}
+
+#[test]
+fn evaluates_expressions_in_the_root() {
+    let mut repl = repl();
+    let shown = repl.evaluate("a = b").unwrap();
+    assert!(shown.contains("Depends on nothing."), "{}", shown);
+    assert!(shown.contains("Has no invariants."), "{}", shown);
+}
+
+#[test]
+fn reports_unknown_names() {
+    let mut repl = repl();
+    let diagnostics = repl.evaluate("nothing_by_this_name").unwrap_err();
+    let message = diagnostics[0].format_colorful(&repl.file_tree);
+    assert!(message.contains("Cannot find what"), "{}", message);
+    assert!(message.contains("nothing_by_this_name"), "{}", message);
+    // Earlier lines don't stop later ones from working.
+    let shown = repl.evaluate("a").unwrap();
+    assert!(shown.starts_with("a"), "{}", shown);
+}
+
+#[test]
+fn lines_share_one_file() {
+    let mut repl = repl();
+    let files = repl.file_tree.paths().len();
+    for _ in 0..3 {
+        repl.evaluate("a").unwrap();
+    }
+    assert_eq!(repl.file_tree.paths().len(), files);
+}
+
+#[test]
+fn notations_from_the_root_can_be_typed() {
+    let mut repl = load("notation.sr");
+    let shown = repl.evaluate("a AND b").unwrap();
+    assert!(shown.starts_with("a AND b"), "{}", shown);
+}
@@ -30,6 +30,8 @@ pub trait Scope: Debug {
     }
 
     fn local_lookup_ident(&self, ident: &str) -> LookupIdentResult;
+    /// Returns every identifier that local_lookup_ident can find.
+    fn local_idents(&self) -> Vec<String>;
     fn local_reverse_lookup_ident(
         &self,
         env: &mut Environment,
@@ -48,6 +50,20 @@ pub trait Scope: Debug {
         }
     }
 
+    /// Returns every identifier visible from this scope, innermost first and
+    /// without duplicates.
+    fn idents(&self) -> Vec<String> {
+        let mut result = self.local_idents();
+        if let Some(parent) = self.parent() {
+            for ident in parent.borrow().scope.idents() {
+                if !result.contains(&ident) {
+                    result.push(ident);
+                }
+            }
+        }
+        result
+    }
+
     fn reverse_lookup_ident(
         &self,
         env: &mut Environment,
@@ -90,6 +106,10 @@ impl Scope for SPlain {
         Ok(None)
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         _env: &mut Environment,
@@ -119,6 +139,10 @@ impl Scope for SRoot {
         Ok(None)
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         _env: &mut Environment,
@@ -152,6 +176,10 @@ impl Scope for SPlaceholder {
         unreachable!()
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        unreachable!()
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         _env: &mut Environment,
@@ -185,6 +213,10 @@ impl<Base: Scope + Clone + 'static> Scope for SWithParent<Base> {
         self.0.local_lookup_ident(ident)
     }
 
+    fn local_idents(&self) -> Vec<String> {
+        self.0.local_idents()
+    }
+
     fn local_reverse_lookup_ident(
         &self,
         env: &mut Environment,
@@ -201,3 +233,59 @@ impl<Base: Scope + Clone + 'static> Scope for SWithParent<Base> {
         Some(self.1.ptr_clone())
     }
 }
+
+/// Makes the names given by IMPORT statements, and what the notations declared
+/// in a file mean, visible inside a struct without making them members of it.
+#[derive(Debug)]
+pub struct SImports {
+    pub imports: Vec<(String, ItemPtr)>,
+    pub inner: Box<dyn Scope>,
+}
+
+impl Scope for SImports {
+    fn dyn_clone(&self) -> Box<dyn Scope> {
+        Box::new(Self {
+            imports: self
+                .imports
+                .iter()
+                .map(|(alias, import)| (alias.clone(), import.ptr_clone()))
+                .collect(),
+            inner: self.inner.dyn_clone(),
+        })
+    }
+
+    fn local_lookup_ident(&self, ident: &str) -> LookupIdentResult {
+        for (alias, import) in &self.imports {
+            if alias == ident {
+                return Ok(Some(import.ptr_clone()));
+            }
+        }
+        self.inner.local_lookup_ident(ident)
+    }
+
+    fn local_idents(&self) -> Vec<String> {
+        let mut result: Vec<_> = self
+            .imports
+            .iter()
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        result.append(&mut self.inner.local_idents());
+        result
+    }
+
+    fn local_reverse_lookup_ident(
+        &self,
+        env: &mut Environment,
+        value: ItemPtr,
+    ) -> ReverseLookupIdentResult {
+        self.inner.local_reverse_lookup_ident(env, value)
+    }
+
+    fn local_get_invariant_sets(&self) -> Vec<InvariantSetPtr> {
+        self.inner.local_get_invariant_sets()
+    }
+
+    fn parent(&self) -> Option<ItemPtr> {
+        self.inner.parent()
+    }
+}
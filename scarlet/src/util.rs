@@ -62,6 +62,38 @@ impl<OriginalKey: Eq + Hash, IsomorphicKey: Isomorphism<OriginalKey>, Result>
     }
 }
 
+/// The number of single-character insertions, deletions or substitutions
+/// required to turn one string into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+            let cost = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(row[j] + 1);
+            row.push(cost);
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+/// A 64-bit FNV-1a hash, which unlike DefaultHasher is guaranteed to give the
+/// same result on every platform and every version of Rust.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET_BASIS;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
 pub fn rcrc<T>(value: T) -> Rc<RefCell<T>> {
     Rc::new(RefCell::new(value))
 }
@@ -0,0 +1,109 @@
+//! The `--watch` option, which checks the source code again whenever one of
+//! the roots it was read from changes. Files are polled rather than watched
+//! through the operating system, which is plenty for folders of this size.
+
+mod tests;
+
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::pipeline::Analysis;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long the files must stay the same before checking them again, so that
+/// saving several files at once only causes one check.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The modification time and length of every source file being watched.
+type Snapshot = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// Calls check, then calls it again each time the source code it read
+/// changes. Never returns.
+pub fn run(path: &Path, mut check: impl FnMut() -> Analysis) -> ! {
+    let mut watched = vec![path.to_owned()];
+    loop {
+        clear_screen();
+        // Taken before checking so that changes made while checking still
+        // cause another check.
+        let before = snapshot(&watched);
+        let analysis = check();
+        let loaded = watched_paths(path, &analysis);
+        // Which roots get loaded is only known after checking. If that
+        // changed which paths are watched, the snapshots can't be compared, so
+        // the baseline is taken again over the new paths.
+        let mut last = if loaded == watched {
+            before
+        } else {
+            snapshot(&loaded)
+        };
+        watched = loaded;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = snapshot(&watched);
+            if current != last {
+                last = current;
+                break;
+            }
+        }
+        loop {
+            thread::sleep(DEBOUNCE);
+            let current = snapshot(&watched);
+            if current == last {
+                break;
+            }
+            last = current;
+        }
+    }
+}
+
+fn clear_screen() {
+    if io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+}
+
+/// Every root that was loaded, whether it is a single file or a folder.
+/// Both are watched so that a root changing from one to the other is noticed.
+fn watched_paths(path: &Path, analysis: &Analysis) -> Vec<PathBuf> {
+    if analysis.file_tree.children.len() == 0 {
+        return vec![path.to_owned()];
+    }
+    let mut paths = Vec::new();
+    for (name, _) in &analysis.file_tree.children {
+        paths.push(analysis.base.join(name));
+        paths.push(analysis.base.join(format!("{}.sr", name)));
+    }
+    paths
+}
+
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    let mut result = Vec::new();
+    for path in paths {
+        add_to_snapshot(path, &mut result);
+    }
+    result
+}
+
+fn add_to_snapshot(path: &Path, into: &mut Snapshot) {
+    if path.is_dir() {
+        let mut entries = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+        entries.sort();
+        for entry in entries {
+            add_to_snapshot(&entry, into);
+        }
+    } else if path.extension().map(|ext| ext == "sr").unwrap_or(false) {
+        if let Ok(metadata) = fs::metadata(path) {
+            into.push((path.to_owned(), metadata.modified().ok(), metadata.len()));
+        }
+    }
+}
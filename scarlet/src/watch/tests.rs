@@ -0,0 +1,26 @@
+#![cfg(test)]
+
+use std::fs;
+
+use super::snapshot;
+use crate::item::test_util::TempFolder;
+
+#[test]
+fn snapshot_notices_source_changes_only() {
+    let folder = TempFolder::new("watch");
+    fs::create_dir(folder.join("root")).unwrap();
+    fs::write(folder.join("root").join("a.sr"), "a IS UNIQUE").unwrap();
+    let watched = vec![folder.join("root"), folder.join("root.sr")];
+
+    let first = snapshot(&watched);
+    assert_eq!(first.len(), 1);
+
+    fs::write(folder.join("root").join("notes.txt"), "not source code").unwrap();
+    assert_eq!(snapshot(&watched), first);
+
+    fs::write(folder.join("root").join("a.sr"), "a IS UNIQUE\nb IS a").unwrap();
+    assert_ne!(snapshot(&watched), first);
+
+    fs::write(folder.join("root.sr"), "").unwrap();
+    assert_eq!(snapshot(&watched).len(), 2);
+}